@@ -2,28 +2,21 @@ export async function ensureJpegBytes(file) {
     try {
         console.log("[Start] ensureJpegBytes for:", file.name, file.type);
 
-        const isHeic = (file.type === "image/heic" || file.name.toLowerCase().endsWith(".heic"));
+        const name = file.name.toLowerCase();
+        const isHeic = (file.type === "image/heic" || file.type === "image/heif" || name.endsWith(".heic") || name.endsWith(".heif"));
+
+        // --- 1. 元ファイルのバイト列（Exifの読み書きはRust側で行う） ---
+        const original = new Uint8Array(await file.arrayBuffer());
 
         if (!isHeic) {
-            console.log("[Info] JPEG detected, skipping conversion.");
-            const buffer = await file.arrayBuffer();
+            console.log("[Info] Non-HEIC file detected, skipping conversion.");
             return {
-                jpeg: new Uint8Array(buffer),
-                exif: {}
+                jpeg: original,
+                original: original
             }
         }
 
-        // --- 1. Exifメタ全体取得 ---
-        console.time("exifr.parse");
-        const exifObj = await exifr.parse(file, { translateValues: false });
-        console.timeEnd("exifr.parse");
-        if (!exifObj) {
-            console.warn("[Warn] Exif data could not be parsed from HEIC.");
-        } else {
-            console.log("[Info] Exif keys:", Object.keys(exifObj));
-        }
-
-        // --- 3. HEIC → JPEG変換 ---
+        // --- 2. HEIC → JPEG変換（プレビュー表示用） ---
         console.time("heic2any");
         const jpegBlob = await heic2any({ blob: file, toType: "image/jpeg" });
         console.timeEnd("heic2any");
@@ -41,36 +34,17 @@ export async function ensureJpegBytes(file) {
         }*/
         return {
             jpeg: finalBytes,
-            exif: sanitizeExif(exifObj),
+            original: original,
         }
     } catch (err) {
         console.error("[Error] Failed in ensureJpegBytes:", err);
         return {
             jpeg: new Uint8Array([]),
-            exif: {}
+            original: new Uint8Array([])
         }
     }
 }
 
-function sanitizeExif(obj) {
-    const replacer = (_, value) => {
-        if (
-            value instanceof Uint8Array ||
-            value instanceof Uint16Array ||
-            value instanceof Uint32Array ||
-            value instanceof Int8Array ||
-            value instanceof Int16Array ||
-            value instanceof Int32Array ||
-            value instanceof Float32Array ||
-            value instanceof Float64Array
-        ) {
-            return Array.from(value);
-        }
-        return value;
-    };
-    return JSON.parse(JSON.stringify(obj, replacer));
-}
-
 async function rotateAndConvertToJpeg(blob, orientation) {
     const imageBitmap = await createImageBitmap(blob);
     const canvas = document.createElement("canvas");
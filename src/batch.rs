@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDateTime};
+use little_exif::exif_tag::ExifTag;
+
+use crate::exif::{ExifEditData, ExifTime};
+use crate::exif::clock::{format_shift, ClockShift, GPS_OFFSET_MISSING};
+use crate::exif::tag_value::{empty_tag, parse_tag, tag_name, value_string, TagValueError};
+use crate::file_type::FileType;
+use crate::geo::fuzz::LocationFuzz;
+use crate::geo::geotag::Geotag;
+use crate::zip::{ZipError, ZipWriter};
+
+#[derive(Clone, PartialEq)]
+pub enum TagOperation {
+    Set(ExifTag),
+    Delete(ExifTag),
+    Shift(ExifTime, Duration),
+    Clock(ClockShift),
+    Geotag(Geotag),
+    Fuzz(LocationFuzz),
+}
+
+impl TagOperation {
+    pub fn set(name: &str, value: &str) -> Result<Self, TagValueError> {
+        parse_tag(name, value).map(TagOperation::Set)
+    }
+
+    pub fn delete(name: &str) -> Result<Self, TagValueError> {
+        empty_tag(name).map(TagOperation::Delete).ok_or_else(|| TagValueError::UnknownTag(name.to_string()))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            TagOperation::Set(tag) => format!("設定: {} = {}", tag_name(tag), value_string(tag)),
+            TagOperation::Delete(tag) => format!("削除: {}", tag_name(tag)),
+            TagOperation::Shift(time, delta) => format!("時刻シフト: {} {}", time.name(), format_shift(delta)),
+            TagOperation::Clock(shift) => shift.describe(),
+            TagOperation::Geotag(geotag) => geotag.describe(),
+            TagOperation::Fuzz(fuzz) => fuzz.describe(),
+        }
+    }
+
+    // ジオタグは対応する地点が見つからなければ失敗とする。
+    // GPS日時を計算し直せなかったときは、日時の補正は残したまま警告を返す
+    pub fn apply(&self, eed: &mut ExifEditData) -> Result<Option<String>, String> {
+        match self {
+            TagOperation::Set(tag) => eed.update_tag(tag.clone()),
+            TagOperation::Delete(tag) => eed.delete_tag(tag.clone()),
+            TagOperation::Shift(time, delta) => eed.shift_time(*time, *delta),
+            TagOperation::Clock(shift) => {
+                if !shift.apply(eed) {
+                    return Ok(Some(GPS_OFFSET_MISSING.to_string()));
+                }
+            }
+            TagOperation::Geotag(geotag) => { geotag.apply(eed)?; }
+            // 位置情報のないファイルはそのままにする
+            TagOperation::Fuzz(fuzz) => { fuzz.apply(eed); }
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct BatchFailure {
+    pub name: String,
+    pub reason: String,
+}
+
+impl BatchFailure {
+    pub fn new(name: &str, reason: &str) -> Self {
+        Self { name: name.to_string(), reason: reason.to_string() }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct BatchFile {
+    pub name: String,
+    pub file_type: FileType,
+    pub bytes: Vec<u8>,
+    pub exif: ExifEditData,
+}
+
+impl BatchFile {
+    pub fn load(name: &str, bytes: Vec<u8>) -> Result<Self, BatchFailure> {
+        let file_type = FileType::detect(&bytes)
+            .ok_or_else(|| BatchFailure::new(name, "対応していないファイル形式です"))?;
+        let metadata = file_type.read_metadata(&bytes)
+            .map_err(|e| BatchFailure::new(name, &format!("メタデータを読み込めません: {}", e)))?;
+        Ok(Self { name: name.to_string(), file_type, bytes, exif: ExifEditData::new(&metadata) })
+    }
+
+    // 書き出したバイト列と、適用しきれなかった編集についての警告を返す
+    pub fn process(&self, operations: &[TagOperation]) -> Result<(Vec<u8>, Vec<String>), BatchFailure> {
+        let mut eed = self.exif.clone();
+        let mut warnings = Vec::new();
+        for op in operations.iter() {
+            if let Some(warning) = op.apply(&mut eed).map_err(|e| BatchFailure::new(&self.name, &e))? {
+                warnings.push(warning);
+            }
+        }
+        let mut bytes = self.file_type.write_metadata(&self.bytes, &eed.metadata)
+            .map_err(|e| BatchFailure::new(&self.name, &format!("書き込みに失敗しました: {}", e)))?;
+        // XMPやIPTCにも同じ位置が残っていることが多いため、合わせてぼかす
+        for op in operations.iter() {
+            if let TagOperation::Fuzz(fuzz) = op {
+                bytes = fuzz.apply_embedded(self.file_type, bytes)
+                    .map_err(|e| BatchFailure::new(&self.name, &format!("書き込みに失敗しました: {}", e)))?;
+            }
+        }
+        Ok((bytes, warnings))
+    }
+
+    pub fn output_name(&self) -> String {
+        let stem = match self.name.rsplit_once('.') {
+            Some((stem, _)) => stem,
+            None => &self.name,
+        };
+        format!("{}.{}", stem, self.file_type.extension())
+    }
+}
+
+pub struct BatchOutput {
+    pub zip: Vec<u8>,
+    pub succeeded: usize,
+    pub failures: Vec<BatchFailure>,
+    // ZIPには含めたが、一部の編集を適用できなかったファイル
+    pub warnings: Vec<BatchFailure>,
+}
+
+// 失敗したファイルや、ZIPに入りきらないファイルはZIPに含めず、理由を一覧として返す
+pub fn build_zip(files: &[BatchFile], operations: &[TagOperation], modified: NaiveDateTime) -> Result<BatchOutput, ZipError> {
+    let mut writer = ZipWriter::new(modified);
+    let mut used_names = HashSet::new();
+    let mut succeeded = 0;
+    let mut failures = Vec::new();
+    let mut warnings = Vec::new();
+
+    for file in files.iter() {
+        match file.process(operations) {
+            Ok((bytes, file_warnings)) => {
+                let name = unique_name(&file.output_name(), &mut used_names);
+                match writer.add_file(&name, &bytes) {
+                    Ok(()) => {
+                        succeeded += 1;
+                        warnings.extend(file_warnings.iter().map(|w| BatchFailure::new(&file.name, w)));
+                    }
+                    Err(e) => failures.push(BatchFailure::new(&file.name, &e.to_string())),
+                }
+            }
+            Err(failure) => failures.push(failure),
+        }
+    }
+    Ok(BatchOutput { zip: writer.finish()?, succeeded, failures, warnings })
+}
+
+fn unique_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    let mut candidate = name.to_string();
+    let mut i = 1;
+    while used_names.contains(&candidate) {
+        candidate = match name.rsplit_once('.') {
+            Some((stem, ext)) => format!("{}_{}.{}", stem, i, ext),
+            None => format!("{}_{}", name, i),
+        };
+        i += 1;
+    }
+    used_names.insert(candidate.clone());
+    candidate
+}
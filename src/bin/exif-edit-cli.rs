@@ -0,0 +1,340 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::rc::Rc;
+
+use chrono::{Duration, Local};
+use exif_edit::batch::TagOperation;
+use exif_edit::exif::{group_name, ExifEditData};
+use exif_edit::exif::clock::{parse_shift, ClockShift};
+use exif_edit::exif::exif_capture::TimeOffset;
+use exif_edit::exif::export::ExportFormat;
+use exif_edit::exif::tag_value::{empty_tag, tag_name, value_string};
+use exif_edit::file_type::FileType;
+use exif_edit::geo::fuzz::LocationFuzz;
+use exif_edit::geo::geotag::{Geotag, GeotagOptions};
+use exif_edit::geo::track::Track;
+use exif_edit::geo::waypoint::{Waypoint, WaypointFormat};
+use exif_edit::iptc;
+use exif_edit::xmp::container as xmp_container;
+
+const USAGE: &str = "使い方:
+  exif-edit-cli show [--format json|csv|exiftool] FILE...
+  exif-edit-cli set TAG=VALUE... FILE... (--in-place | -o OUTPUT)
+  exif-edit-cli delete TAG... FILE... (--in-place | -o OUTPUT)
+  exif-edit-cli strip FILE... (--in-place | -o OUTPUT)
+  exif-edit-cli shift (DELTA | --timezone FROM TO) [--offset OFFSET] [--gps] FILE... (--in-place | -o OUTPUT)
+  exif-edit-cli waypoints [--format gpx|kml|geojson] FILE...
+  exif-edit-cli geotag TRACK [--max-gap MINUTES] [--clock-offset DELTA] [--default-offset OFFSET] FILE... (--in-place | -o OUTPUT)
+  exif-edit-cli fuzz (--grid METERS | --radius METERS) [--seed N] FILE... (--in-place | -o OUTPUT)
+
+strip はExifに加えて、JPEG・PNG・WebPのXMPとJPEGのIPTCも削除します。
+
+shift は DateTimeOriginal, CreateDate, ModifyDate をまとめてずらします。
+  DELTA は +1h23m5s や -01:30:00 のように指定します。
+  --timezone +00:00 +09:00 のように指定すると、その差だけずらし OffsetTime* も書き換えます。
+  --gps を付けると、補正後の日時から GPSDateStamp / GPSTimeStamp を UTC で計算し直します。
+
+geotag は GPX, KML, GeoJSON のトラック (TRACK) から撮影時刻の地点を探し、位置情報を書き込みます。
+  --max-gap はトラックの地点の間隔の上限（分、既定は10）、--clock-offset はカメラの時計の遅れです。
+  OffsetTimeOriginal のない画像は --default-offset（既定は +00:00）のタイムゾーンとみなします。
+
+fuzz は緯度・経度の精度を下げ、高度・向き・GPSDest* を削除して GPSHPositioningError に精度を記録します。
+  XMPの位置も同じようにぼかし、XMP・IPTCの細かい地名（Location, Sub-location）は削除します。
+  --grid は METERS 間隔の格子に丸め、--radius は半径 METERS 以内でランダムにずらします。
+
+対応形式: JPEG, HEIC, PNG, WebP, TIFF（先頭のマジックバイトで判定）
+-o は入力ファイルが1つのときのみ指定できます。";
+
+#[derive(PartialEq)]
+enum Command {
+    Show,
+    Set,
+    Delete,
+    Strip,
+    Shift,
+    Geotag,
+    Fuzz,
+    Waypoints,
+}
+
+enum Output {
+    InPlace,
+    Path(PathBuf),
+}
+
+struct Args {
+    command: Command,
+    operations: Vec<TagOperation>,
+    files: Vec<PathBuf>,
+    output: Option<Output>,
+    format: Option<ExportFormat>,
+    waypoint_format: WaypointFormat,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let command = match args.first().map(|s| s.as_str()) {
+        Some("show") => Command::Show,
+        Some("set") => Command::Set,
+        Some("delete") => Command::Delete,
+        Some("strip") => Command::Strip,
+        Some("shift") => Command::Shift,
+        Some("geotag") => Command::Geotag,
+        Some("fuzz") => Command::Fuzz,
+        Some("waypoints") => Command::Waypoints,
+        Some(s) => return Err(format!("不明なコマンドです: {}", s)),
+        None => return Err("コマンドを指定してください".to_string()),
+    };
+
+    let mut operations = Vec::new();
+    let mut files = Vec::new();
+    let mut output = None;
+    let mut format = None;
+    let mut waypoint_format = WaypointFormat::Gpx;
+    let mut shift: Option<ClockShift> = None;
+    let mut offset = None;
+    let mut gps = false;
+    let mut track = None;
+    let mut fuzz: Option<LocationFuzz> = None;
+    let mut seed = None;
+    let mut geotag_options = GeotagOptions {
+        max_gap: Duration::minutes(10),
+        clock_offset: Duration::zero(),
+        default_offset: TimeOffset { sign: true, hour: 0, minute: 0 },
+    };
+    let offset_arg = |s: Option<&String>| s.and_then(|s| TimeOffset::from_str(s))
+        .ok_or_else(|| "オフセットは +09:00 のように指定してください".to_string());
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--in-place" | "-i" => output = Some(Output::InPlace),
+            "-o" | "--output" => match iter.next() {
+                Some(path) => output = Some(Output::Path(PathBuf::from(path))),
+                None => return Err("-o の後に出力先を指定してください".to_string()),
+            },
+            "--format" if command == Command::Waypoints => waypoint_format = match iter.next().map(|s| s.as_str()) {
+                Some("gpx") => WaypointFormat::Gpx,
+                Some("kml") => WaypointFormat::Kml,
+                Some("geojson") => WaypointFormat::GeoJson,
+                _ => return Err("--format には gpx, kml, geojson のいずれかを指定してください".to_string()),
+            },
+            "--format" => format = match iter.next().map(|s| s.as_str()) {
+                Some("json") => Some(ExportFormat::Json),
+                Some("csv") => Some(ExportFormat::Csv),
+                Some("exiftool") => Some(ExportFormat::Exiftool),
+                _ => return Err("--format には json, csv, exiftool のいずれかを指定してください".to_string()),
+            },
+            "--offset" if command == Command::Shift => offset = Some(offset_arg(iter.next())?),
+            "--gps" if command == Command::Shift => gps = true,
+            "--timezone" if command == Command::Shift => {
+                let from = offset_arg(iter.next())?;
+                let to = offset_arg(iter.next())?;
+                shift = Some(ClockShift::between(&from, &to));
+            }
+            "--max-gap" if command == Command::Geotag => {
+                geotag_options.max_gap = iter.next().and_then(|s| s.parse::<i64>().ok()).filter(|m| *m >= 0)
+                    .and_then(Duration::try_minutes)
+                    .ok_or_else(|| "--max-gap には0以上の分数を指定してください".to_string())?;
+            }
+            "--clock-offset" if command == Command::Geotag => {
+                geotag_options.clock_offset = iter.next().and_then(|s| parse_shift(s))
+                    .ok_or_else(|| "--clock-offset には +1m30s のようにずらす時間を指定してください".to_string())?;
+            }
+            "--default-offset" if command == Command::Geotag => geotag_options.default_offset = offset_arg(iter.next())?,
+            "--grid" | "--radius" if command == Command::Fuzz => {
+                let meters = iter.next().and_then(|s| s.parse::<f64>().ok()).filter(|m| *m > 0.0)
+                    .ok_or_else(|| format!("{} にはメートル単位の正の数を指定してください", arg))?;
+                fuzz = Some(if arg == "--grid" { LocationFuzz::grid(meters) } else { LocationFuzz::random(meters, 0) });
+            }
+            "--seed" if command == Command::Fuzz => {
+                seed = Some(iter.next().and_then(|s| s.parse::<u64>().ok())
+                    .ok_or_else(|| "--seed には整数を指定してください".to_string())?);
+            }
+            _ => match command {
+                Command::Geotag if track.is_none() => {
+                    let text = fs::read_to_string(arg).map_err(|e| format!("{}を読み込めません: {}", arg, e))?;
+                    track = Some(Track::parse(&text).map_err(|e| format!("{}: {}", arg, e))?);
+                }
+                Command::Shift if shift.is_none() && parse_shift(arg).is_some() => {
+                    shift = parse_shift(arg).map(ClockShift::new);
+                }
+                Command::Set if arg.contains('=') => {
+                    let (name, value) = arg.split_once('=').unwrap();
+                    operations.push(TagOperation::set(name.trim(), value).map_err(|e| e.to_string())?);
+                }
+                Command::Delete if empty_tag(arg).is_some() => {
+                    operations.push(TagOperation::delete(arg).map_err(|e| e.to_string())?);
+                }
+                _ => files.push(PathBuf::from(arg)),
+            },
+        }
+    }
+
+    if files.is_empty() {
+        return Err("ファイルを指定してください".to_string());
+    }
+    if command == Command::Shift {
+        let mut shift = shift.ok_or_else(|| "ずらす時間か --timezone を指定してください".to_string())?;
+        if offset.is_some() {
+            shift.offset = offset;
+        }
+        shift.update_gps = gps;
+        operations.push(TagOperation::Clock(shift));
+    }
+    if command == Command::Geotag {
+        let track = track.ok_or_else(|| "トラックのファイルを指定してください".to_string())?;
+        operations.push(TagOperation::Geotag(Geotag { track: Rc::new(track), options: geotag_options }));
+    }
+    if command == Command::Fuzz {
+        let mut fuzz = fuzz.ok_or_else(|| "--grid か --radius を指定してください".to_string())?;
+        fuzz.seed = seed.unwrap_or_else(|| Local::now().timestamp_nanos_opt().unwrap_or_default() as u64);
+        operations.push(TagOperation::Fuzz(fuzz));
+    }
+    match command {
+        Command::Show | Command::Waypoints => if output.is_some() {
+            return Err("show と waypoints では出力先を指定できません".to_string());
+        },
+        _ => {
+            if format.is_some() {
+                return Err("--format は show と waypoints でのみ指定できます".to_string());
+            }
+            if (command == Command::Set || command == Command::Delete) && operations.is_empty() {
+                return Err("編集するタグを指定してください".to_string());
+            }
+            match output {
+                None => return Err("--in-place か -o のどちらかを指定してください".to_string()),
+                Some(Output::Path(_)) if files.len() > 1 => {
+                    return Err("-o は入力ファイルが1つのときのみ指定できます".to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(Args { command, operations, files, output, format, waypoint_format })
+}
+
+fn load(path: &Path) -> Result<(FileType, Vec<u8>, ExifEditData), String> {
+    let bytes = fs::read(path).map_err(|e| format!("読み込みに失敗しました: {}", e))?;
+    let file_type = FileType::detect(&bytes).ok_or_else(|| "対応していないファイル形式です".to_string())?;
+    let metadata = file_type.read_metadata(&bytes)
+        .map_err(|e| format!("メタデータを読み込めません: {}", e))?;
+    Ok((file_type, bytes, ExifEditData::new(&metadata)))
+}
+
+fn show(path: &Path) -> Result<(), String> {
+    let (file_type, _, eed) = load(path)?;
+    println!("== {} ({})", path.display(), file_type.label());
+    for ifd in eed.metadata.get_ifds() {
+        for tag in ifd.get_tags() {
+            println!("[{}] {} = {}", group_name(tag.get_group()), tag_name(tag), value_string(tag));
+        }
+    }
+    Ok(())
+}
+
+// 全ファイルをまとめて1つの文書として標準出力に書き出す
+fn export(files: &[PathBuf], format: ExportFormat) -> ExitCode {
+    let mut failed = false;
+    let mut loaded = Vec::new();
+    for path in files.iter() {
+        match load(path) {
+            Ok((_, _, eed)) => loaded.push((path.display().to_string(), eed)),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                failed = true;
+            }
+        }
+    }
+    let refs = loaded.iter().map(|(name, eed)| (name.as_str(), eed)).collect::<Vec<(&str, &ExifEditData)>>();
+    print!("{}", format.export(&refs));
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+// 位置情報のないファイルは飛ばし、標準エラー出力で知らせる
+fn waypoints(files: &[PathBuf], format: WaypointFormat) -> ExitCode {
+    let mut failed = false;
+    let mut waypoints: Vec<Waypoint> = Vec::new();
+    for path in files.iter() {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match load(path) {
+            Ok((_, _, eed)) => match eed.waypoint(&name) {
+                Some(waypoint) => waypoints.push(waypoint),
+                None => eprintln!("{}: 位置情報がありません", path.display()),
+            },
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                failed = true;
+            }
+        }
+    }
+    print!("{}", format.export(&waypoints));
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+fn edit(path: &Path, args: &Args) -> Result<(), String> {
+    let (file_type, bytes, mut eed) = load(path)?;
+    if args.command == Command::Strip {
+        eed.delete_all();
+    }
+    for op in args.operations.iter() {
+        if let Some(warning) = op.apply(&mut eed)? {
+            eprintln!("{}: {}", path.display(), warning);
+        }
+    }
+    let mut bytes = file_type.write_metadata(&bytes, &eed.metadata)
+        .map_err(|e| format!("書き込みに失敗しました: {}", e))?;
+    if args.command == Command::Strip {
+        // XMPやIPTCにも同じ情報が残っていることが多いため、合わせて削除する
+        bytes = xmp_container::write(file_type, &bytes, None)
+            .and_then(|bytes| iptc::write(file_type, &bytes, None))
+            .map_err(|e| format!("書き込みに失敗しました: {}", e))?;
+    }
+    for op in args.operations.iter() {
+        if let TagOperation::Fuzz(fuzz) = op {
+            bytes = fuzz.apply_embedded(file_type, bytes)
+                .map_err(|e| format!("書き込みに失敗しました: {}", e))?;
+        }
+    }
+    let dest = match &args.output {
+        Some(Output::Path(dest)) => dest.as_path(),
+        _ => path,
+    };
+    fs::write(dest, bytes).map_err(|e| format!("{}に保存できません: {}", dest.display(), e))
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if args.is_empty() || args[0] == "-h" || args[0] == "--help" {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("エラー: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.command == Command::Waypoints {
+        return waypoints(&args.files, args.waypoint_format);
+    }
+    if let Some(format) = args.format {
+        return export(&args.files, format);
+    }
+
+    // 1ファイルの失敗で止めず、最後まで処理してから終了コードで知らせる
+    let mut failed = false;
+    for path in args.files.iter() {
+        let result = match args.command {
+            Command::Show => show(path),
+            _ => edit(path, &args),
+        };
+        if let Err(e) = result {
+            eprintln!("{}: {}", path.display(), e);
+            failed = true;
+        }
+    }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
@@ -1,12 +1,25 @@
 pub mod basic_image;
+pub mod batch;
+pub mod changes;
+pub mod clock;
+pub mod copy;
 pub mod exif_capture;
+pub mod fuzz;
+pub mod geotag;
+pub mod icc;
 pub mod gps;
+pub mod import;
 pub mod interop;
+pub mod iptc;
+pub mod preset;
+pub mod segments;
 pub mod thumbnail;
 pub mod user;
 pub mod uneditable;
+pub mod xmp;
 
 pub mod accordion;
 pub mod map_component;
+pub mod svg_map;
 pub mod tabs;
 pub mod utils;
\ No newline at end of file
@@ -1,7 +1,6 @@
 use yew::prelude::*;
 
-use super::utils::ShowValue;
-use crate::exif::utils::AllList;
+use crate::exif::utils::{AllList, ShowValue};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
@@ -8,18 +8,21 @@ use crate::{
     ev, on_string, on_int, on_int_ref,
     on_f64, on_enum, on_vec, on_f64_vec
 };
+use crate::exif::ExifEditData;
 use crate::exif::rational::{approx_frac, ExifRational};
-use crate::exif::utils::{AllList, F64};
+use crate::exif::utils::{AllList, F64, ShowValue};
 use crate::exif::basic_image::{
     ResolutionUnit, Compression, PhotometricInterpretation, ColorSpace,
     PlanarConfiguration, YCbCrSubSampling, YCbCrPositioning, Orientation
 };
+use crate::file_type::FileType;
+use crate::icc::IccAction;
 
 use super::accordion::{
     Mode, AccordionMode, Accordion
 };
+use super::icc::IccProfileInfo;
 use super::tabs::TabItem;
-use super::utils::{InfoProps, ShowValue};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Tabs {
@@ -27,8 +30,16 @@ enum Tabs {
     ImageFormat,
 }
 
+#[derive(Properties, PartialEq)]
+pub struct BasicImageInfoProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>,
+    pub file_type: FileType,
+    pub file_bytes: UseStateHandle<Option<Vec<u8>>>,
+    pub icc_action: UseStateHandle<IccAction>,
+}
+
 #[function_component(BasicImageInfo)]
-pub fn basic_image_info(props: &InfoProps) -> Html {
+pub fn basic_image_info(props: &BasicImageInfoProps) -> Html {
     let selected_tab = use_state(|| Tabs::DeviceModel);
     let input_refs = [
         use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(),
@@ -127,6 +138,14 @@ pub fn basic_image_info(props: &InfoProps) -> Html {
             </ul>
 
             <div class="tab-content border border-top-0 p-3">
+            {
+                if *selected_tab == Tabs::ImageFormat {
+                    html! {
+                        <IccProfileInfo exif={props.exif.clone()} file_type={props.file_type}
+                            file_bytes={props.file_bytes.clone()} icc_action={props.icc_action.clone()} />
+                    }
+                } else { html! {} }
+            }
             <div class="accordion">
             {
                 match *selected_tab {
@@ -0,0 +1,463 @@
+use chrono::Local;
+use web_sys::{js_sys, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::batch::{build_zip, BatchFailure, BatchFile, TagOperation};
+use crate::components::clock::ClockShiftForm;
+use crate::components::fuzz::LocationFuzzForm;
+use crate::components::geotag::GeotagForm;
+use crate::components::utils::{download_bytes, file_list};
+use crate::exif::ExifTime;
+use crate::exif::clock::{parse_shift, ClockShift};
+use crate::geo::fuzz::LocationFuzz;
+use crate::geo::geotag::Geotag;
+use crate::geo::waypoint::{Waypoint, WaypointFormat};
+use crate::exif::tag_value::TAG_NAMES;
+
+const MAX_FILE_SIZE: usize = 1_073_741_824;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OperationKind {
+    Set,
+    Delete,
+    Shift,
+    Clock,
+    Geotag,
+    Fuzz,
+}
+
+impl OperationKind {
+    fn from_value(s: &str) -> Self {
+        match s {
+            "delete" => OperationKind::Delete,
+            "shift" => OperationKind::Shift,
+            "clock" => OperationKind::Clock,
+            "geotag" => OperationKind::Geotag,
+            "fuzz" => OperationKind::Fuzz,
+            _ => OperationKind::Set,
+        }
+    }
+}
+
+#[function_component(BatchMode)]
+pub fn batch_mode() -> Html {
+    let files = use_state(|| Vec::<BatchFile>::new());
+    let load_failures = use_state(|| Vec::<BatchFailure>::new());
+    let process_failures = use_state(|| Vec::<BatchFailure>::new());
+    let process_warnings = use_state(|| Vec::<BatchFailure>::new());
+    let operations = use_state(|| Vec::<TagOperation>::new());
+    let op_kind = use_state(|| OperationKind::Set);
+    let op_error = use_state(|| None::<String>);
+    let result_count = use_state(|| None::<usize>);
+    let is_loading = use_state(|| false);
+
+    let kind_ref = use_node_ref();
+    let name_ref = use_node_ref();
+    let time_ref = use_node_ref();
+    let value_ref = use_node_ref();
+
+    let load_files = {
+        let files = files.clone();
+        let load_failures = load_failures.clone();
+        let result_count = result_count.clone();
+        let is_loading = is_loading.clone();
+        Callback::from(move |list: Vec<web_sys::File>| {
+            let files = files.clone();
+            let load_failures = load_failures.clone();
+            let result_count = result_count.clone();
+            let is_loading = is_loading.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                is_loading.set(true);
+                let mut loaded = (*files).clone();
+                let mut failures = (*load_failures).clone();
+                for file in list.into_iter() {
+                    let name = file.name();
+                    if file.size() as usize > MAX_FILE_SIZE {
+                        failures.push(BatchFailure::new(&name, "ファイルサイズが大きすぎます (最大1GBまで対応)"));
+                        continue;
+                    }
+                    match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                        Ok(buf) => {
+                            let bytes = js_sys::Uint8Array::new(&buf).to_vec();
+                            match BatchFile::load(&name, bytes) {
+                                Ok(batch_file) => loaded.push(batch_file),
+                                Err(failure) => failures.push(failure),
+                            }
+                        }
+                        Err(_) => failures.push(BatchFailure::new(&name, "ファイルを読み込めません")),
+                    }
+                }
+                files.set(loaded);
+                load_failures.set(failures);
+                result_count.set(None);
+                is_loading.set(false);
+            });
+        })
+    };
+
+    let on_file_change = {
+        let load_files = load_files.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(list) = input.files() {
+                load_files.emit(file_list(&list));
+            }
+            input.set_value("");
+        })
+    };
+
+    let on_drop = {
+        let load_files = load_files.clone();
+        Callback::from(move |event: DragEvent| {
+            event.prevent_default();
+            if let Some(list) = event.data_transfer().and_then(|dt| dt.files()) {
+                load_files.emit(file_list(&list));
+            }
+        })
+    };
+
+    let on_drag_over = Callback::from(|event: DragEvent| {
+        event.prevent_default();
+    });
+
+    let on_clear_files = {
+        let files = files.clone();
+        let load_failures = load_failures.clone();
+        let process_failures = process_failures.clone();
+        let process_warnings = process_warnings.clone();
+        let result_count = result_count.clone();
+        Callback::from(move |_: MouseEvent| {
+            files.set(Vec::new());
+            load_failures.set(Vec::new());
+            process_failures.set(Vec::new());
+            process_warnings.set(Vec::new());
+            result_count.set(None);
+        })
+    };
+
+    let on_export_waypoints = {
+        let files = files.clone();
+        let op_error = op_error.clone();
+        Callback::from(move |format: WaypointFormat| {
+            let files = files.clone();
+            let op_error = op_error.clone();
+            Callback::from(move |_: MouseEvent| {
+                let waypoints = files.iter()
+                    .filter_map(|f| f.exif.waypoint(&f.name))
+                    .collect::<Vec<Waypoint>>();
+                let now = Local::now().naive_local();
+                let savename = format!("exif_edit_{}.{}", now.format("%Y_%m_%dT%H_%M_%S"), format.extension());
+                if download_bytes(format.export(&waypoints).as_bytes(), &savename).is_err() {
+                    op_error.set(Some("ファイルのダウンロードに失敗しました。".to_string()));
+                }
+            })
+        })
+    };
+
+    let on_kind_change = {
+        let kind_ref = kind_ref.clone();
+        let op_kind = op_kind.clone();
+        let op_error = op_error.clone();
+        Callback::from(move |_: Event| {
+            if let Some(select) = kind_ref.cast::<HtmlSelectElement>() {
+                op_kind.set(OperationKind::from_value(&select.value()));
+                op_error.set(None);
+            }
+        })
+    };
+
+    let on_add_operation = {
+        let name_ref = name_ref.clone();
+        let time_ref = time_ref.clone();
+        let value_ref = value_ref.clone();
+        let op_kind = op_kind.clone();
+        let op_error = op_error.clone();
+        let operations = operations.clone();
+        Callback::from(move |_: MouseEvent| {
+            let name = name_ref.cast::<HtmlInputElement>().map(|input| input.value().trim().to_string()).unwrap_or_default();
+            let value = value_ref.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+            let op = match *op_kind {
+                OperationKind::Set => TagOperation::set(&name, &value).map_err(|e| e.to_string()),
+                OperationKind::Delete => TagOperation::delete(&name).map_err(|e| e.to_string()),
+                OperationKind::Clock | OperationKind::Geotag | OperationKind::Fuzz => return,
+                OperationKind::Shift => {
+                    let time = time_ref.cast::<HtmlSelectElement>().and_then(|select| ExifTime::from_name(&select.value()));
+                    match (time, parse_shift(&value)) {
+                        (Some(time), Some(delta)) => Ok(TagOperation::Shift(time, delta)),
+                        _ => Err(format!("時刻のずらし幅を読み取れません: {}", value)),
+                    }
+                }
+            };
+            match op {
+                Ok(op) => {
+                    let mut ops = (*operations).clone();
+                    ops.push(op);
+                    operations.set(ops);
+                    op_error.set(None);
+                }
+                Err(e) => op_error.set(Some(e)),
+            }
+        })
+    };
+
+    let on_add_clock = {
+        let operations = operations.clone();
+        let op_error = op_error.clone();
+        Callback::from(move |shift: ClockShift| {
+            let mut ops = (*operations).clone();
+            ops.push(TagOperation::Clock(shift));
+            operations.set(ops);
+            op_error.set(None);
+        })
+    };
+
+    let on_add_geotag = {
+        let operations = operations.clone();
+        let op_error = op_error.clone();
+        Callback::from(move |geotag: Geotag| {
+            let mut ops = (*operations).clone();
+            ops.push(TagOperation::Geotag(geotag));
+            operations.set(ops);
+            op_error.set(None);
+        })
+    };
+
+    let on_add_fuzz = {
+        let operations = operations.clone();
+        let op_error = op_error.clone();
+        Callback::from(move |fuzz: LocationFuzz| {
+            let mut ops = (*operations).clone();
+            ops.push(TagOperation::Fuzz(fuzz));
+            operations.set(ops);
+            op_error.set(None);
+        })
+    };
+
+    let on_remove_operation = {
+        let operations = operations.clone();
+        Callback::from(move |i: usize| {
+            let operations = operations.clone();
+            Callback::from(move |_: MouseEvent| {
+                let mut ops = (*operations).clone();
+                if i < ops.len() {
+                    ops.remove(i);
+                }
+                operations.set(ops);
+            })
+        })
+    };
+
+    let on_process = {
+        let files = files.clone();
+        let operations = operations.clone();
+        let process_failures = process_failures.clone();
+        let process_warnings = process_warnings.clone();
+        let result_count = result_count.clone();
+        let op_error = op_error.clone();
+        Callback::from(move |_: MouseEvent| {
+            let now = Local::now().naive_local();
+            match build_zip(&files, &operations, now) {
+                Ok(output) => {
+                    if output.succeeded > 0 {
+                        let savename = format!("exif_edit_{}.zip", now.format("%Y_%m_%dT%H_%M_%S"));
+                        if download_bytes(&output.zip, &savename).is_err() {
+                            op_error.set(Some("ZIPファイルのダウンロードに失敗しました。".to_string()));
+                        }
+                    }
+                    result_count.set(Some(output.succeeded));
+                    process_failures.set(output.failures);
+                    process_warnings.set(output.warnings);
+                }
+                Err(e) => op_error.set(Some(format!("ZIPファイルの作成に失敗しました: {}", e))),
+            }
+        })
+    };
+
+    let failure_list = |title: &'static str, failures: &Vec<BatchFailure>| {
+        if failures.is_empty() {
+            html! {}
+        } else {
+            html! {
+                <div class="alert alert-warning small text-start">
+                    <p class="fw-bold mb-1">{ title }</p>
+                    <ul class="mb-0">
+                    { for failures.iter().map(|f| html! { <li>{ format!("{}: {}", f.name, f.reason) }</li> }) }
+                    </ul>
+                </div>
+            }
+        }
+    };
+
+    html! {
+        <>
+        <div
+            class="card shadow-sm mb-4"
+            ondragover={on_drag_over}
+            ondrop={on_drop}
+        >
+            <div class="card-body text-center">
+            <h2 class="h5 mb-3">{ "ステップ1: 画像をまとめてアップロード" }</h2>
+            <p class="mb-3">{ "複数の画像をドラッグ＆ドロップするか、ボタンで選択してください。同じ編集内容をすべての画像に適用します。" }</p>
+            <div class="mx-auto" style="max-width: 360px;">
+                <input
+                    type="file"
+                    multiple=true
+                    accept="image/jpeg,image/heic,image/heif,image/png,image/webp,image/tiff,.heic,.heif,.tif,.tiff"
+                    class="form-control mb-3"
+                    onchange={on_file_change} />
+            </div>
+            {
+                if *is_loading {
+                    html! {
+                        <div class="spinner-border text-primary mb-3" role="status">
+                            <span class="visually-hidden">{ "読み込み中..." }</span>
+                        </div>
+                    }
+                } else { html! {} }
+            }
+            {
+                if files.is_empty() {
+                    html! {}
+                } else {
+                    html! {
+                        <>
+                        <p class="mb-2">{ format!("読み込み済み: {}件", files.len()) }</p>
+                        <ul class="list-group mb-3 text-start">
+                        { for files.iter().map(|f| html! {
+                            <li class="list-group-item d-flex justify-content-between">
+                                <span>{ f.name.clone() }</span>
+                                <span class="badge text-bg-secondary">{ f.file_type.label() }</span>
+                            </li>
+                        }) }
+                        </ul>
+                        <button type="button" class="btn btn-outline-secondary btn-sm mb-3" onclick={on_clear_files}>{ "ファイルをすべて取り除く" }</button>
+                        {
+                            match files.iter().filter(|f| f.exif.position().is_some()).count() {
+                                0 => html! {},
+                                n => html! {
+                                    <div class="mb-3">
+                                        <p class="small text-muted mb-2">{ format!("位置情報のある{}件の撮影地点を書き出せます。", n) }</p>
+                                        <div class="d-flex flex-wrap gap-2 justify-content-center">
+                                        { for WaypointFormat::all().iter().map(|format| html! {
+                                            <button type="button" class="btn btn-outline-success btn-sm" onclick={on_export_waypoints.emit(*format)}>
+                                                <i class="bi bi-geo-alt me-1" aria-hidden="true"></i>
+                                                { format.label() }
+                                            </button>
+                                        }) }
+                                        </div>
+                                    </div>
+                                },
+                            }
+                        }
+                        </>
+                    }
+                }
+            }
+            { failure_list("読み込めなかったファイル", &load_failures) }
+            </div>
+        </div>
+
+        <div class="card shadow-sm mb-4">
+            <div class="card-body">
+            <h2 class="h5 mb-3 text-center">{ "ステップ2: 編集内容を設定" }</h2>
+            <p class="text-muted small text-center mb-3">
+                { "タグ名はExifの英語名で指定します（例: Artist, GPSLatitude）。数値が複数ある場合はカンマで区切り、分数は「1/250」のように入力してください。" }
+            </p>
+            <div class="row g-2 mb-2">
+                <div class="col-12 col-sm-3">
+                    <select class="form-select" ref={kind_ref} onchange={on_kind_change}>
+                        <option value="set" selected={*op_kind == OperationKind::Set}>{ "設定" }</option>
+                        <option value="delete" selected={*op_kind == OperationKind::Delete}>{ "削除" }</option>
+                        <option value="shift" selected={*op_kind == OperationKind::Shift}>{ "時刻シフト" }</option>
+                        <option value="clock" selected={*op_kind == OperationKind::Clock}>{ "時計補正" }</option>
+                        <option value="geotag" selected={*op_kind == OperationKind::Geotag}>{ "ジオタグ" }</option>
+                        <option value="fuzz" selected={*op_kind == OperationKind::Fuzz}>{ "位置をぼかす" }</option>
+                    </select>
+                </div>
+                {
+                    if *op_kind == OperationKind::Clock || *op_kind == OperationKind::Geotag || *op_kind == OperationKind::Fuzz {
+                        html! {}
+                    } else if *op_kind == OperationKind::Shift {
+                        html! {
+                            <div class="col-12 col-sm-4">
+                                <select class="form-select" ref={time_ref}>
+                                { for ExifTime::all().iter().map(|t| html! { <option value={t.name()}>{ t.name() }</option> }) }
+                                </select>
+                            </div>
+                        }
+                    } else {
+                        html! {
+                            <div class="col-12 col-sm-4">
+                                <input type="text" class="form-control" list="batchTagNames" placeholder="タグ名" ref={name_ref} />
+                                <datalist id="batchTagNames">
+                                { for TAG_NAMES.iter().map(|name| html! { <option value={*name} /> }) }
+                                </datalist>
+                            </div>
+                        }
+                    }
+                }
+                {
+                    match *op_kind {
+                        OperationKind::Delete | OperationKind::Clock | OperationKind::Geotag | OperationKind::Fuzz => html! {},
+                        OperationKind::Set => html! {
+                            <div class="col-12 col-sm-5">
+                                <input type="text" class="form-control" placeholder="値" ref={value_ref} />
+                            </div>
+                        },
+                        OperationKind::Shift => html! {
+                            <div class="col-12 col-sm-5">
+                                <input type="text" class="form-control" placeholder="+01:00:00" ref={value_ref} />
+                            </div>
+                        },
+                    }
+                }
+            </div>
+            {
+                if *op_kind == OperationKind::Clock {
+                    html! { <ClockShiftForm on_submit={on_add_clock} button={"編集内容を追加"} /> }
+                } else if *op_kind == OperationKind::Geotag {
+                    html! { <GeotagForm on_submit={on_add_geotag} button={"編集内容を追加"} /> }
+                } else if *op_kind == OperationKind::Fuzz {
+                    html! { <LocationFuzzForm on_submit={on_add_fuzz} button={"編集内容を追加"} /> }
+                } else {
+                    html! { <button type="button" class="btn btn-secondary w-100 mb-3" onclick={on_add_operation}>{ "編集内容を追加" }</button> }
+                }
+            }
+            {
+                if let Some(msg) = (*op_error).clone() {
+                    html! { <div class="alert alert-danger small">{ msg }</div> }
+                } else { html! {} }
+            }
+            {
+                if operations.is_empty() {
+                    html! { <p class="text-muted small text-center">{ "編集内容はまだありません。" }</p> }
+                } else {
+                    html! {
+                        <ol class="list-group list-group-numbered mb-3">
+                        { for operations.iter().enumerate().map(|(i, op)| html! {
+                            <li class="list-group-item d-flex justify-content-between align-items-start">
+                                <span class="ms-2 me-auto text-break">{ op.describe() }</span>
+                                <button type="button" class="btn btn-outline-danger btn-sm" onclick={on_remove_operation.emit(i)}>{ "取消" }</button>
+                            </li>
+                        }) }
+                        </ol>
+                    }
+                }
+            }
+            <button type="button" class="btn btn-primary w-100 mb-3"
+                disabled={files.is_empty()}
+                onclick={on_process}>
+                { format!("{}件の画像に適用してZIPでダウンロード", files.len()) }
+            </button>
+            {
+                if let Some(count) = *result_count {
+                    html! { <p class="text-center">{ format!("{}件のファイルを書き出しました。", count) }</p> }
+                } else { html! {} }
+            }
+            { failure_list("処理できなかったファイル", &process_failures) }
+            { failure_list("書き出したが、一部の編集を適用できなかったファイル", &process_warnings) }
+            </div>
+        </div>
+        </>
+    }
+}
@@ -0,0 +1,97 @@
+use yew::prelude::*;
+
+use crate::exif::ExifEditData;
+use crate::exif::diff::{diff, group_changes, ChangeKind, TagChange};
+use crate::exif::tag_value::TagKey;
+
+#[derive(Properties, PartialEq)]
+pub struct ChangesProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>,
+    pub original: UseStateHandle<Option<ExifEditData>>,
+}
+
+#[function_component(Changes)]
+pub fn changes(props: &ChangesProps) -> Html {
+    let (Some(original), Some(edited)) = (props.original.as_ref(), props.exif.as_ref()) else {
+        return html! {};
+    };
+    let changes = diff(original, edited);
+
+    let on_revert = {
+        let exif = props.exif.clone();
+        Callback::from(move |change: TagChange| {
+            let exif = exif.clone();
+            Callback::from(move |_: MouseEvent| {
+                if let Some(eed) = exif.as_ref() {
+                    let mut eed = eed.clone();
+                    eed.revert_change(&change);
+                    exif.set(Some(eed));
+                }
+            })
+        })
+    };
+
+    let on_revert_all = {
+        let exif = props.exif.clone();
+        let original = original.clone();
+        Callback::from(move |_: MouseEvent| exif.set(Some(original.clone())))
+    };
+
+    let badge = |kind: ChangeKind| match kind {
+        ChangeKind::Added => "badge text-bg-success",
+        ChangeKind::Removed => "badge text-bg-danger",
+        ChangeKind::Modified => "badge text-bg-warning",
+    };
+
+    html! {
+        <div class="tab-content border border-top-0 p-3">
+        {
+            if changes.is_empty() {
+                html! { <p class="text-muted text-center mb-0">{ "読み込み時から変更されたタグはありません。" }</p> }
+            } else {
+                html! {
+                    <>
+                    <div class="d-flex justify-content-between align-items-center mb-3">
+                        <span>{ format!("{}件のタグが変更されています。", changes.len()) }</span>
+                        <button type="button" class="btn btn-outline-secondary btn-sm" onclick={on_revert_all}>{ "すべて元に戻す" }</button>
+                    </div>
+                    { for group_changes(&changes).into_iter().map(|(group, changes)| html! {
+                        <>
+                        <h3 class="h6 mt-3">{ group }</h3>
+                        <div class="table-responsive">
+                        <table class="table table-sm align-middle small">
+                            <thead>
+                                <tr>
+                                    <th>{ "タグ" }</th>
+                                    <th>{ "種類" }</th>
+                                    <th>{ "変更前" }</th>
+                                    <th>{ "変更後" }</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                            { for changes.into_iter().map(|change| html! {
+                                <tr>
+                                    <td class="fw-bold">{ TagKey::new(change.ifd, &change.name).label() }</td>
+                                    <td><span class={badge(change.kind)}>{ change.kind.label() }</span></td>
+                                    <td class="text-break">{ change.old_value.clone().unwrap_or("-".to_string()) }</td>
+                                    <td class="text-break">{ change.new_value.clone().unwrap_or("-".to_string()) }</td>
+                                    <td>
+                                        <button type="button" class="btn btn-outline-secondary btn-sm" title="元に戻す" onclick={on_revert.emit(change.clone())}>
+                                            <i class="bi bi-arrow-counterclockwise" aria-hidden="true"></i>
+                                        </button>
+                                    </td>
+                                </tr>
+                            }) }
+                            </tbody>
+                        </table>
+                        </div>
+                        </>
+                    }) }
+                    </>
+                }
+            }
+        }
+        </div>
+    }
+}
@@ -0,0 +1,170 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::exif::clock::{parse_shift, ClockShift};
+use crate::exif::exif_capture::TimeOffset;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ShiftMode {
+    Delta,
+    TimeZone,
+}
+
+// 実際に使われているUTCからのオフセット（夏時間を含む）
+const UTC_OFFSETS: &[&str] = &[
+    "-12:00", "-11:00", "-10:00", "-09:30", "-09:00", "-08:00", "-07:00", "-06:00", "-05:00", "-04:00",
+    "-03:30", "-03:00", "-02:30", "-02:00", "-01:00", "+00:00", "+01:00", "+02:00", "+03:00", "+03:30",
+    "+04:00", "+04:30", "+05:00", "+05:30", "+05:45", "+06:00", "+06:30", "+07:00", "+08:00", "+08:45",
+    "+09:00", "+09:30", "+10:00", "+10:30", "+11:00", "+12:00", "+12:45", "+13:00", "+13:45", "+14:00",
+];
+
+pub fn offset_options() -> Vec<String> {
+    UTC_OFFSETS.iter().map(|o| o.to_string()).collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ClockShiftFormProps {
+    pub on_submit: Callback<ClockShift>,
+    pub button: &'static str,
+}
+
+#[function_component(ClockShiftForm)]
+pub fn clock_shift_form(props: &ClockShiftFormProps) -> Html {
+    let mode = use_state(|| ShiftMode::Delta);
+    let error = use_state(|| None::<String>);
+
+    let mode_ref = use_node_ref();
+    let delta_ref = use_node_ref();
+    let from_ref = use_node_ref();
+    let to_ref = use_node_ref();
+    let rewrite_ref = use_node_ref();
+    let offset_ref = use_node_ref();
+    let gps_ref = use_node_ref();
+
+    let on_mode_change = {
+        let mode = mode.clone();
+        let mode_ref = mode_ref.clone();
+        let error = error.clone();
+        Callback::from(move |_: Event| {
+            if let Some(select) = mode_ref.cast::<HtmlSelectElement>() {
+                mode.set(if select.value() == "timezone" { ShiftMode::TimeZone } else { ShiftMode::Delta });
+                error.set(None);
+            }
+        })
+    };
+
+    let on_click = {
+        let mode = mode.clone();
+        let error = error.clone();
+        let on_submit = props.on_submit.clone();
+        let (delta_ref, from_ref, to_ref) = (delta_ref.clone(), from_ref.clone(), to_ref.clone());
+        let (rewrite_ref, offset_ref, gps_ref) = (rewrite_ref.clone(), offset_ref.clone(), gps_ref.clone());
+        Callback::from(move |_: MouseEvent| {
+            let select_offset = |r: &NodeRef| r.cast::<HtmlSelectElement>().and_then(|select| TimeOffset::from_str(&select.value()));
+            let checked = |r: &NodeRef| r.cast::<HtmlInputElement>().map(|input| input.checked()).unwrap_or(false);
+
+            let shift = match *mode {
+                ShiftMode::Delta => {
+                    let value = delta_ref.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+                    match parse_shift(&value) {
+                        Some(delta) => {
+                            let mut shift = ClockShift::new(delta);
+                            if checked(&rewrite_ref) {
+                                shift.offset = select_offset(&offset_ref);
+                            }
+                            Some(shift)
+                        }
+                        None => {
+                            error.set(Some(format!("時刻のずらし幅を読み取れません: {}", value)));
+                            None
+                        }
+                    }
+                }
+                ShiftMode::TimeZone => match (select_offset(&from_ref), select_offset(&to_ref)) {
+                    (Some(from), Some(to)) => Some(ClockShift::between(&from, &to)),
+                    _ => {
+                        error.set(Some("タイムゾーンを読み取れません。".to_string()));
+                        None
+                    }
+                },
+            };
+            if let Some(mut shift) = shift {
+                shift.update_gps = checked(&gps_ref);
+                error.set(None);
+                on_submit.emit(shift);
+            }
+        })
+    };
+
+    let offset_select = |node_ref: NodeRef, selected: &'static str| html! {
+        <select class="form-select" ref={node_ref}>
+        { for offset_options().into_iter().map(|o| html! { <option value={o.clone()} selected={o == selected}>{ o.clone() }</option> }) }
+        </select>
+    };
+
+    html! {
+        <div>
+            <div class="row g-2 mb-2">
+                <div class="col-12 col-sm-4">
+                    <select class="form-select" ref={mode_ref} onchange={on_mode_change}>
+                        <option value="delta" selected={*mode == ShiftMode::Delta}>{ "時間をずらす" }</option>
+                        <option value="timezone" selected={*mode == ShiftMode::TimeZone}>{ "タイムゾーンを付け替える" }</option>
+                    </select>
+                </div>
+                {
+                    match *mode {
+                        ShiftMode::Delta => html! {
+                            <>
+                            <div class="col-12 col-sm-4">
+                                <input type="text" class="form-control" placeholder="+1h 23m 5s / -00:30:00" ref={delta_ref} />
+                            </div>
+                            <div class="col-12 col-sm-4">
+                                <div class="input-group">
+                                    <div class="input-group-text">
+                                        <input class="form-check-input mt-0" type="checkbox" title="OffsetTime*を書き換える" ref={rewrite_ref} />
+                                    </div>
+                                    { offset_select(offset_ref, "+09:00") }
+                                </div>
+                            </div>
+                            </>
+                        },
+                        ShiftMode::TimeZone => html! {
+                            <>
+                            <div class="col-6 col-sm-4">
+                                <div class="input-group">
+                                    <span class="input-group-text">{ "カメラ" }</span>
+                                    { offset_select(from_ref, "+00:00") }
+                                </div>
+                            </div>
+                            <div class="col-6 col-sm-4">
+                                <div class="input-group">
+                                    <span class="input-group-text">{ "正しい" }</span>
+                                    { offset_select(to_ref, "+09:00") }
+                                </div>
+                            </div>
+                            </>
+                        },
+                    }
+                }
+            </div>
+            <div class="form-check mb-2">
+                <input class="form-check-input" type="checkbox" id="clockShiftGps" ref={gps_ref} />
+                <label class="form-check-label small" for="clockShiftGps">
+                    { "補正後の撮影日時とオフセットから、GPSDateStamp / GPSTimeStampをUTCで計算し直す" }
+                </label>
+                <div class="form-text">
+                    { "OffsetTime*のない画像は、オフセットを書き換えないとGPS日時を計算できません。" }
+                </div>
+            </div>
+            <p class="small text-muted mb-2">
+                { "DateTimeOriginal, CreateDate, ModifyDateをまとめてずらします。タイムゾーンの付け替えでは、OffsetTime*も新しいタイムゾーンに書き換えます。" }
+            </p>
+            <button type="button" class="btn btn-secondary w-100 mb-2" onclick={on_click}>{ props.button }</button>
+            {
+                if let Some(msg) = (*error).clone() {
+                    html! { <div class="alert alert-danger small">{ msg }</div> }
+                } else { html! {} }
+            }
+        </div>
+    }
+}
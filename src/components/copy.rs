@@ -0,0 +1,197 @@
+use std::collections::BTreeSet;
+
+use web_sys::{js_sys, HtmlInputElement};
+use yew::prelude::*;
+
+use crate::exif::ExifEditData;
+use crate::exif::copy::OPT_IN_GROUPS;
+use crate::exif::tag_value::{value_string, TagKey};
+use crate::file_type::FileType;
+
+use super::utils::InfoProps;
+
+#[function_component(CopyMetadata)]
+pub fn copy_metadata(props: &InfoProps) -> Html {
+    let source = use_state(|| None::<(String, ExifEditData)>);
+    let selected = use_state(BTreeSet::<TagKey>::new);
+    let error = use_state(|| None::<String>);
+    let message = use_state(|| None::<String>);
+
+    let on_change = {
+        let source = source.clone();
+        let selected = selected.clone();
+        let error = error.clone();
+        let message = message.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else { return; };
+            input.set_value("");
+
+            let source = source.clone();
+            let selected = selected.clone();
+            let error = error.clone();
+            let message = message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let name = file.name();
+                let bytes = match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                    Ok(buf) => js_sys::Uint8Array::new(&buf).to_vec(),
+                    Err(_) => {
+                        error.set(Some("ファイルを読み込めません。".to_string()));
+                        return;
+                    }
+                };
+                let Some(file_type) = FileType::detect(&bytes) else {
+                    error.set(Some("対応していないファイル形式です。".to_string()));
+                    return;
+                };
+                match file_type.read_metadata(&bytes) {
+                    Ok(metadata) => {
+                        let eed = ExifEditData::new(&metadata);
+                        // 初期状態では、画像の形式に関わる分類などを除いてすべて選択しておく
+                        selected.set(eed.copyable_groups().into_iter()
+                            .filter(|(group, _)| !OPT_IN_GROUPS.contains(group))
+                            .flat_map(|(_, keys)| keys.into_iter())
+                            .collect());
+                        source.set(Some((name, eed)));
+                        error.set(None);
+                        message.set(None);
+                    }
+                    Err(e) => error.set(Some(format!("メタデータを読み込めません: {}", e))),
+                }
+            });
+        })
+    };
+
+    let on_toggle_group = {
+        let selected = selected.clone();
+        Callback::from(move |keys: Vec<TagKey>| {
+            let selected = selected.clone();
+            Callback::from(move |_: Event| {
+                let mut set = (*selected).clone();
+                if keys.iter().all(|key| set.contains(key)) {
+                    for key in keys.iter() {
+                        set.remove(key);
+                    }
+                } else {
+                    set.extend(keys.iter().cloned());
+                }
+                selected.set(set);
+            })
+        })
+    };
+
+    let on_toggle_tag = {
+        let selected = selected.clone();
+        Callback::from(move |key: TagKey| {
+            let selected = selected.clone();
+            Callback::from(move |_: Event| {
+                let mut set = (*selected).clone();
+                if !set.remove(&key) {
+                    set.insert(key.clone());
+                }
+                selected.set(set);
+            })
+        })
+    };
+
+    let on_apply = {
+        let exif = props.exif.clone();
+        let source = source.clone();
+        let selected = selected.clone();
+        let message = message.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let (Some((_, src)), Some(eed)) = (source.as_ref(), exif.as_ref()) {
+                let mut eed = eed.clone();
+                let keys = selected.iter().cloned().collect::<Vec<TagKey>>();
+                let copied = eed.copy_tags_from(src, &keys);
+                exif.set(Some(eed));
+                message.set(Some(format!("{}件のタグをコピーしました。", copied.len())));
+            }
+        })
+    };
+
+    let on_clear = {
+        let source = source.clone();
+        let selected = selected.clone();
+        let message = message.clone();
+        Callback::from(move |_: MouseEvent| {
+            source.set(None);
+            selected.set(BTreeSet::new());
+            message.set(None);
+        })
+    };
+
+    html! {
+        <div class="tab-content border border-top-0 p-3">
+            <label class="form-label small text-muted" for="copySource">
+                { "別の画像（コピー元）を読み込み、選んだタグを編集中の画像に上書きします。オフセットなどファイル構造に関わるタグ、MakerNote、未知のタグはコピーされません。" }
+            </label>
+            <input id="copySource" type="file" accept="image/jpeg,image/heic,image/png,image/webp,image/tiff" class="form-control form-control-sm mb-3" onchange={on_change} />
+            {
+                if let Some(msg) = (*error).clone() {
+                    html! { <div class="alert alert-danger small">{ msg }</div> }
+                } else { html! {} }
+            }
+            {
+                if let Some((name, src)) = source.as_ref() {
+                    let groups = src.copyable_groups();
+                    let tags = src.copyable_tags();
+                    html! {
+                        <>
+                        <div class="d-flex justify-content-between align-items-center mb-2">
+                            <span class="fw-bold text-break">{ format!("コピー元: {}", name) }</span>
+                            <button type="button" class="btn btn-outline-secondary btn-sm" onclick={on_clear}>{ "解除" }</button>
+                        </div>
+                        {
+                            if groups.is_empty() {
+                                html! { <p class="text-muted">{ "コピーできるタグがありません。" }</p> }
+                            } else {
+                                html! {
+                                    <>
+                                    { for groups.into_iter().map(|(group, keys)| {
+                                        let checked = keys.iter().all(|key| selected.contains(key));
+                                        let count = keys.iter().filter(|key| selected.contains(*key)).count();
+                                        html! {
+                                            <details class="mb-2">
+                                                <summary>
+                                                    <input type="checkbox" class="form-check-input me-2" id={format!("copy-{}", group)}
+                                                        checked={checked} onchange={on_toggle_group.emit(keys.clone())} />
+                                                    <label class="form-check-label fw-bold" for={format!("copy-{}", group)}>{ group }</label>
+                                                    <span class="text-muted small ms-2">{ format!("{}/{}", count, keys.len()) }</span>
+                                                </summary>
+                                                <ul class="list-unstyled small ms-4 mt-1">
+                                                { for keys.iter().map(|key| html! {
+                                                    <li class="form-check">
+                                                        <input type="checkbox" class="form-check-input" id={format!("copy-tag-{}-{}", key.ifd, key.name)}
+                                                            checked={selected.contains(key)} onchange={on_toggle_tag.emit(key.clone())} />
+                                                        <label class="form-check-label" for={format!("copy-tag-{}-{}", key.ifd, key.name)}>
+                                                            <span class="fw-bold">{ key.label() }</span>
+                                                            <span class="text-muted text-break">
+                                                                { format!(": {}", tags.get(key).map(value_string).unwrap_or_default()) }
+                                                            </span>
+                                                        </label>
+                                                    </li>
+                                                }) }
+                                                </ul>
+                                            </details>
+                                        }
+                                    }) }
+                                    </>
+                                }
+                            }
+                        }
+                        <button type="button" class="btn btn-primary w-100 mt-2" disabled={selected.is_empty()} onclick={on_apply}>
+                            { format!("選択した{}件のタグをコピー", selected.len()) }
+                        </button>
+                        </>
+                    }
+                } else { html! {} }
+            }
+            {
+                if let Some(msg) = (*message).clone() {
+                    html! { <div class="alert alert-success small mt-2 mb-0">{ msg }</div> }
+                } else { html! {} }
+            }
+        </div>
+    }
+}
@@ -5,7 +5,6 @@ use yew::prelude::*;
 use little_exif::exif_tag::ExifTag;
 use little_exif::rational::{iR64, uR64};
 
-use crate::components::utils::ShowValue;
 use crate::{
     ev, ev_time, on_string, on_int, 
     on_f64, on_enum, on_enum_u8, on_vec, 
@@ -20,7 +19,7 @@ use crate::exif::exif_capture::{
     ComponentsConfiguration, CompositeImage
 };
 use crate::exif::rational::{approx_frac, ExifRational};
-use crate::exif::utils::{AllList, F64};
+use crate::exif::utils::{AllList, F64, ShowValue};
 
 use super::accordion::{
     Mode, AccordionMode, Accordion
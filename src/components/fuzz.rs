@@ -0,0 +1,59 @@
+use chrono::Local;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use crate::geo::fuzz::{format_meters, LocationFuzz};
+
+const DISTANCES: [f64; 3] = [100.0, 1000.0, 10000.0];
+
+#[derive(Properties, PartialEq)]
+pub struct LocationFuzzFormProps {
+    pub on_submit: Callback<LocationFuzz>,
+    pub button: &'static str,
+}
+
+#[function_component(LocationFuzzForm)]
+pub fn location_fuzz_form(props: &LocationFuzzFormProps) -> Html {
+    let method_ref = use_node_ref();
+    let meters_ref = use_node_ref();
+
+    let on_click = {
+        let on_submit = props.on_submit.clone();
+        let (method_ref, meters_ref) = (method_ref.clone(), meters_ref.clone());
+        Callback::from(move |_: MouseEvent| {
+            let method = method_ref.cast::<HtmlSelectElement>().map(|select| select.value()).unwrap_or_default();
+            let Some(meters) = meters_ref.cast::<HtmlSelectElement>()
+                .and_then(|select| select.value().parse::<f64>().ok()) else { return; };
+            let fuzz = if method == "random" {
+                LocationFuzz::random(meters, Local::now().timestamp_nanos_opt().unwrap_or_default() as u64)
+            } else {
+                LocationFuzz::grid(meters)
+            };
+            on_submit.emit(fuzz);
+        })
+    };
+
+    html! {
+        <div>
+            <div class="row g-2 mb-2">
+                <div class="col-6">
+                    <select class="form-select" ref={method_ref}>
+                        <option value="grid" selected={true}>{ "格子に丸める" }</option>
+                        <option value="random">{ "ランダムにずらす" }</option>
+                    </select>
+                </div>
+                <div class="col-6">
+                    <select class="form-select" ref={meters_ref}>
+                    { for DISTANCES.iter().map(|m| html! {
+                        <option value={m.to_string()} selected={*m == 1000.0}>{ format!("約{}", format_meters(*m)) }</option>
+                    }) }
+                    </select>
+                </div>
+            </div>
+            <p class="small text-muted mb-2">
+                { "緯度・経度の精度を下げ、GPSAltitude, GPSImgDirection, GPSDest*を削除します。下げた精度はGPSHPositioningErrorに記録します。" }
+            </p>
+            <button type="button" class="btn btn-secondary w-100 mb-2" onclick={on_click}>{ props.button }</button>
+        </div>
+    }
+}
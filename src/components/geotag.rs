@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use chrono::Duration;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::components::clock::offset_options;
+use crate::exif::clock::parse_shift;
+use crate::exif::exif_capture::TimeOffset;
+use crate::geo::geotag::{Geotag, GeotagOptions};
+use crate::geo::track::Track;
+
+#[derive(Properties, PartialEq)]
+pub struct GeotagFormProps {
+    pub on_submit: Callback<Geotag>,
+    pub button: &'static str,
+}
+
+#[function_component(GeotagForm)]
+pub fn geotag_form(props: &GeotagFormProps) -> Html {
+    let track = use_state(|| None::<(String, Rc<Track>)>);
+    let error = use_state(|| None::<String>);
+
+    let gap_ref = use_node_ref();
+    let clock_ref = use_node_ref();
+    let offset_ref = use_node_ref();
+
+    let on_change = {
+        let track = track.clone();
+        let error = error.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else { return; };
+            input.set_value("");
+
+            let track = track.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let text = match wasm_bindgen_futures::JsFuture::from(file.text()).await {
+                    Ok(v) => v.as_string().unwrap_or_default(),
+                    Err(_) => {
+                        error.set(Some("ファイルを読み込めません。".to_string()));
+                        return;
+                    }
+                };
+                match Track::parse(&text) {
+                    Ok(t) => {
+                        track.set(Some((file.name(), Rc::new(t))));
+                        error.set(None);
+                    }
+                    Err(e) => {
+                        track.set(None);
+                        error.set(Some(e));
+                    }
+                }
+            });
+        })
+    };
+
+    let on_click = {
+        let track = track.clone();
+        let error = error.clone();
+        let on_submit = props.on_submit.clone();
+        let (gap_ref, clock_ref, offset_ref) = (gap_ref.clone(), clock_ref.clone(), offset_ref.clone());
+        Callback::from(move |_: MouseEvent| {
+            let Some((_, t)) = track.as_ref() else { return; };
+            let max_gap = gap_ref.cast::<HtmlInputElement>()
+                .and_then(|input| input.value().trim().parse::<i64>().ok())
+                .filter(|m| *m >= 0)
+                .and_then(Duration::try_minutes);
+            let clock = clock_ref.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+            let clock_offset = if clock.trim().is_empty() { Some(Duration::zero()) } else { parse_shift(&clock) };
+            let default_offset = offset_ref.cast::<HtmlSelectElement>().and_then(|select| TimeOffset::from_str(&select.value()));
+            match (max_gap, clock_offset, default_offset) {
+                (Some(max_gap), Some(clock_offset), Some(default_offset)) => {
+                    error.set(None);
+                    on_submit.emit(Geotag {
+                        track: t.clone(),
+                        options: GeotagOptions { max_gap, clock_offset, default_offset },
+                    });
+                }
+                (None, _, _) => error.set(Some("最大間隔は分単位の0以上の整数で入力してください。".to_string())),
+                (_, None, _) => error.set(Some(format!("時計の遅れを読み取れません: {}", clock))),
+                _ => error.set(Some("入力内容を読み取れません。".to_string())),
+            }
+        })
+    };
+
+    html! {
+        <div>
+            <label class="form-label small text-muted" for="geotagTrack">
+                { "GPX, KML, GeoJSONのトラックログから、撮影日時に対応する地点を探して位置情報を書き込みます。" }
+            </label>
+            <input id="geotagTrack" type="file" accept=".gpx,.kml,.geojson,.json" class="form-control form-control-sm mb-2" onchange={on_change} />
+            {
+                if let Some((name, t)) = track.as_ref() {
+                    html! {
+                        <p class="small mb-2 text-break">
+                        { format!(
+                            "{}: {}地点 ({} 〜 {} UTC)",
+                            name,
+                            t.points.len(),
+                            t.start().map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default(),
+                            t.end().map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default(),
+                        ) }
+                        </p>
+                    }
+                } else { html! {} }
+            }
+            <div class="row g-2 mb-2">
+                <div class="col-12 col-sm-4">
+                    <div class="input-group input-group-sm">
+                        <span class="input-group-text">{ "最大間隔" }</span>
+                        <input type="number" min="0" class="form-control" value="10" ref={gap_ref} />
+                        <span class="input-group-text">{ "分" }</span>
+                    </div>
+                </div>
+                <div class="col-12 col-sm-4">
+                    <div class="input-group input-group-sm">
+                        <span class="input-group-text">{ "時計の遅れ" }</span>
+                        <input type="text" class="form-control" placeholder="+1m 30s" ref={clock_ref} />
+                    </div>
+                </div>
+                <div class="col-12 col-sm-4">
+                    <div class="input-group input-group-sm">
+                        <span class="input-group-text" title="OffsetTimeOriginalがない画像のタイムゾーン">{ "既定のTZ" }</span>
+                        <select class="form-select" ref={offset_ref}>
+                        { for offset_options().into_iter().map(|o| html! { <option value={o.clone()} selected={o == "+09:00"}>{ o.clone() }</option> }) }
+                        </select>
+                    </div>
+                </div>
+            </div>
+            <button type="button" class="btn btn-secondary w-100 mb-2" disabled={track.is_none()} onclick={on_click}>{ props.button }</button>
+            {
+                if let Some(msg) = (*error).clone() {
+                    html! { <div class="alert alert-danger small">{ msg }</div> }
+                } else { html! {} }
+            }
+        </div>
+    }
+}
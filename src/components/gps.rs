@@ -5,26 +5,25 @@ use yew::prelude::*;
 
 use little_exif::exif_tag::ExifTag;
 
-use crate::components::utils::ShowValue;
 use crate::exif::rational::{approx_frac, ExifRational};
-use crate::exif::utils::{AllList, F64};
+use crate::exif::utils::{AllList, F64, ShowValue};
 use crate::{
     ev, on_f64, on_string, on_int, on_vec, on_enum
 };
 
 use crate::exif::gps::{
-    DMS,
+    DMS, parse_coordinates, signed_degree,
     GPSAltitudeRef, GPSStatus, GPSMeasureMode, GPSSpeedRef, NorthRef,
     GPSDestDistanceRef, GPSDifferential
 };
 
 use super::accordion::{Accordion, AccordionMode, Mode};
-use super::map_component::MapComponent;
+use super::map_component::{MapOverlay, MapView, OverlayEdit};
 use super::utils::InfoProps;
 
 macro_rules! on_latlng {
     (
-        $name:ident,
+        $name:ident, $set:ident,
         $lat_ref:ident, $lat:ident, $lng_ref:ident, $lng:ident, 
         $props:ident
     ) => {
@@ -38,15 +37,9 @@ macro_rules! on_latlng {
                         Mode::Update | Mode::Create => {
                             if let Some(eed) = exif.as_ref() {
                                 let mut eed = eed.clone();
-                                let lat_ref = if lat >= 0.0 { "N".to_string() } else { "S".to_string() };
-                                let lat = DMS::from_f64(lat, None).unwrap().to_vec();
-                                let lng_ref = if lng >= 0.0 { "E".to_string() } else { "N".to_string() };
-                                let lng = DMS::from_f64(lng, None).unwrap().to_vec();
-                                eed.update_tag(ExifTag::$lat_ref(lat_ref));
-                                eed.update_tag(ExifTag::$lat(lat));
-                                eed.update_tag(ExifTag::$lng_ref(lng_ref));
-                                eed.update_tag(ExifTag::$lng(lng));
-                                exif.set(Some(eed));
+                                if eed.$set(lat, lng) {
+                                    exif.set(Some(eed));
+                                }
                             }
                         }
                         Mode::Delete => {
@@ -138,7 +131,7 @@ pub fn gps_info(props: &InfoProps) -> Html {
         use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(),
         use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(), use_node_ref(),
     ];
-    on_latlng!(gps, GPSLatitudeRef, GPSLatitude, GPSLongitudeRef, GPSLongitude, props);
+    on_latlng!(gps, set_lat_lng, GPSLatitudeRef, GPSLatitude, GPSLongitudeRef, GPSLongitude, props);
     on_string!(gps_map_datum, GPSMapDatum, input_refs[0], props);
     let altitude = {
         let input_ref_t = input_refs[1].clone();
@@ -203,7 +196,7 @@ pub fn gps_info(props: &InfoProps) -> Html {
     on_with_ref!(uR64, gps_track, NorthRef, GPSTrackRef, GPSTrack, input_refs[9], input_refs[10], props);
     on_with_ref!(uR64, gps_img_direction, NorthRef, GPSImgDirectionRef, GPSImgDirection, input_refs[11], input_refs[12], props);
 
-    on_latlng!(gps_dest, GPSDestLatitudeRef, GPSDestLatitude, GPSDestLongitudeRef, GPSDestLongitude, props);
+    on_latlng!(gps_dest, set_dest_lat_lng, GPSDestLatitudeRef, GPSDestLatitude, GPSDestLongitudeRef, GPSDestLongitude, props);
     on_with_ref!(uR64, gps_dest_bearing, NorthRef, GPSDestBearingRef, GPSDestBearing, input_refs[13], input_refs[14], props);
     on_with_ref!(uR64, gps_dest_distance, GPSDestDistanceRef, GPSDestDistanceRef, GPSDestDistance, input_refs[15], input_refs[16], props);
 
@@ -273,6 +266,28 @@ pub fn gps_info(props: &InfoProps) -> Html {
             })
         })
     };
+    let overlay = props.exif.as_ref().map(|eed| {
+        let info = &eed.gps_info.location_info;
+        MapOverlay {
+            direction: info.gps_img_direction.as_ref().map(|d| d.value()),
+            track: info.gps_track.as_ref().map(|d| d.value()),
+            dest: eed.dest_position(),
+        }
+    }).unwrap_or_default();
+    let on_overlay = {
+        let exif = props.exif.clone();
+        Callback::from(move |edit: OverlayEdit| {
+            if let Some(eed) = exif.as_ref() {
+                let mut eed = eed.clone();
+                match edit {
+                    OverlayEdit::Direction(degrees) => eed.set_img_direction(degrees),
+                    OverlayEdit::Track(degrees) => eed.set_track(degrees),
+                    OverlayEdit::Dest(lat, lng) => if !eed.set_dest_from_position(lat, lng) { return; },
+                }
+                exif.set(Some(eed));
+            }
+        })
+    };
     on_enum!(GPSDifferential, gps_differential, GPSDifferential, input_refs[20], props);
     on_f64!(uR64, gps_h_positioning_error, GPSHPositioningError, input_refs[21], props);
     on_vec!(u8, gps_version_id, GPSVersionID, input_refs[22], props);
@@ -290,7 +305,9 @@ pub fn gps_info(props: &InfoProps) -> Html {
                     lat={ev!(gps_info.location_info.gps_latitude, props)}
                     lng_ref={ev!(gps_info.location_info.gps_longitude_ref, props)}
                     lng={ev!(gps_info.location_info.gps_longitude, props)}
-                    on_func={gps} />
+                    on_func={gps}
+                    {overlay}
+                    on_overlay={Some(on_overlay)} />
                 <Accordion<String>
                     name={ "GPSMapDatum" }
                     lead={Some("使用されている測地系")}
@@ -453,18 +470,18 @@ pub struct AccordionGPSProps {
     pub lat: Option<DMS>,
     pub lng_ref: Option<bool>,
     pub lng: Option<DMS>,
-    pub on_func: Callback<Mode, Callback<(f64, f64)>>
+    pub on_func: Callback<Mode, Callback<(f64, f64)>>,
+    #[prop_or_default]
+    pub overlay: MapOverlay,
+    #[prop_or_default]
+    pub on_overlay: Option<Callback<OverlayEdit>>,
 }
 
 #[function_component(AccordionGPS)]
 pub fn accordion_gps(props: &AccordionGPSProps) -> Html {
     let id_safe = props.name.replace(" ", "-").replace("/", "-");
-    let (is_open, lat, lng) = match (&props.lat_ref, &props.lat, &props.lng_ref, &props.lng) {
-        (Some(lat_ref), Some(lat), Some(lng_ref), Some(lng)) => (
-            true, 
-            if *lat_ref { lat.to_f64() } else { -lat.to_f64() },
-            if *lng_ref { lng.to_f64() } else { -lng.to_f64() },
-        ),
+    let (is_open, lat, lng) = match (&props.lat, &props.lng) {
+        (Some(lat), Some(lng)) => (true, signed_degree(props.lat_ref, lat), signed_degree(props.lng_ref, lng)),
         _ => (false, 0.0, 0.0)
     };
     let red_latlng = use_state(|| (lat, lng));
@@ -515,6 +532,42 @@ pub fn accordion_gps(props: &AccordionGPSProps) -> Html {
         })
     };
 
+    // 入力欄の座標で更新（未設定なら追加）する
+    let coord_ref = use_node_ref();
+    let coord_error = use_state(|| None::<String>);
+    let on_coord = {
+        let coord_ref = coord_ref.clone();
+        let coord_error = coord_error.clone();
+        let red_latlng = red_latlng.clone();
+        let on_base = if is_open { on_update_base.clone() } else { on_create_base.clone() };
+        Callback::from(move |_: MouseEvent| {
+            let Some(input) = coord_ref.cast::<HtmlInputElement>() else { return; };
+            match parse_coordinates(&input.value()) {
+                Ok((lat, lng)) => {
+                    red_latlng.set((lat, lng));
+                    coord_error.set(None);
+                    input.set_value("");
+                    on_base.emit((lat, lng));
+                }
+                Err(e) => coord_error.set(Some(e)),
+            }
+        })
+    };
+    let coord_input = html! {
+        <div class="mb-3">
+            <div class="input-group">
+                <input type="text" class="form-control" ref={coord_ref}
+                    placeholder={"35.6586, 139.7454 / 35°39'31.2\"N 139°42'10.5\"E / GoogleマップのURL"} />
+                <button type="button" class="btn btn-outline-primary" onclick={on_coord}>{ "座標を入力" }</button>
+            </div>
+            {
+                if let Some(msg) = (*coord_error).clone() {
+                    html! { <div class="form-text text-danger">{ msg }</div> }
+                } else { html! {} }
+            }
+        </div>
+    };
+
     html! {
         <div class="accordion-item">
             <h2 class="accordion-header" id={format!("heading-{}", id_safe.clone())}>
@@ -547,19 +600,23 @@ pub fn accordion_gps(props: &AccordionGPSProps) -> Html {
                         <p>{ format!("緯度：{:.6}", lat) }</p>
                         <p>{ format!("経度：{:.6}", lng) }</p>
                         <div class="mb-3">
-                        <MapComponent {lat} {lng} {on_marker_move} />
+                        <MapView {lat} {lng} {on_marker_move} overlay={props.overlay} on_overlay={props.on_overlay.clone()} />
                         </div>
                         <div class="d-flex justify-content-end gap-2 mb-3">
                             <button type="button" class="btn btn-primary" onclick={on_update.clone()}>{ "更新" }</button>
                             <button type="button" class="btn btn-danger" onclick={on_delete.clone()}>{ "削除" }</button>
                         </div>
+                        { coord_input }
                         </>
                     }
                 } else {
                     html! {
+                        <>
                         <div class="mb-3">
                             <button type="button" class="btn btn-primary" style="width: 100%;" onclick={on_create.clone()}>{ "追加" }</button>
                         </div>
+                        { coord_input }
+                        </>
                     }
                 } }
                 </div>
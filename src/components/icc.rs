@@ -0,0 +1,91 @@
+use yew::prelude::*;
+
+use crate::exif::ExifEditData;
+use crate::file_type::FileType;
+use crate::icc::{self, exif_mismatches, IccAction, IccProfile};
+
+#[derive(Properties, PartialEq)]
+pub struct IccProfileInfoProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>,
+    pub file_type: FileType,
+    pub file_bytes: UseStateHandle<Option<Vec<u8>>>,
+    pub icc_action: UseStateHandle<IccAction>,
+}
+
+#[function_component(IccProfileInfo)]
+pub fn icc_profile_info(props: &IccProfileInfoProps) -> Html {
+    if !icc::is_supported(props.file_type) {
+        return html! {
+            <p class="small text-muted">{ format!("{}のICCプロファイルの読み書きには対応していません。", props.file_type.label()) }</p>
+        };
+    }
+
+    let profile = (*props.file_bytes).as_ref()
+        .and_then(|bytes| icc::read(props.file_type, bytes))
+        .map(|data| data.and_then(|data| IccProfile::parse(&data)));
+
+    let on_action = {
+        let icc_action = props.icc_action.clone();
+        Callback::from(move |action: IccAction| {
+            let icc_action = icc_action.clone();
+            Callback::from(move |_: Event| icc_action.set(action))
+        })
+    };
+
+    let actions = [
+        (IccAction::Keep, "そのまま残す"),
+        (IccAction::Strip, "削除する"),
+        (IccAction::ReplaceSrgb, if profile.is_some() { "sRGBに置き換える" } else { "sRGBを埋め込む" }),
+    ];
+
+    html! {
+        <div class="card mb-3">
+        <div class="card-body">
+            <h6 class="card-title">{ "ICCプロファイル" }</h6>
+            {
+                match profile.as_ref() {
+                    None => html! { <p class="small text-muted">{ "ICCプロファイルは埋め込まれていません。" }</p> },
+                    Some(Err(e)) => html! { <div class="alert alert-warning small">{ format!("ICCプロファイルを読み込めませんでした（{}）。", e) }</div> },
+                    Some(Ok(profile)) => html! {
+                        <>
+                        <table class="table table-sm small mb-2">
+                            <tbody>
+                                <tr><th>{ "説明" }</th><td>{ profile.description.clone().unwrap_or_else(|| "-".to_string()) }</td></tr>
+                                <tr><th>{ "種類" }</th><td>{ profile.family().label() }</td></tr>
+                                <tr><th>{ "バージョン" }</th><td>{ profile.version.clone() }</td></tr>
+                                <tr><th>{ "色空間" }</th><td>{ format!("{}（PCS: {}）", profile.color_space, profile.pcs) }</td></tr>
+                                <tr><th>{ "機器クラス" }</th><td>{ profile.device_class_label() }</td></tr>
+                                <tr><th>{ "レンダリングインテント" }</th><td>{ profile.rendering_intent.label() }</td></tr>
+                                <tr><th>{ "サイズ" }</th><td>{ format!("{} バイト", profile.size) }</td></tr>
+                            </tbody>
+                        </table>
+                        {
+                            for props.exif.as_ref().map(|eed| exif_mismatches(eed, profile)).unwrap_or_default().into_iter().map(|msg| html! {
+                                <div class="alert alert-warning small py-1 mb-2">
+                                    <i class="bi bi-exclamation-triangle-fill me-1" aria-hidden="true"></i>{ msg }
+                                </div>
+                            })
+                        }
+                        </>
+                    },
+                }
+            }
+            <div class="small fw-bold mb-1">{ "ダウンロード時の扱い" }</div>
+            { for actions.iter().map(|(action, label)| html! {
+                <div class="form-check form-check-inline">
+                    <input class="form-check-input" type="radio" name="icc-action"
+                        id={format!("icc-action-{:?}", action)}
+                        checked={*props.icc_action == *action}
+                        onchange={on_action.emit(*action)} />
+                    <label class="form-check-label small" for={format!("icc-action-{:?}", action)}>{ *label }</label>
+                </div>
+            }) }
+            {
+                if *props.icc_action == IccAction::ReplaceSrgb {
+                    html! { <p class="small text-muted mt-1 mb-0">{ "画素の色は変換しません。元の色空間がsRGBでない場合は色が変わって見えます。" }</p> }
+                } else { html! {} }
+            }
+        </div>
+        </div>
+    }
+}
@@ -0,0 +1,93 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::exif::import::{parse_document, ImportReport};
+
+use super::utils::InfoProps;
+
+#[function_component(ImportMetadata)]
+pub fn import_metadata(props: &InfoProps) -> Html {
+    let report = use_state(|| None::<ImportReport>);
+    let error = use_state(|| None::<String>);
+
+    let on_change = {
+        let exif = props.exif.clone();
+        let report = report.clone();
+        let error = error.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else { return; };
+            input.set_value("");
+
+            let exif = exif.clone();
+            let report = report.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let text = match wasm_bindgen_futures::JsFuture::from(file.text()).await {
+                    Ok(v) => v.as_string().unwrap_or_default(),
+                    Err(_) => {
+                        error.set(Some("ファイルを読み込めません。".to_string()));
+                        return;
+                    }
+                };
+                match (parse_document(&text), exif.as_ref()) {
+                    (Ok(document), Some(eed)) => {
+                        let mut eed = eed.clone();
+                        let r = eed.import_entries(&document);
+                        exif.set(Some(eed));
+                        report.set(Some(r));
+                        error.set(None);
+                    }
+                    (Err(e), _) => {
+                        report.set(None);
+                        error.set(Some(e));
+                    }
+                    _ => {}
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="mb-3">
+            <label class="form-label small text-muted" for="importJson">
+                { "JSON（タグ名と値の組、本ツールのJSON、exiftool -j -nの出力）を読み込んで、現在の画像に反映できます。exiftoolの出力は、-nを付けずに書き出すと「North」のような表示用の値になり読み込めません。" }
+            </label>
+            <input id="importJson" type="file" accept=".json,application/json" class="form-control form-control-sm" onchange={on_change} />
+            {
+                if let Some(msg) = (*error).clone() {
+                    html! { <div class="alert alert-danger small mt-2 mb-0">{ msg }</div> }
+                } else { html! {} }
+            }
+            {
+                if let Some(r) = (*report).clone() {
+                    html! {
+                        <div class="alert alert-info small mt-2 mb-0">
+                            <p class="mb-1">{ r.summary() }</p>
+                            { for r.notes().iter().map(|note| html! { <p class="mb-1 text-warning-emphasis">{ note.clone() }</p> }) }
+                            {
+                                if r.invalid.is_empty() { html! {} } else {
+                                    html! {
+                                        <>
+                                        <p class="fw-bold mb-0">{ "値を読み取れなかったタグ" }</p>
+                                        <ul class="mb-1">
+                                        { for r.invalid.iter().map(|(name, value)| html! { <li>{ format!("{}: {}", name, value) }</li> }) }
+                                        </ul>
+                                        </>
+                                    }
+                                }
+                            }
+                            {
+                                if r.unsupported.is_empty() { html! {} } else {
+                                    html! {
+                                        <p class="mb-0 text-break">{ format!("未対応のタグ: {}", r.unsupported.join(", ")) }</p>
+                                    }
+                                }
+                            }
+                        </div>
+                    }
+                } else { html! {} }
+            }
+        </div>
+    }
+}
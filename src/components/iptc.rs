@@ -0,0 +1,195 @@
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::file_type::FileType;
+use crate::iptc::{is_supported, IptcData, IptcField, FIELDS, KEYWORDS};
+
+#[derive(Properties, PartialEq)]
+pub struct IptcInfoProps {
+    pub iptc: UseStateHandle<Option<IptcData>>,
+    pub file_type: FileType,
+    // 読み込み時にIPTCを解析できなかった場合の理由
+    pub parse_error: Option<String>,
+}
+
+#[function_component(IptcInfo)]
+pub fn iptc_info(props: &IptcInfoProps) -> Html {
+    let error = use_state(|| None::<String>);
+    let keyword_ref = use_node_ref();
+
+    // IPTCがなければ空のデータから編集を始める
+    let on_set = {
+        let iptc = props.iptc.clone();
+        let error = error.clone();
+        Callback::from(move |(field, values): (IptcField, Vec<String>)| {
+            let mut data = (*iptc).clone().unwrap_or_default();
+            match data.set(&field, &values) {
+                Ok(()) => {
+                    iptc.set(Some(data));
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        })
+    };
+
+    let keywords = props.iptc.as_ref().map(|data| data.get(&KEYWORDS)).unwrap_or_default();
+
+    let on_add_keyword = {
+        let keyword_ref = keyword_ref.clone();
+        let keywords = keywords.clone();
+        let on_set = on_set.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(input) = keyword_ref.cast::<HtmlInputElement>() else { return; };
+            let mut values = keywords.clone();
+            // カンマ区切りでまとめて追加できるようにする
+            for keyword in input.value().split([',', '、']).map(|k| k.trim()).filter(|k| !k.is_empty()) {
+                if !values.iter().any(|v| v == keyword) {
+                    values.push(keyword.to_string());
+                }
+            }
+            on_set.emit((KEYWORDS, values));
+            input.set_value("");
+        })
+    };
+
+    let on_remove_keyword = {
+        let keywords = keywords.clone();
+        let on_set = on_set.clone();
+        Callback::from(move |index: usize| {
+            let keywords = keywords.clone();
+            let on_set = on_set.clone();
+            Callback::from(move |_: MouseEvent| {
+                let mut values = keywords.clone();
+                if index < values.len() {
+                    values.remove(index);
+                }
+                on_set.emit((KEYWORDS, values));
+            })
+        })
+    };
+
+    let on_delete_all = {
+        let iptc = props.iptc.clone();
+        Callback::from(move |_: MouseEvent| iptc.set(None))
+    };
+
+    if !is_supported(props.file_type) {
+        return html! {
+            <div class="tab-content border border-top-0 p-3">
+                <p class="text-muted mb-0">{ format!("{}のIPTCの読み書きには対応していません。", props.file_type.label()) }</p>
+            </div>
+        };
+    }
+
+    html! {
+        <div class="tab-content border border-top-0 p-3">
+        {
+            if let Some(e) = props.parse_error.as_ref().filter(|_| props.iptc.as_ref().map(|d| d.is_empty()).unwrap_or(false)) {
+                html! {
+                    <div class="alert alert-warning small">
+                        { format!("IPTCを解析できませんでした（{}）。削除しない限り、ダウンロード時はそのまま残します。", e) }
+                    </div>
+                }
+            } else { html! {} }
+        }
+        {
+            if let Some(msg) = (*error).clone() {
+                html! { <div class="alert alert-danger small">{ msg }</div> }
+            } else { html! {} }
+        }
+        <p class="small text-muted">
+            { "APP13（Photoshop IRB）に保存されたIPTC-IIMの項目です。保存時の文字コードはUTF-8になります。" }
+        </p>
+
+        <div class="mb-3">
+            <label class="form-label small fw-bold mb-1">{ format!("{} ({})", KEYWORDS.label, KEYWORDS.name) }</label>
+            <div class="d-flex flex-wrap gap-1 mb-2">
+            {
+                if keywords.is_empty() {
+                    html! { <span class="text-muted small">{ "キーワードはありません。" }</span> }
+                } else { html! {} }
+            }
+            { for keywords.iter().enumerate().map(|(i, keyword)| html! {
+                <span class="badge text-bg-secondary d-inline-flex align-items-center">
+                    { keyword.clone() }
+                    <button type="button" class="btn-close btn-close-white ms-1" style="font-size: 0.5rem;"
+                        aria-label="削除" onclick={on_remove_keyword.emit(i)}></button>
+                </span>
+            }) }
+            </div>
+            <div class="input-group input-group-sm">
+                <input type="text" class="form-control" placeholder="キーワード（カンマ区切りで複数）" ref={keyword_ref} />
+                <button type="button" class="btn btn-outline-primary" onclick={on_add_keyword}>{ "追加" }</button>
+            </div>
+        </div>
+
+        { for FIELDS.iter().filter(|f| **f != KEYWORDS).map(|field| html! {
+            <IptcFieldRow key={field.name} field={*field}
+                values={props.iptc.as_ref().map(|data| data.get(field)).unwrap_or_default()}
+                on_set={on_set.clone()} />
+        }) }
+
+        <button type="button" class="btn btn-danger w-100 mt-2" disabled={props.iptc.is_none()} onclick={on_delete_all}>
+            { "IPTCをすべて削除" }
+        </button>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct IptcFieldRowProps {
+    field: IptcField,
+    values: Vec<String>,
+    on_set: Callback<(IptcField, Vec<String>)>,
+}
+
+#[function_component(IptcFieldRow)]
+fn iptc_field_row(props: &IptcFieldRowProps) -> Html {
+    let input_ref = use_node_ref();
+    let field = props.field;
+    let multiline = field.repeatable || field.max_len > 256;
+    let text = props.values.join("\n");
+
+    let on_update = {
+        let input_ref = input_ref.clone();
+        let on_set = props.on_set.clone();
+        Callback::from(move |_: MouseEvent| {
+            let value = input_ref.cast::<HtmlTextAreaElement>().map(|t| t.value())
+                .or_else(|| input_ref.cast::<HtmlInputElement>().map(|i| i.value()));
+            let Some(value) = value else { return; };
+            let values = if field.repeatable {
+                value.lines().map(|l| l.to_string()).collect()
+            } else {
+                vec![value]
+            };
+            on_set.emit((field, values));
+        })
+    };
+    let on_delete = {
+        let on_set = props.on_set.clone();
+        Callback::from(move |_: MouseEvent| on_set.emit((field, Vec::new())))
+    };
+
+    html! {
+        <div class="mb-2">
+            <label class="form-label small fw-bold mb-1">
+                { format!("{} ({})", field.label, field.name) }
+                <span class="text-muted fw-normal ms-1">
+                { if field.repeatable { format!("（1行に1項目、各{}バイトまで）", field.max_len) } else { format!("（{}バイトまで）", field.max_len) } }
+                </span>
+            </label>
+            <div class="input-group input-group-sm">
+                {
+                    if multiline {
+                        html! { <textarea class="form-control" rows={text.lines().count().clamp(1, 5).to_string()} ref={input_ref} value={text.clone()} /> }
+                    } else {
+                        html! { <input type="text" class="form-control" ref={input_ref} value={text.clone()} /> }
+                    }
+                }
+                <button type="button" class="btn btn-outline-primary" onclick={on_update}>{ "更新" }</button>
+                <button type="button" class="btn btn-outline-danger" disabled={props.values.is_empty()} onclick={on_delete}>{ "削除" }</button>
+            </div>
+        </div>
+    }
+}
@@ -1,22 +1,218 @@
-use gloo_utils::document;
-use wasm_bindgen::{prelude::Closure, JsCast};
-use web_sys::{Element, HtmlElement, Node};
+use base64::Engine;
+use gloo_utils::{document, window};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use web_sys::{js_sys::{self, Array}, Element, HtmlElement, HtmlInputElement, HtmlSelectElement, Node};
 use yew::prelude::*;
 
-use leaflet::{DragEndEvent, Icon, IconOptions, LatLng, Map, MapOptions, Marker, MarkerOptions, TileLayer};
+use leaflet::{
+    DragEndEvent, Icon, IconOptions, LatLng, Map, MapOptions, Marker, MarkerOptions,
+    MouseEvent as MapMouseEvent, Point, Polyline, PolylineOptions, TileLayer,
+};
 
-pub enum Msg {}
+use crate::exif::gps::bearing;
+
+use super::svg_map::SvgMap;
+
+const TILE_URL_KEY: &str = "exif-edit-tile-url";
+pub const OSM_TILE_URL: &str = "https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png";
+
+pub const YELLOW: &str = "#ffc107";
+pub const RED: &str = "#dc3545";
+
+// 外部のサーバーに頼らないよう、マーカーの画像はSVGで作る
+pub fn marker_icon(color: &str) -> String {
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="32" height="32" viewBox="0 0 32 32"><path d="M16 31C16 31 5 18.5 5 11.5a11 11 0 0 1 22 0C27 18.5 16 31 16 31z" fill="{}" stroke="#333" stroke-width="1.5"/><circle cx="16" cy="11.5" r="4" fill="#fff"/></svg>"##,
+        color
+    );
+    format!("data:image/svg+xml;base64,{}", base64::engine::general_purpose::STANDARD.encode(svg))
+}
+
+// 未設定なら地図タイルを使わない
+pub fn load_tile_url() -> Option<String> {
+    let storage = window().local_storage().ok()??;
+    storage.get_item(TILE_URL_KEY).ok()?.filter(|url| !url.trim().is_empty())
+}
+
+pub fn save_tile_url(url: Option<&str>) {
+    if let Ok(Some(storage)) = window().local_storage() {
+        let _ = match url {
+            Some(url) => storage.set_item(TILE_URL_KEY, url),
+            None => storage.remove_item(TILE_URL_KEY),
+        };
+    }
+}
+
+pub fn is_tile_url(url: &str) -> bool {
+    ["{z}", "{x}", "{y}"].iter().all(|p| url.contains(p))
+}
+
+// オフラインなどでLeafletを読み込めていなければfalse
+fn leaflet_loaded() -> bool {
+    js_sys::Reflect::has(&window(), &JsValue::from_str("L")).unwrap_or(false)
+}
+
+const ARROW_WING_LENGTH_RATE: f64 = 0.9;
+const ARROW_WING_ANGLE: f64 = 0.1;
+
+pub const DIRECTION_COLOR: &str = "#0d6efd";
+pub const TRACK_COLOR: &str = "#198754";
+pub const DEST_COLOR: &str = "#6f42c1";
+
+// 撮影場所に重ねて表示する向きと目的地
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct MapOverlay {
+    pub direction: Option<f64>,
+    pub track: Option<f64>,
+    pub dest: Option<(f64, f64)>,
+}
+
+// 地図上をクリック、またはハンドルをドラッグしたときに変更するもの
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayKind {
+    #[default]
+    Position,
+    Direction,
+    Track,
+    Dest,
+}
+
+impl OverlayKind {
+    pub fn all() -> [Self; 4] {
+        [OverlayKind::Position, OverlayKind::Direction, OverlayKind::Track, OverlayKind::Dest]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OverlayKind::Position => "撮影場所",
+            OverlayKind::Direction => "カメラの向き",
+            OverlayKind::Track => "移動方向",
+            OverlayKind::Dest => "目的地",
+        }
+    }
+
+    pub fn value(&self) -> &'static str {
+        match self {
+            OverlayKind::Position => "position",
+            OverlayKind::Direction => "direction",
+            OverlayKind::Track => "track",
+            OverlayKind::Dest => "dest",
+        }
+    }
+
+    pub fn from_value(s: &str) -> Self {
+        Self::all().into_iter().find(|k| k.value() == s).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OverlayEdit {
+    Direction(f64),
+    Track(f64),
+    Dest(f64, f64),
+}
+
+// 撮影場所から見たpointの方角、または目的地そのものを編集内容にする
+pub fn overlay_edit(kind: OverlayKind, position: (f64, f64), point: (f64, f64)) -> Option<OverlayEdit> {
+    match kind {
+        OverlayKind::Position => None,
+        OverlayKind::Direction => Some(OverlayEdit::Direction(bearing(position, point))),
+        OverlayKind::Track => Some(OverlayEdit::Track(bearing(position, point))),
+        OverlayKind::Dest => Some(OverlayEdit::Dest(point.0, point.1)),
+    }
+}
+
+pub fn handle_icon(color: &str) -> String {
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="14" height="14" viewBox="0 0 14 14"><circle cx="7" cy="7" r="5.5" fill="#fff" stroke="{}" stroke-width="3"/></svg>"##,
+        color
+    );
+    format!("data:image/svg+xml;base64,{}", base64::engine::general_purpose::STANDARD.encode(svg))
+}
+
+fn make_arrow(map: &Map, marker_pos: &LatLng, arrow_length: f64, azimuth_rad: f64) -> Array {
+    let dx = arrow_length * azimuth_rad.sin();
+    let dy = -arrow_length * azimuth_rad.cos();
+    let arrow_wing_length = arrow_length * ARROW_WING_LENGTH_RATE;
+    let dx_r = arrow_wing_length * (azimuth_rad + ARROW_WING_ANGLE).sin();
+    let dy_r = -arrow_wing_length * (azimuth_rad + ARROW_WING_ANGLE).cos();
+    let dx_l = arrow_wing_length * (azimuth_rad - ARROW_WING_ANGLE).sin();
+    let dy_l = -arrow_wing_length * (azimuth_rad - ARROW_WING_ANGLE).cos();
+
+    let marker_container = map.lat_lng_to_container_point(marker_pos);
+    let arrow_tip_container = Point::new(
+        marker_container.x() + dx,
+        marker_container.y() + dy,
+    );
+    let arrow_tip_latlng = map.container_point_to_lat_lng(&arrow_tip_container);
+    let arrow_coords = Array::new();
+    arrow_coords.push(marker_pos);
+    arrow_coords.push(&arrow_tip_latlng);
+    arrow_coords.push(
+        &map.container_point_to_lat_lng(&Point::new(
+            marker_container.x() + dx_r,
+            marker_container.y() + dy_r
+        ))
+    );
+    arrow_coords.push(&arrow_tip_latlng);
+    arrow_coords.push(
+        &map.container_point_to_lat_lng(&Point::new(
+            marker_container.x() + dx_l,
+            marker_container.y() + dy_l
+        ))
+    );
+    arrow_coords
+}
+
+fn icon(url: String, size: f64, anchor: (f64, f64)) -> Icon {
+    let options = IconOptions::new();
+    options.set_icon_url(url);
+    options.set_icon_size((size, size).into());
+    options.set_icon_anchor(anchor.into());
+    Icon::new(&options)
+}
+
+fn polyline(coords: &Array, color: &str, dashed: bool) -> Polyline {
+    let options = PolylineOptions::new();
+    options.set_color(color.to_string());
+    options.set_weight(3.0);
+    if dashed {
+        options.set_dash_array("6 6".to_string());
+    }
+    Polyline::new_with_options(coords, &options)
+}
+
+pub enum Msg {
+    Redraw,
+    Click(f64, f64),
+    Point(OverlayKind, f64, f64),
+}
 
 #[derive(PartialEq, Properties)]
 pub struct Props {
     pub lat: f64,
     pub lng: f64,
+    pub tile_url: String,
     pub on_marker_move: Callback<(f64, f64)>,
+    #[prop_or_default]
+    pub overlay: MapOverlay,
+    #[prop_or_default]
+    pub click_mode: OverlayKind,
+    #[prop_or_default]
+    pub on_overlay: Callback<OverlayEdit>,
 }
 
+type DragHandler = Closure<dyn FnMut(DragEndEvent)>;
+
 pub struct MapComponent {
     map: Option<Map>,
-    red_marker: Option<Marker>,
+    yellow_marker: Option<Marker>,
+    // イベントのClosureはforgetせず、マーカーや地図と一緒に持っておき、消すときに破棄する
+    red_marker: Option<(Marker, DragHandler)>,
+    overlay_lines: Vec<Polyline>,
+    overlay_markers: Vec<(Marker, DragHandler)>,
+    zoomend_handler: Option<Closure<dyn FnMut(JsValue)>>,
+    click_handler: Option<Closure<dyn FnMut(MapMouseEvent)>>,
     latlng: LatLng,
     container: HtmlElement,
 }
@@ -28,13 +224,80 @@ impl MapComponent {
     }
 
     fn update_view(&mut self, ctx: &Context<MapComponent>) {
-        if let (Some(map), Some(red_marker)) = (&self.map, &self.red_marker) {
+        if let (Some(map), Some(yellow_marker), Some((red_marker, _))) = (&self.map, &self.yellow_marker, &self.red_marker) {
             let props = ctx.props();
             let marker_pos = LatLng::new(props.lat, props.lng);
+            yellow_marker.set_lat_lng(&marker_pos);
             red_marker.set_lat_lng(&marker_pos);
             map.set_view(&marker_pos, 11.0);
         }
     }
+
+    // ドラッグの終了をMsg::Pointとして受け取る
+    fn draggable_marker(ctx: &Context<Self>, pos: &LatLng, icon: Icon, kind: OverlayKind) -> (Marker, DragHandler) {
+        let options = MarkerOptions::new();
+        options.set_draggable(true);
+        options.set_interactive(true);
+        options.set_icon(icon);
+        let marker = Marker::new_with_options(pos, &options);
+
+        let link = ctx.link().clone();
+        let marker_clone = marker.clone();
+        let dragend_cb = Closure::wrap(Box::new(move |_: DragEndEvent| {
+            let new_pos = marker_clone.get_lat_lng();
+            link.send_message(Msg::Point(kind, new_pos.lat(), new_pos.lng()));
+        }) as Box<dyn FnMut(DragEndEvent)>);
+        marker.on("dragend", dragend_cb.as_ref().unchecked_ref());
+        (marker, dragend_cb)
+    }
+
+    // 矢印の長さは画面上で一定にするため、ズームのたびに描き直す
+    fn draw_overlay(&mut self, ctx: &Context<Self>) {
+        for line in self.overlay_lines.drain(..) {
+            line.remove();
+        }
+        // 地図から外したマーカーにはイベントが届かないため、ここでClosureを破棄してよい
+        for (marker, _) in self.overlay_markers.drain(..) {
+            marker.remove();
+        }
+        let Some(map) = self.map.clone() else { return; };
+        let props = ctx.props();
+        let pos = LatLng::new(props.lat, props.lng);
+        let arrow_length = map.get_size().y() * 0.3;
+
+        for (kind, azimuth, color) in [
+            (OverlayKind::Track, props.overlay.track, TRACK_COLOR),
+            (OverlayKind::Direction, props.overlay.direction, DIRECTION_COLOR),
+        ] {
+            let Some(azimuth) = azimuth else { continue; };
+            let arrow = polyline(&make_arrow(&map, &pos, arrow_length, azimuth.to_radians()), color, kind == OverlayKind::Track);
+            arrow.add_to(&map);
+            self.overlay_lines.push(arrow);
+
+            let container = map.lat_lng_to_container_point(&pos);
+            let tip = map.container_point_to_lat_lng(&Point::new(
+                container.x() + arrow_length * azimuth.to_radians().sin(),
+                container.y() - arrow_length * azimuth.to_radians().cos(),
+            ));
+            let (handle, handler) = Self::draggable_marker(ctx, &tip, icon(handle_icon(color), 14.0, (7.0, 7.0)), kind);
+            handle.add_to(&map);
+            self.overlay_markers.push((handle, handler));
+        }
+
+        if let Some((lat, lng)) = props.overlay.dest {
+            let dest = LatLng::new(lat, lng);
+            let coords = Array::new();
+            coords.push(&pos);
+            coords.push(&dest);
+            let line = polyline(&coords, DEST_COLOR, true);
+            line.add_to(&map);
+            self.overlay_lines.push(line);
+
+            let (marker, handler) = Self::draggable_marker(ctx, &dest, icon(marker_icon(DEST_COLOR), 32.0, (16.0, 32.0)), OverlayKind::Dest);
+            marker.add_to(&map);
+            self.overlay_markers.push((marker, handler));
+        }
+    }
 }
 
 impl Component for MapComponent {
@@ -49,7 +312,12 @@ impl Component for MapComponent {
 
         Self {
             map: None,
+            yellow_marker: None,
             red_marker: None,
+            overlay_lines: Vec::new(),
+            overlay_markers: Vec::new(),
+            zoomend_handler: None,
+            click_handler: None,
             latlng: LatLng::new(props.lat, props.lng),
             container
         }
@@ -60,48 +328,61 @@ impl Component for MapComponent {
             let map = Map::new_with_element(&self.container, &MapOptions::default());
             map.set_view(&self.latlng, 11.0);
 
-            TileLayer::new("https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png")
+            TileLayer::new(&ctx.props().tile_url)
                 .add_to(&map);
 
-            let yellow_icon_options = IconOptions::new();
-            yellow_icon_options.set_icon_url("https://maps.gstatic.com/mapfiles/ms2/micons/yellow-dot.png".to_string());
-            yellow_icon_options.set_icon_size((32.0, 32.0).into());
-            yellow_icon_options.set_icon_anchor((16.0, 32.0).into());
-            let yellow_icon = Icon::new(&yellow_icon_options);
             let yellow_marker_options = MarkerOptions::new();
-            yellow_marker_options.set_icon(yellow_icon);
-            Marker::new_with_options(&self.latlng, &yellow_marker_options).add_to(&map);
-
-            let red_icon_options = IconOptions::new();
-            red_icon_options.set_icon_url("https://maps.gstatic.com/mapfiles/ms2/micons/red-dot.png".to_string());
-            red_icon_options.set_icon_size((32.0, 32.0).into());
-            red_icon_options.set_icon_anchor((16.0, 32.0).into());
-            let red_icon = Icon::new(&red_icon_options);
-            let red_marker_options = MarkerOptions::new();
-            red_marker_options.set_draggable(true);
-            red_marker_options.set_interactive(true);
-            red_marker_options.set_icon(red_icon);
-
-            let red_marker = Marker::new_with_options(&self.latlng, &red_marker_options);
-
-            let on_marker_move = ctx.props().on_marker_move.clone();
-            let red_marker_clone = red_marker.clone();
-
-            let dragend_cb = Closure::wrap(Box::new(move |_: DragEndEvent| {
-                let new_pos = red_marker_clone.get_lat_lng();
-                on_marker_move.emit((new_pos.lat(), new_pos.lng()));
-            }) as Box<dyn FnMut(DragEndEvent)>);
-            red_marker.on("dragend", dragend_cb.as_ref().unchecked_ref());
-            dragend_cb.forget();
+            yellow_marker_options.set_icon(icon(marker_icon(YELLOW), 32.0, (16.0, 32.0)));
+            let yellow_marker = Marker::new_with_options(&self.latlng, &yellow_marker_options);
+            yellow_marker.add_to(&map);
 
+            let (red_marker, red_handler) = Self::draggable_marker(ctx, &self.latlng, icon(marker_icon(RED), 32.0, (16.0, 32.0)), OverlayKind::Position);
             red_marker.add_to(&map);
 
+            let link = ctx.link().clone();
+            let zoomend_cb = Closure::wrap(Box::new(move |_: JsValue| {
+                link.send_message(Msg::Redraw);
+            }) as Box<dyn FnMut(JsValue)>);
+            map.on("zoomend", zoomend_cb.as_ref().unchecked_ref());
+
+            let link = ctx.link().clone();
+            let click_cb = Closure::wrap(Box::new(move |e: MapMouseEvent| {
+                let pos = e.lat_lng();
+                link.send_message(Msg::Click(pos.lat(), pos.lng()));
+            }) as Box<dyn FnMut(MapMouseEvent)>);
+            map.on("click", click_cb.as_ref().unchecked_ref());
+
             self.map = Some(map);
-            self.red_marker = Some(red_marker);
+            self.yellow_marker = Some(yellow_marker);
+            self.red_marker = Some((red_marker, red_handler));
+            self.zoomend_handler = Some(zoomend_cb);
+            self.click_handler = Some(click_cb);
+            self.draw_overlay(ctx);
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, _msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let props = ctx.props();
+        match msg {
+            Msg::Redraw => self.draw_overlay(ctx),
+            // 撮影場所は赤いマーカーのドラッグで動かすため、クリックでは変えない
+            Msg::Click(lat, lng) => {
+                if let Some(edit) = overlay_edit(props.click_mode, (props.lat, props.lng), (lat, lng)) {
+                    props.on_overlay.emit(edit);
+                }
+            }
+            Msg::Point(kind, lat, lng) => {
+                match overlay_edit(kind, (props.lat, props.lng), (lat, lng)) {
+                    Some(edit) => props.on_overlay.emit(edit),
+                    None => {
+                        if let Some((red_marker, _)) = &self.red_marker {
+                            red_marker.set_lat_lng(&LatLng::new(lat, lng));
+                        }
+                        props.on_marker_move.emit((lat, lng));
+                    }
+                }
+            }
+        }
         false
     }
 
@@ -109,13 +390,25 @@ impl Component for MapComponent {
         let new_pos = (ctx.props().lat, ctx.props().lng);
         let old_pos = (old_props.lat, old_props.lng);
 
-        if new_pos.0 != old_pos.0 && new_pos.1 != old_pos.1 {
+        if new_pos != old_pos {
             self.update_view(ctx);
         }
+        if new_pos != old_pos || ctx.props().overlay != old_props.overlay {
+            self.draw_overlay(ctx);
+        }
         
         false
     }
 
+    // 地図を先に破棄し、Closureが呼ばれないようにしてから手放す
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        if let Some(map) = self.map.take() {
+            map.remove();
+        }
+        drop(self.zoomend_handler.take());
+        drop(self.click_handler.take());
+    }
+
     fn view(&self, _ctx: &Context<Self>) -> Html {
         html! {
             <div class="map-container component-container">
@@ -123,4 +416,128 @@ impl Component for MapComponent {
             </div>
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(PartialEq, Properties)]
+pub struct MapViewProps {
+    pub lat: f64,
+    pub lng: f64,
+    pub on_marker_move: Callback<(f64, f64)>,
+    #[prop_or_default]
+    pub overlay: MapOverlay,
+    // 指定すると、向きや目的地を地図上で編集できる
+    #[prop_or_default]
+    pub on_overlay: Option<Callback<OverlayEdit>>,
+}
+
+// タイルのURLが設定されていればLeafletの地図を、なければSVGの簡易表示を使う
+#[function_component(MapView)]
+pub fn map_view(props: &MapViewProps) -> Html {
+    let tile_url = use_state(load_tile_url);
+    let click_mode = use_state(OverlayKind::default);
+    let mode_ref = use_node_ref();
+    let url_ref = use_node_ref();
+    let url_error = use_state(|| None::<String>);
+
+    let set_url = {
+        let tile_url = tile_url.clone();
+        let url_error = url_error.clone();
+        Callback::from(move |url: Option<String>| {
+            save_tile_url(url.as_deref());
+            tile_url.set(url);
+            url_error.set(None);
+        })
+    };
+
+    let on_save = {
+        let set_url = set_url.clone();
+        let url_ref = url_ref.clone();
+        let url_error = url_error.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(input) = url_ref.cast::<HtmlInputElement>() else { return; };
+            let url = input.value().trim().to_string();
+            if url.is_empty() {
+                set_url.emit(None);
+            } else if is_tile_url(&url) {
+                set_url.emit(Some(url));
+            } else {
+                url_error.set(Some("URLには{z}, {x}, {y}を含めてください。".to_string()));
+            }
+        })
+    };
+
+    let on_osm = {
+        let set_url = set_url.clone();
+        Callback::from(move |_: MouseEvent| set_url.emit(Some(OSM_TILE_URL.to_string())))
+    };
+
+    let on_none = {
+        let set_url = set_url.clone();
+        Callback::from(move |_: MouseEvent| set_url.emit(None))
+    };
+
+    let on_mode_change = {
+        let click_mode = click_mode.clone();
+        let mode_ref = mode_ref.clone();
+        Callback::from(move |_: Event| {
+            if let Some(select) = mode_ref.cast::<HtmlSelectElement>() {
+                click_mode.set(OverlayKind::from_value(&select.value()));
+            }
+        })
+    };
+
+    let (lat, lng, on_marker_move) = (props.lat, props.lng, props.on_marker_move.clone());
+    let (overlay, click_mode_value) = (props.overlay, *click_mode);
+    let on_overlay = props.on_overlay.clone().unwrap_or_default();
+    html! {
+        <>
+        {
+            match (*tile_url).clone() {
+                Some(url) if leaflet_loaded() => html! {
+                    <MapComponent key={url.clone()} {lat} {lng} tile_url={url} {on_marker_move}
+                        {overlay} click_mode={click_mode_value} {on_overlay} />
+                },
+                _ => html! {
+                    <SvgMap {lat} {lng} {on_marker_move} {overlay} click_mode={click_mode_value} {on_overlay} />
+                },
+            }
+        }
+        {
+            if props.on_overlay.is_some() {
+                html! {
+                    <div class="input-group input-group-sm mt-2">
+                        <span class="input-group-text">{ "クリックで設定" }</span>
+                        <select class="form-select" ref={mode_ref} onchange={on_mode_change}>
+                        { for OverlayKind::all().into_iter().map(|k| html! {
+                            <option value={k.value()} selected={k == *click_mode}>{ k.label() }</option>
+                        }) }
+                        </select>
+                    </div>
+                }
+            } else { html! {} }
+        }
+        <details class="mt-2">
+            <summary class="small">{ "地図の設定" }</summary>
+            <div class="mt-2">
+                <p class="small text-muted mb-2">
+                    { "地図タイルを取得するとき、表示している位置がタイルのサーバーに伝わります。ローカルのタイルサーバーも指定できます（例: http://localhost:8080/{z}/{x}/{y}.png）。" }
+                </p>
+                <div class="input-group input-group-sm mb-2">
+                    <input type="text" class="form-control" ref={url_ref}
+                        value={(*tile_url).clone().unwrap_or_default()} placeholder="https://example.com/{z}/{x}/{y}.png" />
+                    <button type="button" class="btn btn-outline-primary" onclick={on_save}>{ "保存" }</button>
+                </div>
+                <div class="d-flex gap-2">
+                    <button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_osm}>{ "OpenStreetMapを使う" }</button>
+                    <button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_none}>{ "地図を使わない" }</button>
+                </div>
+                {
+                    if let Some(msg) = (*url_error).clone() {
+                        html! { <div class="form-text text-danger">{ msg }</div> }
+                    } else { html! {} }
+                }
+            </div>
+        </details>
+        </>
+    }
+}
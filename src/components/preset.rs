@@ -0,0 +1,159 @@
+use little_exif::exif_tag::ExifTag;
+use yew::prelude::*;
+
+use crate::exif::ExifEditData;
+use crate::exif::preset::ScrubPreset;
+use crate::exif::tag_value::value_string;
+use crate::iptc::IptcData;
+use crate::xmp::XmpPacket;
+
+#[derive(Properties, PartialEq)]
+pub struct ScrubPresetsProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>,
+    pub xmp: UseStateHandle<Option<XmpPacket>>,
+    pub iptc: UseStateHandle<Option<IptcData>>,
+}
+
+// XMP・IPTCの項目を、Exifのタグの一覧と同じ形で並べる
+fn target_items(title: &str, items: Vec<(String, String)>) -> Html {
+    if items.is_empty() {
+        return html! {};
+    }
+    html! {
+        <>
+        <p class="mt-3 mb-1 fw-bold">{ title }</p>
+        <ul class="list-group small">
+        { for items.into_iter().map(|(name, value)| html! {
+            <li class="list-group-item">
+                <span class="fw-bold">{ name }</span>
+                <span class="text-muted text-break">{ format!(": {}", value) }</span>
+            </li>
+        }) }
+        </ul>
+        </>
+    }
+}
+
+#[function_component(ScrubPresets)]
+pub fn scrub_presets(props: &ScrubPresetsProps) -> Html {
+    let pending = use_state(|| None::<ScrubPreset>);
+
+    let on_select = {
+        let pending = pending.clone();
+        Callback::from(move |preset: ScrubPreset| {
+            let pending = pending.clone();
+            Callback::from(move |_: MouseEvent| pending.set(Some(preset)))
+        })
+    };
+
+    let on_cancel = {
+        let pending = pending.clone();
+        Callback::from(move |_: MouseEvent| pending.set(None))
+    };
+
+    let on_apply = {
+        let pending = pending.clone();
+        let exif = props.exif.clone();
+        let xmp = props.xmp.clone();
+        let iptc = props.iptc.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let (Some(preset), Some(eed)) = (*pending, exif.as_ref()) {
+                let mut eed = eed.clone();
+                eed.apply_preset(preset);
+                exif.set(Some(eed));
+                // XMPやIPTCにも同じ情報が残っていることが多いため、合わせて削除する
+                if let Some(packet) = xmp.as_ref() {
+                    let mut packet = packet.clone();
+                    if preset.apply_xmp(&mut packet) > 0 {
+                        xmp.set(Some(packet));
+                    }
+                }
+                if let Some(data) = iptc.as_ref() {
+                    let mut data = data.clone();
+                    if preset.apply_iptc(&mut data) > 0 {
+                        iptc.set(Some(data));
+                    }
+                }
+            }
+            pending.set(None);
+        })
+    };
+
+    let dialog = match (*pending, props.exif.as_ref()) {
+        (Some(preset), Some(eed)) => {
+            let targets = eed.preset_targets(preset);
+            let maker_note_hits = eed.maker_note_hits(preset);
+            let xmp_targets = props.xmp.as_ref().map(|packet| preset.xmp_targets(packet)).unwrap_or_default();
+            let iptc_targets = props.iptc.as_ref().map(|data| preset.iptc_targets(data)).unwrap_or_default();
+            let total = targets.len() + xmp_targets.len() + iptc_targets.len();
+            html! {
+                <>
+                <div class="modal d-block" tabindex="-1" role="dialog" aria-modal="true">
+                    <div class="modal-dialog modal-dialog-scrollable">
+                        <div class="modal-content">
+                            <div class="modal-header">
+                                <h5 class="modal-title">{ preset.name() }</h5>
+                                <button type="button" class="btn-close" aria-label="Close" onclick={on_cancel.clone()}></button>
+                            </div>
+                            <div class="modal-body">
+                                <p class="small text-muted">{ preset.description() }</p>
+                                {
+                                    if total == 0 {
+                                        html! { <p class="mb-0">{ "削除されるタグはありません。" }</p> }
+                                    } else {
+                                        html! {
+                                            <>
+                                            <p>{ format!("次の{}件のタグが削除されます。", total) }</p>
+                                            <ul class="list-group small">
+                                            { for targets.iter().map(|(key, tag)| html! {
+                                                <li class="list-group-item">
+                                                    <span class="fw-bold">{ key.label() }</span>
+                                                    <span class="text-muted text-break">{ format!(": {}", value_string(tag)) }</span>
+                                                    {
+                                                        if matches!(tag, ExifTag::MakerNote(_)) && !maker_note_hits.is_empty() {
+                                                            html! {
+                                                                <div class="text-warning">
+                                                                    <i class="bi bi-exclamation-triangle-fill me-1" aria-hidden="true"></i>
+                                                                    { format!("次の項目を含みます: {}", maker_note_hits.iter().map(|f| f.name.clone()).collect::<Vec<String>>().join(", ")) }
+                                                                </div>
+                                                            }
+                                                        } else { html! {} }
+                                                    }
+                                                </li>
+                                            }) }
+                                            </ul>
+                                            { target_items("XMP", xmp_targets.iter().map(|p| (p.qualified_name(), p.value.to_text())).collect()) }
+                                            { target_items("IPTC", iptc_targets.iter().map(|d| (d.name(), String::from_utf8_lossy(&d.data).to_string())).collect()) }
+                                            </>
+                                        }
+                                    }
+                                }
+                            </div>
+                            <div class="modal-footer">
+                                <button type="button" class="btn btn-secondary" onclick={on_cancel.clone()}>{ "キャンセル" }</button>
+                                <button type="button" class="btn btn-danger" disabled={total == 0} onclick={on_apply}>{ "削除する" }</button>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+                <div class="modal-backdrop show"></div>
+                </>
+            }
+        }
+        _ => html! {},
+    };
+
+    html! {
+        <>
+        <div class="d-flex flex-wrap gap-2 justify-content-center">
+        { for ScrubPreset::all().iter().map(|preset| html! {
+            <button type="button" class="btn btn-outline-danger btn-sm" onclick={on_select.emit(*preset)}>
+                <i class="bi bi-shield-lock me-1" aria-hidden="true"></i>
+                { preset.name() }
+            </button>
+        }) }
+        </div>
+        { dialog }
+        </>
+    }
+}
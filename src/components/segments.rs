@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+
+use yew::prelude::*;
+
+use crate::file_type::FileType;
+use crate::jpeg::{inspect, mpf_conflicts, strip_segments, SegmentKind};
+
+#[derive(Properties, PartialEq)]
+pub struct SegmentInspectorProps {
+    pub file_type: FileType,
+    pub file_bytes: UseStateHandle<Option<Vec<u8>>>,
+    pub file_size: UseStateHandle<Option<usize>>,
+}
+
+#[function_component(SegmentInspector)]
+pub fn segment_inspector(props: &SegmentInspectorProps) -> Html {
+    // 削除するセグメントのオフセット
+    let selected = use_state(HashSet::<usize>::new);
+    let message = use_state(|| None::<Result<String, String>>);
+    // 直前の削除を取り消すための、削除前と削除後のバイト列
+    let undo = use_state(|| None::<(Vec<u8>, Vec<u8>)>);
+
+    // ファイルの内容が変わったら選択を解除する。別のファイルを読み込んだときは取り消しもできなくする
+    {
+        let selected = selected.clone();
+        let message = message.clone();
+        let undo = undo.clone();
+        use_effect_with(props.file_bytes.clone(), move |file_bytes| {
+            selected.set(HashSet::new());
+            let ours = match ((**file_bytes).as_ref(), undo.as_ref()) {
+                (Some(bytes), Some((before, after))) => bytes == after || bytes == before,
+                _ => false,
+            };
+            if !ours {
+                undo.set(None);
+                message.set(None);
+            }
+        });
+    }
+
+    let segments = match props.file_type {
+        FileType::JPEG => (*props.file_bytes).as_ref().and_then(|bytes| inspect(bytes)),
+        _ => None,
+    };
+
+    let on_toggle = {
+        let selected = selected.clone();
+        Callback::from(move |offset: usize| {
+            let selected = selected.clone();
+            Callback::from(move |_: Event| {
+                let mut set = (*selected).clone();
+                if !set.remove(&offset) {
+                    set.insert(offset);
+                }
+                selected.set(set);
+            })
+        })
+    };
+
+    // 同じ種類のセグメントをまとめて選択・解除する
+    let on_select_kind = {
+        let selected = selected.clone();
+        let segments = segments.clone().unwrap_or_default();
+        Callback::from(move |kind: SegmentKind| {
+            let selected = selected.clone();
+            let offsets = segments.iter().filter(|s| s.kind == kind).map(|s| s.offset).collect::<Vec<usize>>();
+            Callback::from(move |_: MouseEvent| {
+                let mut set = (*selected).clone();
+                if offsets.iter().all(|o| set.contains(o)) {
+                    for o in offsets.iter() { set.remove(o); }
+                } else {
+                    set.extend(offsets.iter().copied());
+                }
+                selected.set(set);
+            })
+        })
+    };
+
+    let on_strip = {
+        let selected = selected.clone();
+        let message = message.clone();
+        let file_bytes = props.file_bytes.clone();
+        let file_size = props.file_size.clone();
+        let undo = undo.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(bytes) = (*file_bytes).as_ref() else { return; };
+            let offsets = selected.iter().copied().collect::<Vec<usize>>();
+            // 編集履歴には残らないため、実行前に確認する
+            let confirmed = web_sys::window()
+                .and_then(|w| w.confirm_with_message(&format!("{}個のセグメントを削除します。よろしいですか？", offsets.len())).ok())
+                .unwrap_or(false);
+            if !confirmed { return; }
+            match strip_segments(bytes, &offsets) {
+                Ok(stripped) => {
+                    message.set(Some(Ok(format!("{}個のセグメント（{}バイト）を削除しました。", offsets.len(), bytes.len() - stripped.len()))));
+                    undo.set(Some((bytes.clone(), stripped.clone())));
+                    file_size.set(Some(stripped.len()));
+                    file_bytes.set(Some(stripped));
+                    selected.set(HashSet::new());
+                }
+                Err(e) => message.set(Some(Err(e))),
+            }
+        })
+    };
+
+    // 取り消した後は、別のファイルを読み込んだときと同じく選択とメッセージを消す
+    let on_undo = {
+        let file_bytes = props.file_bytes.clone();
+        let file_size = props.file_size.clone();
+        let undo = undo.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some((before, _)) = (*undo).clone() else { return; };
+            file_size.set(Some(before.len()));
+            file_bytes.set(Some(before));
+            undo.set(None);
+        })
+    };
+
+    let Some(segments) = segments else {
+        return html! {
+            <div class="tab-content border border-top-0 p-3">
+                <p class="text-muted mb-0">{
+                    if props.file_type == FileType::JPEG {
+                        "JPEGの構造を読み取れませんでした。".to_string()
+                    } else {
+                        format!("{}のセグメント表示には対応していません。", props.file_type.label())
+                    }
+                }</p>
+            </div>
+        };
+    };
+
+    let mut kinds = Vec::new();
+    for segment in segments.iter().filter(|s| s.kind.is_removable()) {
+        if !kinds.contains(&segment.kind) {
+            kinds.push(segment.kind);
+        }
+    }
+    let selected_bytes: usize = segments.iter().filter(|s| selected.contains(&s.offset)).map(|s| s.length).sum();
+    let conflicts = mpf_conflicts(&segments, &selected.iter().copied().collect::<Vec<usize>>());
+
+    html! {
+        <div class="tab-content border border-top-0 p-3">
+        {
+            match (*message).clone() {
+                Some(Ok(msg)) => html! {
+                    <div class="alert alert-success small d-flex justify-content-between align-items-center">
+                        <span>{ msg }</span>
+                        {
+                            if undo.is_some() {
+                                html! { <button type="button" class="btn btn-outline-secondary btn-sm" onclick={on_undo}>{ "取り消す" }</button> }
+                            } else { html! {} }
+                        }
+                    </div>
+                },
+                Some(Err(msg)) => html! { <div class="alert alert-danger small">{ msg }</div> },
+                None => html! {},
+            }
+        }
+        <p class="small text-muted">
+            { "JPEGに含まれるセグメントの一覧です。画像データはそのままに、選んだセグメントだけを取り除きます。Exif・XMP・IPTCはそれぞれのタブで編集してください。" }
+        </p>
+        {
+            if kinds.is_empty() {
+                html! {}
+            } else {
+                html! {
+                    <div class="d-flex flex-wrap gap-1 mb-2">
+                    { for kinds.iter().map(|kind| html! {
+                        <button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_select_kind.emit(*kind)}>
+                            { format!("{}をすべて選択", kind.label()) }
+                        </button>
+                    }) }
+                    </div>
+                }
+            }
+        }
+        <div class="table-responsive">
+        <table class="table table-sm small align-middle">
+            <thead>
+                <tr>
+                    <th></th>
+                    <th>{ "位置" }</th>
+                    <th>{ "マーカー" }</th>
+                    <th>{ "種類" }</th>
+                    <th class="text-end">{ "サイズ" }</th>
+                    <th>{ "内容" }</th>
+                </tr>
+            </thead>
+            <tbody>
+            { for segments.iter().map(|segment| html! {
+                <tr class={classes!(selected.contains(&segment.offset).then_some("table-danger"), conflicts.contains(&segment.offset).then_some("table-warning"))}>
+                    <td>
+                    {
+                        if segment.kind.is_removable() {
+                            html! {
+                                <input type="checkbox" class="form-check-input" checked={selected.contains(&segment.offset)}
+                                    onchange={on_toggle.emit(segment.offset)} />
+                            }
+                        } else { html! {} }
+                    }
+                    </td>
+                    <td class="font-monospace">{ format!("0x{:08X}", segment.offset) }</td>
+                    <td class="font-monospace">{ segment.marker_name() }</td>
+                    <td>{ segment.kind.label() }</td>
+                    <td class="text-end">{ format!("{} B", segment.length) }</td>
+                    <td>
+                        { segment.detail.clone() }
+                        {
+                            if let Some(caution) = segment.kind.caution() {
+                                html! {
+                                    <div class="text-warning">
+                                        <i class="bi bi-exclamation-triangle-fill me-1" aria-hidden="true"></i>{ caution }
+                                    </div>
+                                }
+                            } else { html! {} }
+                        }
+                    </td>
+                </tr>
+            }) }
+            </tbody>
+        </table>
+        </div>
+        {
+            if conflicts.is_empty() {
+                html! {}
+            } else {
+                html! {
+                    <div class="alert alert-warning small">
+                        { "MPFより後ろのセグメントを削除すると、MPFが指す2枚目以降の画像の位置がずれます。MPFも合わせて選択してください。" }
+                    </div>
+                }
+            }
+        }
+        <button type="button" class="btn btn-danger w-100" disabled={selected.is_empty() || !conflicts.is_empty()} onclick={on_strip}>
+            { format!("選択したセグメントを削除（{}個、{}バイト）", selected.len(), selected_bytes) }
+        </button>
+        </div>
+    }
+}
@@ -0,0 +1,222 @@
+use std::f64::consts::PI;
+
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+use yew::prelude::*;
+
+use super::map_component::{
+    marker_icon, overlay_edit, MapOverlay, OverlayEdit, OverlayKind,
+    DEST_COLOR, DIRECTION_COLOR, RED, TRACK_COLOR, YELLOW,
+};
+
+// 地図タイルを使わない簡易表示。Webメルカトル図法で経緯線と縮尺だけを描く
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 300.0;
+const TILE_SIZE: f64 = 256.0;
+const MAX_LAT: f64 = 85.051_128_78;
+const MAX_ZOOM: i32 = 19;
+const ARROW_LENGTH: f64 = 80.0;
+const ARROW_WING_LENGTH: f64 = 14.0;
+
+fn scale(zoom: i32) -> f64 {
+    TILE_SIZE * 2f64.powi(zoom)
+}
+
+fn project(lat: f64, lng: f64, zoom: i32) -> (f64, f64) {
+    let phi = lat.clamp(-MAX_LAT, MAX_LAT).to_radians();
+    let x = (lng + 180.0) / 360.0 * scale(zoom);
+    let y = (1.0 - (phi.tan() + 1.0 / phi.cos()).ln() / PI) / 2.0 * scale(zoom);
+    (x, y)
+}
+
+fn unproject(x: f64, y: f64, zoom: i32) -> (f64, f64) {
+    let lng = x / scale(zoom) * 360.0 - 180.0;
+    let lat = (PI - 2.0 * PI * y / scale(zoom)).sinh().atan().to_degrees();
+    (lat, lng)
+}
+
+fn wrap_longitude(lng: f64) -> f64 {
+    (lng + 180.0).rem_euclid(360.0) - 180.0
+}
+
+// 範囲に5本程度の線が入るよう、1, 2, 5 × 10^nの間隔を選ぶ
+fn grid_step(span: f64) -> f64 {
+    let raw = span / 5.0;
+    let base = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0].iter().map(|m| base * m).find(|step| *step >= raw).unwrap_or(base * 10.0)
+}
+
+fn step_decimals(step: f64) -> usize {
+    (-step.log10().floor()).max(0.0) as usize
+}
+
+fn grid_values(from: f64, to: f64, step: f64) -> Vec<f64> {
+    let first = (from / step).ceil() as i64;
+    let last = (to / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+// 画面中央の緯度で、約100pxに収まる切りのよい距離の縮尺
+fn scale_bar(lat: f64, zoom: i32) -> (f64, String) {
+    let meters_per_px = 156_543.033_92 * lat.to_radians().cos() / 2f64.powi(zoom);
+    let meters = grid_step(meters_per_px * 500.0);
+    let label = if meters >= 1000.0 { format!("{}km", meters / 1000.0) } else { format!("{}m", meters) };
+    (meters / meters_per_px, label)
+}
+
+// 方位azimuth（度）へ向かう矢印の線分
+fn arrow_lines(x: f64, y: f64, azimuth: f64) -> Vec<(f64, f64, f64, f64)> {
+    let a = azimuth.to_radians();
+    let (tx, ty) = (x + ARROW_LENGTH * a.sin(), y - ARROW_LENGTH * a.cos());
+    let mut lines = vec![(x, y, tx, ty)];
+    for wing in [a + PI * 0.85, a - PI * 0.85] {
+        lines.push((tx, ty, tx + ARROW_WING_LENGTH * wing.sin(), ty - ARROW_WING_LENGTH * wing.cos()));
+    }
+    lines
+}
+
+#[derive(PartialEq, Properties)]
+pub struct SvgMapProps {
+    pub lat: f64,
+    pub lng: f64,
+    pub on_marker_move: Callback<(f64, f64)>,
+    #[prop_or_default]
+    pub overlay: MapOverlay,
+    #[prop_or_default]
+    pub click_mode: OverlayKind,
+    #[prop_or_default]
+    pub on_overlay: Callback<OverlayEdit>,
+}
+
+#[function_component(SvgMap)]
+pub fn svg_map(props: &SvgMapProps) -> Html {
+    let zoom = use_state(|| 11);
+    let red = use_state(|| (props.lat, props.lng));
+
+    {
+        let red = red.clone();
+        use_effect_with((props.lat, props.lng), move |&(lat, lng)| {
+            red.set((lat, lng));
+        });
+    }
+
+    let z = *zoom;
+    let (cx, cy) = project(props.lat, props.lng, z);
+    let (ox, oy) = (cx - WIDTH / 2.0, cy - HEIGHT / 2.0);
+    let to_view = |lat: f64, lng: f64| {
+        let (x, y) = project(lat, lng, z);
+        (x - ox, y - oy)
+    };
+
+    let on_click = {
+        let red = red.clone();
+        let on_marker_move = props.on_marker_move.clone();
+        let on_overlay = props.on_overlay.clone();
+        let (click_mode, position) = (props.click_mode, (props.lat, props.lng));
+        Callback::from(move |e: MouseEvent| {
+            let Some(svg) = e.current_target().and_then(|t| t.dyn_into::<Element>().ok()) else { return; };
+            let rect = svg.get_bounding_client_rect();
+            if rect.width() <= 0.0 || rect.height() <= 0.0 { return; }
+            let x = (e.client_x() as f64 - rect.left()) * WIDTH / rect.width();
+            let y = (e.client_y() as f64 - rect.top()) * HEIGHT / rect.height();
+            let (lat, lng) = unproject(x + ox, y + oy, z);
+            let pos = (lat.clamp(-MAX_LAT, MAX_LAT), wrap_longitude(lng));
+            match overlay_edit(click_mode, position, pos) {
+                Some(edit) => on_overlay.emit(edit),
+                None => {
+                    red.set(pos);
+                    on_marker_move.emit(pos);
+                }
+            }
+        })
+    };
+
+    let on_zoom = {
+        let zoom = zoom.clone();
+        Callback::from(move |delta: i32| {
+            let zoom = zoom.clone();
+            Callback::from(move |_: MouseEvent| {
+                zoom.set((*zoom + delta).clamp(0, MAX_ZOOM));
+            })
+        })
+    };
+
+    let (lat_top, lng_left) = unproject(ox, oy, z);
+    let (lat_bottom, lng_right) = unproject(ox + WIDTH, oy + HEIGHT, z);
+    let lng_step = grid_step(lng_right - lng_left);
+    let lat_step = grid_step(lat_top - lat_bottom);
+    let meridians = grid_values(lng_left.max(-180.0), lng_right.min(180.0), lng_step);
+    let parallels = grid_values(lat_bottom.max(-MAX_LAT), lat_top.min(MAX_LAT), lat_step);
+    let (bar_px, bar_label) = scale_bar(props.lat.clamp(-MAX_LAT, MAX_LAT), z);
+
+    let marker = |lat: f64, lng: f64, color: &str| {
+        let (x, y) = to_view(lat, lng);
+        html! { <image href={marker_icon(color)} x={(x - 16.0).to_string()} y={(y - 32.0).to_string()} width="32" height="32" /> }
+    };
+
+    let (px, py) = to_view(props.lat, props.lng);
+    let arrow = |azimuth: Option<f64>, color: &'static str, dashed: bool| match azimuth {
+        Some(azimuth) => html! {
+            <g stroke={color} stroke-width="3" stroke-linecap="round" stroke-dasharray={if dashed { "6 6" } else { "none" }}>
+            { for arrow_lines(px, py, azimuth).into_iter().map(|(x1, y1, x2, y2)| html! {
+                <line x1={x1.to_string()} y1={y1.to_string()} x2={x2.to_string()} y2={y2.to_string()} />
+            }) }
+            </g>
+        },
+        None => html! {},
+    };
+    let dest = match props.overlay.dest {
+        Some((lat, lng)) => {
+            let (dx, dy) = to_view(lat, lng);
+            html! {
+                <>
+                <line x1={px.to_string()} y1={py.to_string()} x2={dx.to_string()} y2={dy.to_string()}
+                    stroke={DEST_COLOR} stroke-width="3" stroke-dasharray="6 6" />
+                { marker(lat, lng, DEST_COLOR) }
+                </>
+            }
+        }
+        None => html! {},
+    };
+
+    html! {
+        <div class="map-container component-container">
+            <svg class="w-100 h-auto bg-white border" viewBox={format!("0 0 {} {}", WIDTH, HEIGHT)} onclick={on_click}>
+                <g stroke="#ced4da" stroke-width="1" style="pointer-events: none;">
+                { for meridians.iter().map(|lng| {
+                    let (x, _) = to_view(0.0, *lng);
+                    html! { <line x1={x.to_string()} y1="0" x2={x.to_string()} y2={HEIGHT.to_string()} /> }
+                }) }
+                { for parallels.iter().map(|lat| {
+                    let (_, y) = to_view(*lat, 0.0);
+                    html! { <line x1="0" y1={y.to_string()} x2={WIDTH.to_string()} y2={y.to_string()} /> }
+                }) }
+                </g>
+                <g fill="#6c757d" font-size="10" style="pointer-events: none;">
+                { for meridians.iter().map(|lng| {
+                    let (x, _) = to_view(0.0, *lng);
+                    html! { <text x={(x + 2.0).to_string()} y={(HEIGHT - 4.0).to_string()}>{ format!("{:.*}", step_decimals(lng_step), lng) }</text> }
+                }) }
+                { for parallels.iter().map(|lat| {
+                    let (_, y) = to_view(*lat, 0.0);
+                    html! { <text x="2" y={(y - 2.0).to_string()}>{ format!("{:.*}", step_decimals(lat_step), lat) }</text> }
+                }) }
+                </g>
+                <g style="pointer-events: none;">
+                    <line x1="10" y1="16" x2={(10.0 + bar_px).to_string()} y2="16" stroke="#212529" stroke-width="3" />
+                    <text x="10" y="12" font-size="10" fill="#212529">{ bar_label }</text>
+                    { arrow(props.overlay.track, TRACK_COLOR, true) }
+                    { arrow(props.overlay.direction, DIRECTION_COLOR, false) }
+                    { dest }
+                    { marker(props.lat, props.lng, YELLOW) }
+                    { marker(red.0, red.1, RED) }
+                </g>
+            </svg>
+            <div class="d-flex align-items-center gap-2 mt-1">
+                <button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_zoom.emit(-1)}>{ "−" }</button>
+                <button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_zoom.emit(1)}>{ "＋" }</button>
+                <small class="text-muted">{ "地図タイルを使わずに座標だけを表示しています。" }</small>
+            </div>
+        </div>
+    }
+}
@@ -1,22 +1,215 @@
-use web_sys::HtmlInputElement;
+use base64::Engine;
+use gloo_utils::document;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{js_sys, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, HtmlInputElement};
 use yew::prelude::*;
 
 use little_exif::exif_tag::ExifTag;
 
-use crate::components::utils::ShowValue;
+use crate::exif::ExifEditData;
+use crate::exif::thumbnail::{thumbnail_mismatches, MAX_THUMBNAIL_BYTES, THUMBNAIL_SIZE};
+use crate::exif::utils::ShowValue;
+use crate::file_type::FileType;
+use crate::jpeg;
 use crate::{ev, on_int};
 
 use super::accordion::{Accordion, Mode};
-use super::utils::InfoProps;
+
+// 長辺がmax_size以下になるよう縮小し、MAX_THUMBNAIL_BYTESに収まる画質のJPEGにする
+async fn downscale_to_jpeg(bytes: &[u8], mime: &str, max_size: u32) -> Result<Vec<u8>, String> {
+    let data_url = format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes));
+    let img = HtmlImageElement::new().map_err(|_| "画像を作成できません。".to_string())?;
+    let loaded = js_sys::Promise::new(&mut |resolve: js_sys::Function, reject: js_sys::Function| {
+        img.set_onload(Some(&resolve));
+        img.set_onerror(Some(&reject));
+    });
+    img.set_src(&data_url);
+    wasm_bindgen_futures::JsFuture::from(loaded).await.map_err(|_| "画像を読み込めません。".to_string())?;
+
+    let (w, h) = (img.natural_width(), img.natural_height());
+    if w == 0 || h == 0 {
+        return Err("画像の大きさを読み取れません。".to_string());
+    }
+    let scale = (max_size as f64 / w.max(h) as f64).min(1.0);
+    let (tw, th) = (((w as f64 * scale).round() as u32).max(1), ((h as f64 * scale).round() as u32).max(1));
+
+    let canvas: HtmlCanvasElement = document().create_element("canvas")
+        .ok()
+        .and_then(|e| e.dyn_into().ok())
+        .ok_or_else(|| "canvasを作成できません。".to_string())?;
+    canvas.set_width(tw);
+    canvas.set_height(th);
+    let ctx: CanvasRenderingContext2d = canvas.get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into().ok())
+        .ok_or_else(|| "canvasを作成できません。".to_string())?;
+    ctx.draw_image_with_html_image_element_and_dw_and_dh(&img, 0.0, 0.0, tw as f64, th as f64)
+        .map_err(|_| "画像を縮小できません。".to_string())?;
+
+    for quality in [0.85, 0.7, 0.5, 0.3] {
+        let Ok(url) = canvas.to_data_url_with_type_and_encoder_options("image/jpeg", &JsValue::from_f64(quality)) else { continue; };
+        let Some((_, data)) = url.split_once(',') else { continue; };
+        let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) else { continue; };
+        if bytes.len() <= MAX_THUMBNAIL_BYTES {
+            return Ok(bytes);
+        }
+    }
+    Err(format!("{}バイト以下に縮小できません。", MAX_THUMBNAIL_BYTES))
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ThumbnailProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>,
+    pub file_type: FileType,
+    pub file_bytes: UseStateHandle<Option<Vec<u8>>>,
+    // HEICのプレビュー用JPEG
+    pub preview_bytes: UseStateHandle<Option<Vec<u8>>>,
+}
 
 #[function_component(ThumbnailInfo)]
-pub fn interop_info(props: &InfoProps) -> Html {
+pub fn interop_info(props: &ThumbnailProps) -> Html {
     let input_refs = [use_node_ref()];
+    let error = use_state(|| None::<String>);
+    let is_working = use_state(|| false);
 
     on_int!(u32, thumbnail_length, ThumbnailLength, input_refs[0], props);
 
+    let thumbnail = props.exif.as_ref().and_then(|eed| eed.thumbnail().map(|t| t.to_vec()));
+    let thumbnail_dims = thumbnail.as_ref().and_then(|t| jpeg::dimensions(t));
+    let main_dims = match (props.file_type, (*props.file_bytes).as_ref()) {
+        (FileType::JPEG, Some(bytes)) => jpeg::dimensions(bytes),
+        _ => props.exif.as_ref().and_then(|eed| eed.pixel_dimensions()),
+    };
+    let mismatches = match (thumbnail_dims, main_dims) {
+        (Some(t), Some(m)) => thumbnail_mismatches(t, m),
+        _ => Vec::new(),
+    };
+
+    let set_thumbnail = {
+        let exif = props.exif.clone();
+        let error = error.clone();
+        Callback::from(move |result: Result<Vec<u8>, String>| {
+            let Some(eed) = exif.as_ref() else { return; };
+            let mut eed = eed.clone();
+            match result.and_then(|data| eed.set_thumbnail(data)) {
+                Ok(()) => {
+                    exif.set(Some(eed));
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        })
+    };
+
+    let on_delete = {
+        let exif = props.exif.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(eed) = exif.as_ref() {
+                let mut eed = eed.clone();
+                eed.delete_thumbnail();
+                exif.set(Some(eed));
+            }
+        })
+    };
+
+    let on_regenerate = {
+        let file_type = props.file_type;
+        let file_bytes = props.file_bytes.clone();
+        let preview_bytes = props.preview_bytes.clone();
+        let set_thumbnail = set_thumbnail.clone();
+        let is_working = is_working.clone();
+        Callback::from(move |_: MouseEvent| {
+            let (source, source_type) = match ((*preview_bytes).as_ref(), (*file_bytes).as_ref()) {
+                (Some(bytes), _) => (bytes.clone(), FileType::JPEG),
+                (None, Some(bytes)) => (bytes.clone(), file_type),
+                _ => return,
+            };
+            // Orientationで回転されないよう、Exifを除いてから描画する
+            let source = match source_type {
+                FileType::JPEG => jpeg::strip_exif(&source).unwrap_or(source),
+                _ => source,
+            };
+            let set_thumbnail = set_thumbnail.clone();
+            let is_working = is_working.clone();
+            is_working.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                set_thumbnail.emit(downscale_to_jpeg(&source, source_type.mime(), THUMBNAIL_SIZE).await);
+                is_working.set(false);
+            });
+        })
+    };
+
+    let on_import = {
+        let set_thumbnail = set_thumbnail.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else { return; };
+            input.set_value("");
+            let set_thumbnail = set_thumbnail.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let bytes = match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                    Ok(v) => js_sys::Uint8Array::new(&v).to_vec(),
+                    Err(_) => {
+                        set_thumbnail.emit(Err("ファイルを読み込めません。".to_string()));
+                        return;
+                    }
+                };
+                // そのまま使えるJPEGでなければ縮小して作り直す
+                let result = if jpeg::dimensions(&bytes).is_some() && bytes.len() <= MAX_THUMBNAIL_BYTES {
+                    Ok(bytes)
+                } else {
+                    downscale_to_jpeg(&bytes, &file.type_(), THUMBNAIL_SIZE).await
+                };
+                set_thumbnail.emit(result);
+            });
+        })
+    };
+
     html! {
         <div class="tab-content border border-top-0 p-3">
+        <div class="mb-3">
+            {
+                match &thumbnail {
+                    Some(t) => html! {
+                        <div class="d-flex flex-wrap align-items-start gap-3 mb-2">
+                            <img class="border" style="max-width: 160px; max-height: 160px;"
+                                src={format!("data:image/jpeg;base64,{}", base64::engine::general_purpose::STANDARD.encode(t))}
+                                alt="サムネイル" />
+                            <div class="small">
+                                <p class="mb-1">{ format!(
+                                    "サムネイル: {} ({}バイト)",
+                                    thumbnail_dims.map(|(w, h)| format!("{}×{}", w, h)).unwrap_or_else(|| "大きさ不明".to_string()),
+                                    t.len(),
+                                ) }</p>
+                                <p class="mb-1">{ format!(
+                                    "本体: {}",
+                                    main_dims.map(|(w, h)| format!("{}×{}", w, h)).unwrap_or_else(|| "大きさ不明".to_string()),
+                                ) }</p>
+                                <p class="text-muted mb-0">{ "サムネイルには本体と同じOrientationが適用されるため、回転前の状態で表示しています。" }</p>
+                            </div>
+                        </div>
+                    },
+                    None => html! { <p class="text-muted small">{ "サムネイルはありません。" }</p> },
+                }
+            }
+            { for mismatches.iter().map(|msg| html! { <div class="alert alert-warning small py-2">{ *msg }</div> }) }
+            <div class="d-flex flex-wrap gap-2 mb-2">
+                <button type="button" class="btn btn-danger" disabled={thumbnail.is_none()} onclick={on_delete}>{ "サムネイルを削除" }</button>
+                <button type="button" class="btn btn-primary" disabled={*is_working || props.file_bytes.is_none()} onclick={on_regenerate}>
+                    { "本体から作り直す" }
+                </button>
+                <label class="btn btn-outline-primary mb-0">
+                    { "画像を読み込む" }
+                    <input type="file" accept="image/*" class="d-none" onchange={on_import} />
+                </label>
+            </div>
+            {
+                if let Some(msg) = (*error).clone() {
+                    html! { <div class="alert alert-danger small">{ msg }</div> }
+                } else { html! {} }
+            }
+        </div>
         <div class="accordion">
             <AccordionThumbnailOffsets
                 value={ev!(thumbnail_info.thumbnail_offset, props)} />
@@ -6,8 +6,47 @@ use super::utils::InfoProps;
 
 #[function_component(Uneditable)]
 pub fn uneditable(props: &InfoProps) -> Html {
+    let maker_note = props.exif.as_ref().and_then(|eed| eed.maker_note());
+
     html! {
         <div class="tab-content border border-top-0 p-3">
+        {
+            match maker_note {
+                Some(Ok(maker_note)) => html! {
+                    <div class="card mb-3">
+                    <div class="card-body">
+                        <h6 class="card-title">{ format!("MakerNoteの解析結果（{}）", maker_note.vendor) }</h6>
+                        <p class="small text-muted">
+                            { "読み取り専用です。注意が必要な項目は、プライバシー保護のプリセットでMakerNoteごと削除されます。" }
+                        </p>
+                        <div class="table-responsive">
+                        <table class="table table-sm small align-middle mb-0">
+                            <tbody>
+                            { for maker_note.fields.iter().map(|field| html! {
+                                <tr>
+                                    <th class="text-nowrap">{ field.name.clone() }</th>
+                                    <td class="text-break">
+                                        { field.value.clone() }
+                                        {
+                                            if let Some(sensitivity) = field.sensitivity {
+                                                html! { <span class="badge text-bg-warning ms-1">{ sensitivity.label() }</span> }
+                                            } else { html! {} }
+                                        }
+                                    </td>
+                                </tr>
+                            }) }
+                            </tbody>
+                        </table>
+                        </div>
+                    </div>
+                    </div>
+                },
+                Some(Err(e)) => html! {
+                    <div class="alert alert-warning small">{ format!("MakerNoteを解析できませんでした（{}）。", e) }</div>
+                },
+                None => html! {},
+            }
+        }
         <div class="accordion">
             {
                 if let Some(value) = ev!(uneditable.maker_note, props) {
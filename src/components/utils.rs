@@ -1,62 +1,39 @@
-use chrono::NaiveDateTime;
+use wasm_bindgen::JsCast;
+use web_sys::{js_sys, Url};
 use yew::prelude::*;
 
 use crate::exif::ExifEditData;
-use crate::exif::exif_capture::TimeOffset;
-use crate::exif::utils::F64;
 
-pub trait ShowValue: PartialEq {
-    fn show_value(&self) -> String;
-}
-
-impl ShowValue for String { fn show_value(&self) -> String { self.clone() } }
-
-impl ShowValue for u16 { fn show_value(&self) -> String { self.to_string() } }
-impl ShowValue for u32 { fn show_value(&self) -> String { self.to_string() } }
-
-impl ShowValue for F64 { fn show_value(&self) -> String { self.value().to_string() } }
-
-impl ShowValue for NaiveDateTime { fn show_value(&self) -> String { format!("{:?}", self) } }
-impl ShowValue for TimeOffset { fn show_value(&self) -> String { self.to_string() } }
-
-macro_rules! show_value_vec {
-    ($type:ty) => {
-        impl ShowValue for Vec<$type> {
-            fn show_value(&self) -> String {
-                let v = self.iter().map(|vi| vi.to_string()).collect::<Vec<String>>();
-                v.join(", ")
-            }
-        }
-    };
+#[derive(Properties, PartialEq)]
+pub struct InfoProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>
 }
 
-show_value_vec!(u8);
-show_value_vec!(u16);
-show_value_vec!(u32);
-show_value_vec!(F64);
+// バイト列をBlobにして、アンカー要素のクリックでダウンロードさせる
+pub fn download_bytes(bytes: &[u8], file_name: &str) -> Result<(), ()> {
+    let u8_array = js_sys::Uint8Array::new_with_length(bytes.len() as u32);
+    u8_array.copy_from(bytes);
+    let array = js_sys::Array::new();
+    array.push(&u8_array.buffer());
 
-macro_rules! show_value {
-    ($type:ty, $n:expr) => {
-        impl ShowValue for [$type; $n] {
-            fn show_value(&self) -> String {
-                let v = self.iter().map(|vi| vi.to_string()).collect::<Vec<String>>();
-                v.join(", ")
-            }
-        }
-    };
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&array).map_err(|_| ())?;
+    let document = web_sys::window().and_then(|window| window.document()).ok_or(())?;
+    let body = document.body().ok_or(())?;
+    let anchor = document.create_element("a").map_err(|_| ())?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(|_| ())?;
+    anchor.set_attribute("href", &url).map_err(|_| ())?;
+    anchor.set_attribute("download", file_name).map_err(|_| ())?;
+    body.append_child(&anchor).map_err(|_| ())?;
+    if let Some(dr) = anchor.dyn_ref::<web_sys::HtmlElement>() {
+        dr.click();
+    }
+    let _ = body.remove_child(&anchor);
+    let _ = Url::revoke_object_url(&url);
+    Ok(())
 }
 
-show_value!(u8, 4);
-show_value!(u16, 2);
-show_value!(u16, 768);
-show_value!(F64, 2);
-show_value!(F64, 3);
-show_value!(F64, 4);
-show_value!(F64, 6);
-
-#[derive(Properties, PartialEq)]
-pub struct InfoProps {
-    pub exif: UseStateHandle<Option<ExifEditData>>
+pub fn file_list(files: &web_sys::FileList) -> Vec<web_sys::File> {
+    (0..files.length()).filter_map(|i| files.get(i)).collect()
 }
 
 #[macro_export]
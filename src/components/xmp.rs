@@ -0,0 +1,349 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::exif::ExifEditData;
+use crate::file_type::FileType;
+use crate::xmp::{container, namespace_label, XmpPacket, XmpProperty, XmpValue, NAMESPACES};
+use crate::xmp::sync::SyncField;
+
+#[derive(Clone, Copy, PartialEq)]
+enum SyncDirection {
+    ToXmp,
+    ToExif,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct XmpInfoProps {
+    pub exif: UseStateHandle<Option<ExifEditData>>,
+    pub xmp: UseStateHandle<Option<XmpPacket>>,
+    pub file_type: FileType,
+    // 読み込み時にXMPを解析できなかった場合の理由
+    pub parse_error: Option<String>,
+}
+
+#[function_component(XmpInfo)]
+pub fn xmp_info(props: &XmpInfoProps) -> Html {
+    let error = use_state(|| None::<String>);
+    let namespace_ref = use_node_ref();
+    let name_ref = use_node_ref();
+    let value_ref = use_node_ref();
+
+    // XMPがなければ空のパケットから編集を始める
+    let edit_xmp = {
+        let xmp = props.xmp.clone();
+        let error = error.clone();
+        Callback::from(move |f: Box<dyn Fn(&mut XmpPacket) -> Result<(), String>>| {
+            let mut packet = (*xmp).clone().unwrap_or_default();
+            match f(&mut packet) {
+                Ok(()) => {
+                    xmp.set(Some(packet));
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        })
+    };
+
+    let on_sync = {
+        let exif = props.exif.clone();
+        let xmp = props.xmp.clone();
+        let edit_xmp = edit_xmp.clone();
+        let error = error.clone();
+        Callback::from(move |(field, direction): (SyncField, SyncDirection)| {
+            let exif = exif.clone();
+            let xmp = xmp.clone();
+            let edit_xmp = edit_xmp.clone();
+            let error = error.clone();
+            Callback::from(move |_: MouseEvent| {
+                let Some(eed) = exif.as_ref() else { return; };
+                match direction {
+                    SyncDirection::ToXmp => {
+                        let eed = eed.clone();
+                        edit_xmp.emit(Box::new(move |packet| field.to_xmp(&eed, packet)));
+                    }
+                    SyncDirection::ToExif => {
+                        let packet = (*xmp).clone().unwrap_or_default();
+                        let mut eed = eed.clone();
+                        match field.to_exif(&packet, &mut eed) {
+                            Ok(()) => {
+                                exif.set(Some(eed));
+                                error.set(None);
+                            }
+                            Err(e) => error.set(Some(e)),
+                        }
+                    }
+                }
+            })
+        })
+    };
+
+    let on_sync_all = {
+        let exif = props.exif.clone();
+        let edit_xmp = edit_xmp.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(eed) = exif.as_ref() else { return; };
+            let eed = eed.clone();
+            edit_xmp.emit(Box::new(move |packet| packet.sync_from_exif(&eed).map(|_| ())));
+        })
+    };
+
+    let on_update = {
+        let edit_xmp = edit_xmp.clone();
+        Callback::from(move |(index, text): (usize, String)| {
+            edit_xmp.emit(Box::new(move |packet| {
+                let p = packet.properties.get_mut(index).ok_or_else(|| "プロパティが見つかりません".to_string())?;
+                p.value = p.value.with_text(&text).ok_or_else(|| format!("{}は編集できません", p.qualified_name()))?;
+                Ok(())
+            }));
+        })
+    };
+
+    let on_delete = {
+        let edit_xmp = edit_xmp.clone();
+        Callback::from(move |index: usize| {
+            edit_xmp.emit(Box::new(move |packet| {
+                if index < packet.properties.len() {
+                    packet.properties.remove(index);
+                }
+                Ok(())
+            }));
+        })
+    };
+
+    let on_add = {
+        let edit_xmp = edit_xmp.clone();
+        let (namespace_ref, name_ref, value_ref) = (namespace_ref.clone(), name_ref.clone(), value_ref.clone());
+        Callback::from(move |_: MouseEvent| {
+            let Some(namespace) = namespace_ref.cast::<HtmlSelectElement>().map(|s| s.value()) else { return; };
+            let Some(name_input) = name_ref.cast::<HtmlInputElement>() else { return; };
+            let Some(value_input) = value_ref.cast::<HtmlInputElement>() else { return; };
+            let (name, value) = (name_input.value().trim().to_string(), value_input.value());
+            edit_xmp.emit(Box::new(move |packet| {
+                let valid = name.chars().next().map(|c| c.is_ascii_alphabetic()).unwrap_or(false)
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if !valid {
+                    return Err(format!("プロパティ名が正しくありません: {}", name));
+                }
+                if packet.get(&namespace, &name).is_some() {
+                    return Err(format!("{}は既にあります。一覧から編集してください", name));
+                }
+                packet.set(&namespace, &name, XmpValue::Text(value.clone()))
+            }));
+            name_input.set_value("");
+            value_input.set_value("");
+        })
+    };
+
+    let on_delete_all = {
+        let xmp = props.xmp.clone();
+        Callback::from(move |_: MouseEvent| xmp.set(None))
+    };
+
+    if !container::is_supported(props.file_type) {
+        return html! {
+            <div class="tab-content border border-top-0 p-3">
+                <p class="text-muted mb-0">{ format!("{}のXMPの読み書きには対応していません。", props.file_type.label()) }</p>
+            </div>
+        };
+    }
+
+    let packet = (*props.xmp).clone();
+    let sync_rows = match (props.exif.as_ref(), packet.as_ref()) {
+        (Some(eed), Some(packet)) => SyncField::all().into_iter()
+            .map(|field| (field, field.exif_value(eed), field.xmp_value(packet), field.in_sync(eed, packet)))
+            .collect::<Vec<_>>(),
+        (Some(eed), None) => SyncField::all().into_iter()
+            .map(|field| {
+                let value = field.exif_value(eed);
+                let in_sync = value.is_none();
+                (field, value, None, in_sync)
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let n_conflicts = sync_rows.iter().filter(|(_, _, _, in_sync)| !in_sync).count();
+
+    // 既知の名前空間を先に、それ以外はまとめて後に表示する
+    let mut groups = NAMESPACES.iter()
+        .map(|(_, uri, label)| (label.to_string(), *uri))
+        .collect::<Vec<(String, &str)>>();
+    let properties = packet.as_ref().map(|p| p.properties.clone()).unwrap_or_default();
+    let other = properties.iter().enumerate()
+        .filter(|(_, p)| namespace_label(&p.namespace).is_none())
+        .collect::<Vec<(usize, &XmpProperty)>>();
+    groups.retain(|(_, uri)| properties.iter().any(|p| p.namespace == *uri));
+
+    let row = |index: usize, p: &XmpProperty| html! {
+        <XmpPropertyRow key={format!("{}-{}", index, p.qualified_name())} index={index} property={p.clone()}
+            on_update={on_update.clone()} on_delete={on_delete.clone()} />
+    };
+
+    html! {
+        <div class="tab-content border border-top-0 p-3">
+        {
+            if let Some(e) = props.parse_error.as_ref().filter(|_| packet.as_ref().map(|p| p.is_empty()).unwrap_or(false)) {
+                html! {
+                    <div class="alert alert-warning small">
+                        { format!("XMPを解析できませんでした（{}）。削除しない限り、ダウンロード時はそのまま残します。", e) }
+                    </div>
+                }
+            } else { html! {} }
+        }
+        {
+            if let Some(msg) = (*error).clone() {
+                html! { <div class="alert alert-danger small">{ msg }</div> }
+            } else { html! {} }
+        }
+
+        <h3 class="h6">{ "Exifとの同期" }</h3>
+        <p class="small text-muted">
+            { "ExifとXMPの両方にある項目を比べます。食い違っていると、表示するソフトによって異なる値が使われます。" }
+        </p>
+        <div class="table-responsive">
+        <table class="table table-sm small align-middle">
+            <thead>
+                <tr><th>{ "項目" }</th><th>{ "Exif" }</th><th>{ "XMP" }</th><th></th></tr>
+            </thead>
+            <tbody>
+            { for sync_rows.iter().map(|(field, exif_value, xmp_value, in_sync)| html! {
+                <tr class={classes!((!*in_sync).then_some("table-warning"))}>
+                    <td>
+                        { field.label() }
+                        <div class="text-muted">{ field.xmp_names() }</div>
+                    </td>
+                    <td class="text-break">{ exif_value.clone().unwrap_or_else(|| "-".to_string()) }</td>
+                    <td class="text-break">{ xmp_value.clone().unwrap_or_else(|| "-".to_string()) }</td>
+                    <td class="text-nowrap">
+                    {
+                        if *in_sync {
+                            html! { <i class="bi bi-check-circle-fill text-success" aria-hidden="true"></i> }
+                        } else {
+                            html! {
+                                <>
+                                <button type="button" class="btn btn-outline-primary btn-sm me-1"
+                                    onclick={on_sync.emit((*field, SyncDirection::ToXmp))}>{ "Exif→XMP" }</button>
+                                <button type="button" class="btn btn-outline-secondary btn-sm"
+                                    onclick={on_sync.emit((*field, SyncDirection::ToExif))}>{ "XMP→Exif" }</button>
+                                </>
+                            }
+                        }
+                    }
+                    </td>
+                </tr>
+            }) }
+            </tbody>
+        </table>
+        </div>
+        <button type="button" class="btn btn-primary w-100 mb-4" disabled={n_conflicts == 0} onclick={on_sync_all}>
+            { format!("食い違う{}項目をすべてExifに合わせる", n_conflicts) }
+        </button>
+
+        <h3 class="h6">{ "XMPのプロパティ" }</h3>
+        {
+            if properties.is_empty() {
+                html! { <p class="text-muted small">{ "XMPはありません。" }</p> }
+            } else {
+                html! {
+                    <>
+                    { for groups.iter().map(|(label, uri)| html! {
+                        <div class="mb-3">
+                            <h4 class="h6 text-muted">{ label.clone() }</h4>
+                            { for properties.iter().enumerate().filter(|(_, p)| p.namespace == *uri).map(|(i, p)| row(i, p)) }
+                        </div>
+                    }) }
+                    {
+                        if other.is_empty() { html! {} } else {
+                            html! {
+                                <div class="mb-3">
+                                    <h4 class="h6 text-muted">{ "その他" }</h4>
+                                    { for other.iter().map(|(i, p)| row(*i, p)) }
+                                </div>
+                            }
+                        }
+                    }
+                    </>
+                }
+            }
+        }
+
+        <div class="row g-2 mb-3">
+            <div class="col-4">
+                <select class="form-select form-select-sm" ref={namespace_ref}>
+                { for NAMESPACES.iter().map(|(prefix, uri, _)| html! {
+                    <option value={*uri}>{ *prefix }</option>
+                }) }
+                </select>
+            </div>
+            <div class="col-4">
+                <input type="text" class="form-control form-control-sm" placeholder="プロパティ名" ref={name_ref} />
+            </div>
+            <div class="col-4">
+                <input type="text" class="form-control form-control-sm" placeholder="値" ref={value_ref} />
+            </div>
+        </div>
+        <button type="button" class="btn btn-outline-primary btn-sm w-100 mb-3" onclick={on_add}>{ "プロパティを追加" }</button>
+
+        <button type="button" class="btn btn-danger w-100" disabled={props.xmp.is_none()} onclick={on_delete_all}>
+            { "XMPをすべて削除" }
+        </button>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct XmpPropertyRowProps {
+    index: usize,
+    property: XmpProperty,
+    on_update: Callback<(usize, String)>,
+    on_delete: Callback<usize>,
+}
+
+#[function_component(XmpPropertyRow)]
+fn xmp_property_row(props: &XmpPropertyRowProps) -> Html {
+    let input_ref = use_node_ref();
+    let text = props.property.value.to_text();
+    let editable = props.property.value.is_editable();
+    let multiline = matches!(props.property.value, XmpValue::Seq(_) | XmpValue::Bag(_));
+
+    let on_update = {
+        let input_ref = input_ref.clone();
+        let on_update = props.on_update.clone();
+        let index = props.index;
+        Callback::from(move |_: MouseEvent| {
+            let value = input_ref.cast::<HtmlTextAreaElement>().map(|t| t.value())
+                .or_else(|| input_ref.cast::<HtmlInputElement>().map(|i| i.value()));
+            if let Some(value) = value {
+                on_update.emit((index, value));
+            }
+        })
+    };
+    let on_delete = {
+        let on_delete = props.on_delete.clone();
+        let index = props.index;
+        Callback::from(move |_: MouseEvent| on_delete.emit(index))
+    };
+
+    html! {
+        <div class="mb-2">
+            <label class="form-label small fw-bold mb-1">
+                { props.property.qualified_name() }
+                {
+                    if multiline {
+                        html! { <span class="text-muted fw-normal ms-1">{ "（1行に1項目）" }</span> }
+                    } else { html! {} }
+                }
+            </label>
+            <div class="input-group input-group-sm">
+                {
+                    if multiline {
+                        html! { <textarea class="form-control" rows={text.lines().count().max(1).to_string()} readonly={!editable} ref={input_ref} value={text.clone()} /> }
+                    } else {
+                        html! { <input type="text" class="form-control" readonly={!editable} ref={input_ref} value={text.clone()} /> }
+                    }
+                }
+                <button type="button" class="btn btn-outline-primary" disabled={!editable} onclick={on_update}>{ "更新" }</button>
+                <button type="button" class="btn btn-outline-danger" onclick={on_delete}>{ "削除" }</button>
+            </div>
+        </div>
+    }
+}
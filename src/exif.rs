@@ -1,19 +1,27 @@
 use std::fmt;
 
-use chrono::Local;
+use chrono::{Duration, Local, NaiveDateTime};
 use little_exif::ifd::ExifTagGroup;
 use little_exif::metadata::Metadata;
 use little_exif::exif_tag::ExifTag;
 use little_exif::rational::uR64;
 
 pub mod basic_image;
+pub mod clock;
 pub mod exif_capture;
 pub mod gps;
 pub mod interop;
 pub mod thumbnail;
 pub mod user;
 pub mod uneditable;
+pub mod copy;
+pub mod diff;
+pub mod export;
+pub mod import;
+pub mod makernote;
+pub mod preset;
 pub mod rational;
+pub mod tag_value;
 pub mod utils;
 
 use basic_image::{
@@ -47,17 +55,52 @@ use utils::{
     pick_v0, pick_v0_cast, pick_v0_ur64, pick_v0_ir64,
     pick_v02, pick_v04, pick_v0768,
     pick_v02_ur64, pick_v03_ur64, pick_v04_ur64, pick_v06_ur64,
+    ShowValue,
 };
 
-use crate::components::utils::ShowValue;
-
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum ExifTime {
     DateTimeOriginal,
     CreateDate,
     ModifyDate
 }
 
+impl ExifTime {
+    pub fn all() -> [Self; 3] {
+        [ExifTime::DateTimeOriginal, ExifTime::CreateDate, ExifTime::ModifyDate]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExifTime::DateTimeOriginal => "DateTimeOriginal",
+            ExifTime::CreateDate => "CreateDate",
+            ExifTime::ModifyDate => "ModifyDate",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|t| t.name() == s)
+    }
+
+    pub fn to_tag(&self, ndt: &NaiveDateTime) -> ExifTag {
+        let s = ndt.format("%Y:%m:%d %H:%M:%S").to_string();
+        match self {
+            ExifTime::DateTimeOriginal => ExifTag::DateTimeOriginal(s),
+            ExifTime::CreateDate => ExifTag::CreateDate(s),
+            ExifTime::ModifyDate => ExifTag::ModifyDate(s),
+        }
+    }
+
+    pub fn offset_tag(&self, offset: &TimeOffset) -> ExifTag {
+        let s = offset.to_string();
+        match self {
+            ExifTime::DateTimeOriginal => ExifTag::OffsetTimeOriginal(s),
+            ExifTime::CreateDate => ExifTag::OffsetTimeDigitized(s),
+            ExifTime::ModifyDate => ExifTag::OffsetTime(s),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Coordinate {
     degree: uR64,
@@ -139,6 +182,17 @@ impl ExifEditData {
         }
     }
 
+    // 編集画面のアコーディオンと同じ単位でのタグの分類
+    pub fn tag_groups() -> Vec<(&'static str, &'static [&'static str])> {
+        let mut groups = BasicImageInfo::tag_groups();
+        groups.extend(ExifCaptureInfo::tag_groups());
+        groups.extend(GpsInfo::tag_groups());
+        groups.push(("InteropInfo", InteropInfo::TAG_NAMES));
+        groups.push(("ThumbnailInfo", ThumbnailInfo::TAG_NAMES));
+        groups.push(("UserInfo", UserInfo::TAG_NAMES));
+        groups
+    }
+
     pub fn new(metadata: &Metadata) -> Self {
         let mut ret = Self::empty();
         
@@ -150,6 +204,40 @@ impl ExifEditData {
         ret
     }
 
+    pub fn pick_time(&self, time: ExifTime) -> Option<NaiveDateTime> {
+        let time_info = &self.exif_capture_info.time_info;
+        match time {
+            ExifTime::DateTimeOriginal => time_info.date_time_original,
+            ExifTime::CreateDate => time_info.create_date,
+            ExifTime::ModifyDate => time_info.modify_date,
+        }
+    }
+
+    pub fn pick_offset(&self, time: ExifTime) -> Option<TimeOffset> {
+        let time_info = &self.exif_capture_info.time_info;
+        match time {
+            ExifTime::DateTimeOriginal => time_info.offset_time_original.clone(),
+            ExifTime::CreateDate => time_info.offset_time_digitized.clone(),
+            ExifTime::ModifyDate => time_info.offset_time.clone(),
+        }
+    }
+
+    // 日時タグが存在する場合のみ、指定した時間だけずらす
+    pub fn shift_time(&mut self, time: ExifTime, delta: Duration) {
+        if let Some(ndt) = self.pick_time(time).and_then(|ndt| ndt.checked_add_signed(delta)) {
+            self.update_tag(time.to_tag(&ndt));
+        }
+    }
+
+    pub fn delete_all(&mut self) {
+        let metadata = self.metadata.clone();
+        for ifd in metadata.get_ifds() {
+            for tag in ifd.get_tags() {
+                self.delete_tag(tag.clone());
+            }
+        }
+    }
+
     pub fn update_tag(&mut self, tag: ExifTag) {
         match &tag {
             // BasicImageInfo（基本画像情報）（ここから）
@@ -1031,12 +1119,15 @@ fn string_rational<T: ExifRational>(v: &[T]) -> String {
     }
 }
 
-pub fn unknown_string_core(hex: u16, group: ExifTagGroup) -> String {
-    "Unknown(".to_string()
-    + match group {
+pub fn group_name(group: ExifTagGroup) -> &'static str {
+    match group {
         ExifTagGroup::GENERIC => "Generic",
         ExifTagGroup::EXIF => "Exif",
         ExifTagGroup::INTEROP => "Interop",
         ExifTagGroup::GPS => "GPS",
-    } + &format!(", hex:0x{:x})", hex)
+    }
+}
+
+pub fn unknown_string_core(hex: u16, group: ExifTagGroup) -> String {
+    "Unknown(".to_string() + group_name(group) + &format!(", hex:0x{:x})", hex)
 }
\ No newline at end of file
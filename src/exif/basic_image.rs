@@ -1,8 +1,5 @@
-use little_exif::exif_tag::ExifTag;
-use little_exif::metadata::Metadata;
-
 use super::utils::{
-    F64, AllList, RemoveTag, 
+    F64, AllList, ShowValue,
     pick_v0, pick_v0_cast, pick_v0_ur64, 
     pick_v0768,
     pick_v02_ur64, pick_v03_ur64, pick_v04_ur64, pick_v06_ur64,
@@ -10,7 +7,6 @@ use super::utils::{
 };
 
 use crate::{define_big_struct, define_enum, define_struct};
-use crate::components::utils::ShowValue;
 
 define_big_struct!(
     BasicImageInfo,
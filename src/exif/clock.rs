@@ -0,0 +1,146 @@
+use chrono::{Duration, NaiveDateTime, Timelike};
+use little_exif::exif_tag::ExifTag;
+use little_exif::rational::uR64;
+
+use super::{ExifEditData, ExifTime};
+use super::exif_capture::TimeOffset;
+
+pub const GPS_OFFSET_MISSING: &str = "撮影日時のオフセット（OffsetTime*）がないため、GPS日時は計算し直していません";
+
+// カメラの時計のずれを、3つの日時タグにまとめて反映する
+#[derive(Clone, PartialEq)]
+pub struct ClockShift {
+    pub delta: Duration,
+    // 指定した場合は、存在する日時タグのOffsetTime*をこの値に書き換える
+    pub offset: Option<TimeOffset>,
+    // 補正後の撮影日時とオフセットから、GPSDateStamp/GPSTimeStampをUTCで計算し直す
+    pub update_gps: bool,
+}
+
+impl ClockShift {
+    pub fn new(delta: Duration) -> Self {
+        Self { delta, offset: None, update_gps: false }
+    }
+
+    // 「UTCのまま撮影したが、本当は日本時間」のように、時計のタイムゾーンを付け替える
+    pub fn between(from: &TimeOffset, to: &TimeOffset) -> Self {
+        Self { delta: to.to_duration() - from.to_duration(), offset: Some(to.clone()), update_gps: false }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut ret = format!("時計補正: {}", format_shift(&self.delta));
+        if let Some(offset) = &self.offset {
+            ret.push_str(&format!("、オフセット{}", offset.to_string()));
+        }
+        if self.update_gps {
+            ret.push_str("、GPS日時を再計算");
+        }
+        ret
+    }
+
+    // GPS日時の再計算を指定したのに、オフセットがわからず計算できなかったときはfalseを返す
+    pub fn apply(&self, eed: &mut ExifEditData) -> bool {
+        for time in ExifTime::all().into_iter() {
+            if eed.pick_time(time).is_none() {
+                continue;
+            }
+            eed.shift_time(time, self.delta);
+            if let Some(offset) = &self.offset {
+                eed.update_tag(time.offset_tag(offset));
+            }
+        }
+        !self.update_gps || eed.update_gps_stamp_from_time()
+    }
+}
+
+impl ExifEditData {
+    // 撮影日時（なければデジタル化日時）をUTCにする。オフセットのタグがなければdefault_offsetとみなす
+    pub fn capture_time_utc(&self, default_offset: Option<&TimeOffset>) -> Option<NaiveDateTime> {
+        [ExifTime::DateTimeOriginal, ExifTime::CreateDate].into_iter()
+            .find_map(|time| {
+                let ndt = self.pick_time(time)?;
+                let offset = self.pick_offset(time).or_else(|| default_offset.cloned())?;
+                ndt.checked_sub_signed(offset.to_duration())
+            })
+    }
+
+    pub fn set_gps_stamp(&mut self, utc: &NaiveDateTime) {
+        self.update_tag(ExifTag::GPSDateStamp(utc.format("%Y:%m:%d").to_string()));
+        self.update_tag(ExifTag::GPSTimeStamp(vec![
+            uR64 { nominator: utc.hour(), denominator: 1 },
+            uR64 { nominator: utc.minute(), denominator: 1 },
+            uR64 { nominator: utc.second(), denominator: 1 },
+        ]));
+    }
+
+    // 撮影日時とそのオフセットからUTCを求め、GPS日時に書き込む
+    pub fn update_gps_stamp_from_time(&mut self) -> bool {
+        match self.capture_time_utc(None) {
+            Some(utc) => {
+                self.set_gps_stamp(&utc);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// "+01:30:00", "-2:00", "+1d 03:00:00", "+1h 23m 5s"のような表記を受け付ける。Durationに収まらなければNone
+pub fn parse_shift(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let seconds = if rest.contains(':') {
+        let (days, hms) = match rest.split_once('d') {
+            Some((d, hms)) => (d.trim().parse::<i64>().ok()?, hms.trim()),
+            None => (0, rest.trim()),
+        };
+        let v = hms.split(':').map(|p| p.trim().parse::<i64>().ok()).collect::<Option<Vec<i64>>>()?;
+        let hms = match v.as_slice() {
+            [h, m] => h.checked_mul(3600)?.checked_add(m.checked_mul(60)?)?,
+            [h, m, s] => h.checked_mul(3600)?.checked_add(m.checked_mul(60)?)?.checked_add(*s)?,
+            _ => return None,
+        };
+        days.checked_mul(86400)?.checked_add(hms)?
+    } else {
+        parse_units(rest)?
+    };
+    Duration::try_seconds(seconds.checked_mul(sign)?)
+}
+
+// "1d 2h 3m 4s"のように単位付きの数値を並べた表記
+fn parse_units(s: &str) -> Option<i64> {
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    let mut found = false;
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            c if c.is_whitespace() => continue,
+            _ => return None,
+        };
+        total = total.checked_add(number.parse::<i64>().ok()?.checked_mul(unit)?)?;
+        number.clear();
+        found = true;
+    }
+    if !number.is_empty() || !found {
+        return None;
+    }
+    Some(total)
+}
+
+pub fn format_shift(delta: &Duration) -> String {
+    let total = delta.num_seconds();
+    let sign = if total < 0 { "-" } else { "+" };
+    let total = total.abs();
+    format!("{}{:02}:{:02}:{:02}", sign, total / 3600, total % 3600 / 60, total % 60)
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use little_exif::exif_tag::ExifTag;
+
+use super::ExifEditData;
+use super::tag_value::{is_unknown, tag_name, tags_by_key, TagKey, STRUCTURAL_TAGS};
+
+// 画像ごとに異なる値を持つため、明示的に選んだときだけコピーする分類
+pub const OPT_IN_GROUPS: &[&str] = &["ImageFormat", "ThumbnailInfo", "Other"];
+
+// コピーできるタグ。オフセットなどの構造に関わるタグ、MakerNote、未知のタグは除く
+pub fn is_copyable(tag: &ExifTag) -> bool {
+    let name = tag_name(tag);
+    !is_unknown(tag) && name != "MakerNote" && !STRUCTURAL_TAGS.contains(&name.as_str())
+}
+
+impl ExifEditData {
+    pub fn copyable_tags(&self) -> BTreeMap<TagKey, ExifTag> {
+        tags_by_key(&self.metadata).into_iter()
+            .filter(|(_, tag)| is_copyable(tag))
+            .collect()
+    }
+
+    // 分類ごとに、コピー元に存在するタグ。どの分類にも属さないタグは"Other"にまとめる
+    pub fn copyable_groups(&self) -> Vec<(&'static str, Vec<TagKey>)> {
+        let mut tags = self.copyable_tags();
+        let mut ret = Vec::new();
+        for (group, names) in ExifEditData::tag_groups() {
+            let mut present = Vec::new();
+            for name in names.iter() {
+                let keys = tags.keys().filter(|key| key.name == *name).cloned().collect::<Vec<TagKey>>();
+                for key in keys.into_iter() {
+                    tags.remove(&key);
+                    present.push(key);
+                }
+            }
+            if !present.is_empty() {
+                ret.push((group, present));
+            }
+        }
+        if !tags.is_empty() {
+            ret.push(("Other", tags.into_keys().collect()));
+        }
+        ret
+    }
+
+    // sourceにあるタグのうち、keysで指定したものを同じIFDに上書きする。実際にコピーしたタグを返す
+    pub fn copy_tags_from(&mut self, source: &ExifEditData, keys: &[TagKey]) -> Vec<TagKey> {
+        let tags = source.copyable_tags();
+        let mut copied = Vec::new();
+        for key in keys.iter() {
+            let Some(tag) = tags.get(key) else { continue; };
+            match key.ifd {
+                0 => self.update_tag(tag.clone()),
+                ifd => self.metadata.get_ifd_mut(tag.get_group(), ifd).set_tag(tag.clone()),
+            }
+            copied.push(key.clone());
+        }
+        copied
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use little_exif::exif_tag::ExifTag;
+use little_exif::ifd::ExifTagGroup;
+
+use super::{group_name, ExifEditData};
+use super::tag_value::{tag_name, value_string};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "追加",
+            ChangeKind::Removed => "削除",
+            ChangeKind::Modified => "変更",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct TagChange {
+    pub group: ExifTagGroup,
+    // 0: 本体、1: サムネイル
+    pub ifd: u32,
+    pub name: String,
+    pub kind: ChangeKind,
+    pub before: Option<ExifTag>,
+    pub after: Option<ExifTag>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+// グループごと、タグ名順に並べるためのキー
+fn group_order(group: ExifTagGroup) -> u8 {
+    match group {
+        ExifTagGroup::GENERIC => 0,
+        ExifTagGroup::EXIF => 1,
+        ExifTagGroup::INTEROP => 2,
+        ExifTagGroup::GPS => 3,
+    }
+}
+
+// IFD0とIFD1に同じ名前のタグがあっても別のものとして比べる
+fn tags_of(eed: &ExifEditData) -> BTreeMap<(u32, u8, String), ExifTag> {
+    let mut map = BTreeMap::new();
+    for ifd in eed.metadata.get_ifds() {
+        for tag in ifd.get_tags() {
+            map.insert((ifd.get_generic_ifd_nr(), group_order(tag.get_group()), tag_name(tag)), tag.clone());
+        }
+    }
+    map
+}
+
+// pick_valueで表示用の値を得られないタグは、生の値を文字列にする
+fn display_value(eed: &ExifEditData, tag: &ExifTag) -> String {
+    eed.pick_value(tag.clone()).unwrap_or_else(|| value_string(tag))
+}
+
+pub fn diff(original: &ExifEditData, edited: &ExifEditData) -> Vec<TagChange> {
+    let before = tags_of(original);
+    let after = tags_of(edited);
+
+    let mut keys = before.keys().chain(after.keys()).cloned().collect::<Vec<(u32, u8, String)>>();
+    keys.sort();
+    keys.dedup();
+
+    let mut changes = Vec::new();
+    for key in keys.iter() {
+        let (b, a) = (before.get(key), after.get(key));
+        let kind = match (b, a) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(b), Some(a)) if value_string(b) != value_string(a) => ChangeKind::Modified,
+            _ => continue,
+        };
+        let group = match (b, a) {
+            (Some(tag), _) | (None, Some(tag)) => tag.get_group(),
+            (None, None) => continue,
+        };
+        changes.push(TagChange {
+            group,
+            ifd: key.0,
+            name: key.2.clone(),
+            kind,
+            before: b.cloned(),
+            after: a.cloned(),
+            old_value: b.map(|tag| display_value(original, tag)),
+            new_value: a.map(|tag| display_value(edited, tag)),
+        });
+    }
+    changes
+}
+
+// 差分をグループ名ごとにまとめる（表示用）
+pub fn group_changes(changes: &[TagChange]) -> Vec<(&'static str, Vec<TagChange>)> {
+    let mut ret: Vec<(&'static str, Vec<TagChange>)> = Vec::new();
+    for change in changes.iter() {
+        let name = group_name(change.group);
+        match ret.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => v.push(change.clone()),
+            None => ret.push((name, vec![change.clone()])),
+        }
+    }
+    ret
+}
+
+impl ExifEditData {
+    pub fn revert_change(&mut self, change: &TagChange) {
+        // サムネイル（IFD1）のタグはIFDを指定して戻す
+        if change.ifd != 0 {
+            let ifd = self.metadata.get_ifd_mut(change.group, change.ifd);
+            match (&change.before, &change.after) {
+                (Some(before), _) => ifd.set_tag(before.clone()),
+                (None, Some(after)) => ifd.remove_tag(after.clone()),
+                (None, None) => {}
+            }
+            return;
+        }
+        match (&change.before, &change.after) {
+            (Some(before), _) => self.update_tag(before.clone()),
+            (None, Some(after)) => self.delete_tag(after.clone()),
+            (None, None) => {}
+        }
+    }
+}
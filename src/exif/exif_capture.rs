@@ -1,15 +1,12 @@
-use chrono::NaiveDateTime;
-use little_exif::exif_tag::ExifTag;
-use little_exif::metadata::Metadata;
+use chrono::{Duration, NaiveDateTime};
 
 use super::utils::{
-    F64, AllList, RemoveTag, VersionAscii,
+    F64, AllList, ShowValue, VersionAscii,
     some_string,
     pick_v0, pick_v0_ir64, pick_v0_ur64,
     pick_v02
 };
 use crate::{define_big_struct, define_enum, define_struct};
-use crate::components::utils::ShowValue;
 
 define_big_struct!(
     ExifCaptureInfo,
@@ -306,10 +303,9 @@ impl TimeOffset {
 
         let mut none_flg = false;
         none_flg |= &s[3..4] != ":";
-        none_flg |= minute != 0 && minute != 30;
-        none_flg |= hour > 14;
-        none_flg |= hour == 14 && minute == 30;
-        none_flg |= hour >= 13 && !sign;
+        // ネパール（+05:45）などのように45分ずれた地域もある。範囲は-12:00から+14:00まで
+        none_flg |= minute != 0 && minute != 30 && minute != 45;
+        none_flg |= hour as u32 * 60 + minute as u32 > if sign { 14 * 60 } else { 12 * 60 };
 
         if none_flg { return None; }
 
@@ -318,6 +314,11 @@ impl TimeOffset {
     pub fn to_string(&self) -> String {
         format!("{}{:>02}:{:>02}", if self.sign { "+" } else { "-" }, self.hour, self.minute)
     }
+    // UTCからのずれ
+    pub fn to_duration(&self) -> Duration {
+        let minutes = self.hour as i64 * 60 + self.minute as i64;
+        Duration::minutes(if self.sign { minutes } else { -minutes })
+    }
 }
 
 #[derive(Clone, PartialEq)]
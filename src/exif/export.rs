@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, HashSet};
+
+use little_exif::exif_tag::ExifTag;
+use serde_json::{Map, Number, Value};
+
+use super::ExifEditData;
+use super::gps::{signed_degree, DMS};
+use super::tag_value::{exiftool_name, is_numeric, is_unknown, tags_by_key, value_string, STRUCTURAL_TAGS};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Exiftool,
+}
+
+impl ExportFormat {
+    pub fn all() -> [Self; 3] {
+        [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Exiftool]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Exiftool => "exiftool形式JSON（-n）",
+        }
+    }
+
+    pub fn file_suffix(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "exif.json",
+            ExportFormat::Csv => "exif.csv",
+            ExportFormat::Exiftool => "exiftool.json",
+        }
+    }
+
+    pub fn export(&self, files: &[(&str, &ExifEditData)]) -> String {
+        match self {
+            // 1ファイルのときは分類ごとのオブジェクトを、複数のときはファイル名をキーにしてまとめる
+            ExportFormat::Json => {
+                let value = match files {
+                    [(_, eed)] => to_json(eed),
+                    _ => Value::Object(files.iter().map(|(name, eed)| (name.to_string(), to_json(eed))).collect()),
+                };
+                serde_json::to_string_pretty(&value).unwrap_or_default()
+            }
+            ExportFormat::Csv => to_csv(files),
+            ExportFormat::Exiftool => serde_json::to_string_pretty(&to_exiftool_json(files)).unwrap_or_default(),
+        }
+    }
+}
+
+// 分類ごとの(タグ名, 値)。どの分類にも属さないタグは"Other"に、MakerNoteと未知のタグは"Uneditable"に入れる
+pub fn grouped_values(eed: &ExifEditData) -> Vec<(&'static str, Vec<(String, String)>)> {
+    // IFD1（サムネイル）のタグは"XResolution（IFD1）"のように別の名前にする
+    let tags = tags_by_key(&eed.metadata);
+    let mut used = HashSet::new();
+    let mut ret = Vec::new();
+
+    for (group, names) in ExifEditData::tag_groups() {
+        let mut values = Vec::new();
+        for name in names.iter() {
+            for (key, tag) in tags.iter().filter(|(key, _)| key.name == *name) {
+                if used.insert(key.clone()) {
+                    values.push((key.label(), value_string(tag)));
+                }
+            }
+        }
+        if !values.is_empty() {
+            ret.push((group, values));
+        }
+    }
+
+    let other = tags.iter()
+        .filter(|(key, tag)| !used.contains(*key) && !is_unknown(tag) && key.name != "MakerNote")
+        .map(|(key, tag)| (key.label(), value_string(tag)))
+        .collect::<Vec<(String, String)>>();
+    if !other.is_empty() {
+        ret.push(("Other", other));
+    }
+
+    let mut uneditable = Vec::new();
+    if let Some(maker_note) = &eed.uneditable.maker_note {
+        uneditable.push(("MakerNote".to_string(), maker_note.clone()));
+    }
+    uneditable.extend(eed.uneditable.unknown_all());
+    if !uneditable.is_empty() {
+        ret.push(("Uneditable", uneditable));
+    }
+    ret
+}
+
+pub fn to_json(eed: &ExifEditData) -> Value {
+    let mut root = Map::new();
+    for (group, values) in grouped_values(eed).into_iter() {
+        let obj = values.into_iter().map(|(name, value)| (name, Value::String(value))).collect::<Map<String, Value>>();
+        root.insert(group.to_string(), Value::Object(obj));
+    }
+    Value::Object(root)
+}
+
+fn decimal_value(v: f64) -> Option<Value> {
+    Number::from_f64((v * 1e10).round() / 1e10).map(Value::Number)
+}
+
+// exiftool -nと同じく、有理数は小数にし、複数の値は空白で区切る。緯度・経度は参照（N/S, E/W）を符号にした度にする
+fn exiftool_value(eed: &ExifEditData, tag: &ExifTag) -> Value {
+    let info = &eed.gps_info.location_info;
+    let coordinate = match tag {
+        ExifTag::GPSLatitude(v) => Some((info.gps_latitude_ref, v)),
+        ExifTag::GPSLongitude(v) => Some((info.gps_longitude_ref, v)),
+        ExifTag::GPSDestLatitude(v) => Some((info.gps_dest_latitude_ref, v)),
+        ExifTag::GPSDestLongitude(v) => Some((info.gps_dest_longitude_ref, v)),
+        _ => None,
+    };
+    if let Some(value) = coordinate.and_then(|(r, v)| decimal_value(signed_degree(r, &DMS::from_vec(v)?))) {
+        return value;
+    }
+
+    let s = value_string(tag);
+    if !is_numeric(tag) {
+        return Value::String(s);
+    }
+    let values = s.split(", ").map(|vi| match vi.split_once('/') {
+        Some((n, d)) => match (n.parse::<f64>(), d.parse::<f64>()) {
+            (Ok(n), Ok(d)) if d != 0.0 => decimal_value(n / d).unwrap_or_else(|| Value::String(vi.to_string())),
+            _ => Value::String(vi.to_string()),
+        },
+        None => vi.parse::<i64>().map(|n| Value::Number(n.into())).unwrap_or_else(|_| Value::String(vi.to_string())),
+    }).collect::<Vec<Value>>();
+    match values.as_slice() {
+        [value] => value.clone(),
+        _ => Value::String(values.iter().map(|v| match v {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        }).collect::<Vec<String>>().join(" ")),
+    }
+}
+
+pub fn to_exiftool_json(files: &[(&str, &ExifEditData)]) -> Value {
+    Value::Array(files.iter().map(|(name, eed)| exiftool_object(name, eed)).collect())
+}
+
+fn exiftool_object(source_file: &str, eed: &ExifEditData) -> Value {
+    let mut obj = Map::new();
+    obj.insert("SourceFile".to_string(), Value::String(source_file.to_string()));
+    for (key, tag) in tags_by_key(&eed.metadata).into_iter() {
+        // オフセットなどファイル構造に関わるタグはexiftoolの出力に含めない
+        if is_unknown(&tag) || STRUCTURAL_TAGS.contains(&key.name.as_str()) {
+            continue;
+        }
+        // IFD1のタグはexiftool -G1と同じく"IFD1:"を付けて区別する
+        let name = match key.ifd {
+            0 => exiftool_name(&key.name).to_string(),
+            ifd => format!("IFD{}:{}", ifd, exiftool_name(&key.name)),
+        };
+        obj.insert(name, exiftool_value(eed, &tag));
+    }
+    Value::Object(obj)
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') || s.contains('\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// 1ファイル1行。列は全ファイルに現れたタグを分類順に並べる
+pub fn to_csv(files: &[(&str, &ExifEditData)]) -> String {
+    let rows = files.iter()
+        .map(|(name, eed)| {
+            let values = grouped_values(eed).into_iter()
+                .flat_map(|(_, values)| values.into_iter())
+                .collect::<Vec<(String, String)>>();
+            (*name, values)
+        })
+        .collect::<Vec<(&str, Vec<(String, String)>)>>();
+
+    let mut columns: Vec<String> = Vec::new();
+    for (_, values) in rows.iter() {
+        for (name, _) in values.iter() {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str(&std::iter::once("SourceFile".to_string())
+        .chain(columns.iter().map(|c| csv_field(c)))
+        .collect::<Vec<String>>().join(","));
+    out.push_str("\r\n");
+    for (name, values) in rows.iter() {
+        let values = values.iter().cloned().collect::<BTreeMap<String, String>>();
+        out.push_str(&std::iter::once(csv_field(name))
+            .chain(columns.iter().map(|c| csv_field(values.get(c).map(|v| v.as_str()).unwrap_or(""))))
+            .collect::<Vec<String>>().join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
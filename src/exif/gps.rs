@@ -1,19 +1,17 @@
 use chrono::{NaiveDate, NaiveTime};
 
 use little_exif::exif_tag::ExifTag;
-use little_exif::metadata::Metadata;
 use little_exif::rational::uR64;
 
 use super::utils::{
-    F64, AllList, RemoveTag, 
+    F64,
     pick_v0, pick_v0_ur64, 
     pick_v04, pick_v03_ur64,
     some_string,
 };
 
-use crate::exif::rational::approx_frac;
+use super::ExifEditData;
 use crate::{define_big_struct, define_enum, define_str_enum, define_struct};
-use crate::components::utils::ShowValue;
 
 define_big_struct!(
     GpsInfo,
@@ -104,8 +102,149 @@ define_struct!(
     }
 );
 
+// 北緯・東経ならtrue、南緯・西経ならfalse。それ以外の値は読み取れないものとする
 pub fn gps_ref(s: &str) -> Option<bool> {
-    Some(s == "N" || s == "E")
+    match s.trim() {
+        "N" | "E" => Some(true),
+        "S" | "W" => Some(false),
+        _ => None,
+    }
+}
+
+// 秒の分母の既定値。1/10000秒は赤道上で約3mm
+pub const SECONDS_DENOMINATOR: u32 = 10000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Latitude,
+    Longitude,
+}
+
+impl Axis {
+    pub fn limit(&self) -> f64 {
+        match self {
+            Axis::Latitude => 90.0,
+            Axis::Longitude => 180.0,
+        }
+    }
+
+    pub fn ref_str(&self, positive: bool) -> &'static str {
+        match (self, positive) {
+            (Axis::Latitude, true) => "N",
+            (Axis::Latitude, false) => "S",
+            (Axis::Longitude, true) => "E",
+            (Axis::Longitude, false) => "W",
+        }
+    }
+
+    // 符号付きの度を、参照（N/S, E/W）と度分秒の組にする
+    pub fn encode(&self, value: f64, den: Option<u32>) -> Option<(String, Vec<uR64>)> {
+        if !value.is_finite() || value.abs() > self.limit() { return None; }
+        let dms = DMS::from_f64(value, den)?;
+        Some((self.ref_str(value >= 0.0).to_string(), dms.to_vec()))
+    }
+
+    pub fn decode(&self, r: &str, v: &[uR64]) -> Option<f64> {
+        let positive = gps_ref(r)?;
+        let value = DMS::from_vec(v)?.to_f64();
+        if value > self.limit() { return None; }
+        Some(if positive { value } else { -value })
+    }
+}
+
+pub fn signed_degree(r: Option<bool>, dms: &DMS) -> f64 {
+    if r.unwrap_or(true) { dms.to_f64() } else { -dms.to_f64() }
+}
+
+impl ExifEditData {
+    // 南緯・西経は負の値にする
+    pub fn position(&self) -> Option<(f64, f64)> {
+        let info = &self.gps_info.location_info;
+        let (lat, lng) = (info.gps_latitude.as_ref()?, info.gps_longitude.as_ref()?);
+        Some((signed_degree(info.gps_latitude_ref, lat), signed_degree(info.gps_longitude_ref, lng)))
+    }
+
+    pub fn dest_position(&self) -> Option<(f64, f64)> {
+        let info = &self.gps_info.location_info;
+        let (lat, lng) = (info.gps_dest_latitude.as_ref()?, info.gps_dest_longitude.as_ref()?);
+        Some((signed_degree(info.gps_dest_latitude_ref, lat), signed_degree(info.gps_dest_longitude_ref, lng)))
+    }
+
+    // 範囲外の値なら何も書き込まずfalseを返す
+    pub fn set_lat_lng(&mut self, lat: f64, lng: f64) -> bool {
+        let (Some((lat_ref, lat)), Some((lng_ref, lng))) = (
+            Axis::Latitude.encode(lat, None),
+            Axis::Longitude.encode(lng, None),
+        ) else { return false; };
+        self.update_tag(ExifTag::GPSLatitudeRef(lat_ref));
+        self.update_tag(ExifTag::GPSLatitude(lat));
+        self.update_tag(ExifTag::GPSLongitudeRef(lng_ref));
+        self.update_tag(ExifTag::GPSLongitude(lng));
+        true
+    }
+
+    pub fn set_dest_lat_lng(&mut self, lat: f64, lng: f64) -> bool {
+        let (Some((lat_ref, lat)), Some((lng_ref, lng))) = (
+            Axis::Latitude.encode(lat, None),
+            Axis::Longitude.encode(lng, None),
+        ) else { return false; };
+        self.update_tag(ExifTag::GPSDestLatitudeRef(lat_ref));
+        self.update_tag(ExifTag::GPSDestLatitude(lat));
+        self.update_tag(ExifTag::GPSDestLongitudeRef(lng_ref));
+        self.update_tag(ExifTag::GPSDestLongitude(lng));
+        true
+    }
+
+    // 方位はいずれも真北を基準として書き込む
+    pub fn set_img_direction(&mut self, degrees: f64) {
+        self.update_tag(ExifTag::GPSImgDirectionRef(NorthRef::TrueNorth.to_str()));
+        self.update_tag(ExifTag::GPSImgDirection(bearing_vec(degrees)));
+    }
+
+    pub fn set_track(&mut self, degrees: f64) {
+        self.update_tag(ExifTag::GPSTrackRef(NorthRef::TrueNorth.to_str()));
+        self.update_tag(ExifTag::GPSTrack(bearing_vec(degrees)));
+    }
+
+    // 目的地を書き込み、撮影場所があればそこからの方位と距離（km）も書き換える
+    pub fn set_dest_from_position(&mut self, lat: f64, lng: f64) -> bool {
+        if !self.set_dest_lat_lng(lat, lng) {
+            return false;
+        }
+        if let Some(from) = self.position() {
+            self.update_tag(ExifTag::GPSDestBearingRef(NorthRef::TrueNorth.to_str()));
+            self.update_tag(ExifTag::GPSDestBearing(bearing_vec(bearing(from, (lat, lng)))));
+            self.update_tag(ExifTag::GPSDestDistanceRef(GPSDestDistanceRef::Kilimeters.to_str()));
+            self.update_tag(ExifTag::GPSDestDistance(vec![
+                uR64 { nominator: (distance(from, (lat, lng)) / 10.0).round() as u32, denominator: 100 }
+            ]));
+        }
+        true
+    }
+}
+
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+// fromからtoへの大円の初期方位（度、0以上360未満）
+pub fn bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (phi1, phi2) = (from.0.to_radians(), to.0.to_radians());
+    let dl = (to.1 - from.1).to_radians();
+    let y = dl.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dl.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+// 2点間の大円距離（m）
+pub fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (phi1, phi2) = (from.0.to_radians(), to.0.to_radians());
+    let (dp, dl) = (phi2 - phi1, (to.1 - from.1).to_radians());
+    let a = (dp / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dl / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
+
+fn bearing_vec(degrees: f64) -> Vec<uR64> {
+    let hundredths = (degrees.rem_euclid(360.0) * 100.0).round() as u32 % 36000;
+    vec![uR64 { nominator: hundredths, denominator: 100 }]
 }
 
 pub fn parse_date(s: &str) -> Option<NaiveDate> {
@@ -158,28 +297,22 @@ impl DMS {
             uR64 { nominator: self.degree as u32, denominator: 1 },
             uR64 { nominator: self.minute as u32, denominator: 1 },
             uR64 {
-                nominator: (self.second.value() * self.second.den() as f64) as u32, 
+                nominator: (self.second.value() * self.second.den() as f64).round() as u32,
                 denominator: self.second.den()
             }
         ]
     }
 
+    // 符号は無視し、絶対値を度分秒にする。秒は分母denの単位で丸めてから繰り上げるため、60秒や60分にはならない
     pub fn from_f64(f: f64, den: Option<u32>) -> Option<Self> {
-        if f < -180.0 || f > 180.0 { return None; }
-        let mut value = f.abs();
-        let degree = value as u8;
-        value = (value - degree as f64) * 60.0;
-        let minute = value as u8;
-        value = (value - minute as f64) * 60.0;
-        let (value, den) = match den {
-            Some(den) => (value, den),
-            None => {
-                let (value, _, den) = approx_frac(value).unwrap();
-                (value, den as u32)
-            }
-        };
-        let second = F64::new(value, den);
-        Some(Self { degree, minute, second })
+        if !f.is_finite() || f < -180.0 || f > 180.0 { return None; }
+        let den = den.unwrap_or(SECONDS_DENOMINATOR).max(1) as u64;
+        let units = (f.abs() * 3600.0 * den as f64).round() as u64;
+        let degree = units / (3600 * den);
+        let minute = units % (3600 * den) / (60 * den);
+        let second = units % (60 * den);
+        if degree > 180 { return None; }
+        Some(Self { degree: degree as u8, minute: minute as u8, second: F64::new(second as f64 / den as f64, den as u32) })
     }
 
     pub fn to_f64(&self) -> f64 {
@@ -187,6 +320,179 @@ impl DMS {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum CoordToken {
+    Number(f64),
+    Hemisphere(char),
+}
+
+fn hemisphere(word: &str) -> Option<char> {
+    match word {
+        "N" | "n" | "北緯" => Some('N'),
+        "S" | "s" | "南緯" => Some('S'),
+        "E" | "e" | "東経" => Some('E'),
+        "W" | "w" | "西経" => Some('W'),
+        _ => None,
+    }
+}
+
+fn coord_tokens(s: &str) -> Vec<CoordToken> {
+    let chars = s.chars().collect::<Vec<char>>();
+    let mut ret = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let signed = (c == '-' || c == '+') && chars.get(i + 1).map(|n| n.is_ascii_digit() || *n == '.').unwrap_or(false);
+        if c.is_ascii_digit() || signed || (c == '.' && chars.get(i + 1).map(|n| n.is_ascii_digit()).unwrap_or(false)) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if let Ok(v) = chars[start..i].iter().collect::<String>().parse::<f64>() {
+                ret.push(CoordToken::Number(v));
+            }
+        } else if c.is_alphabetic() && c != 'º' {
+            // "lat"のような方位以外の語は読み飛ばす
+            let start = i;
+            while i < chars.len() && chars[i].is_alphabetic() && chars[i] != 'º' {
+                i += 1;
+            }
+            if let Some(h) = hemisphere(&chars[start..i].iter().collect::<String>()) {
+                ret.push(CoordToken::Hemisphere(h));
+            }
+        } else {
+            i += 1;
+        }
+    }
+    ret
+}
+
+// 度・分・秒（分と秒は省略可）を10進の度にする
+fn dms_value(numbers: &[f64]) -> Result<f64, String> {
+    let (deg, rest) = match numbers.split_first() {
+        Some((deg, rest)) if rest.len() <= 2 => (*deg, rest),
+        _ => return Err("度・分・秒の数が正しくありません".to_string()),
+    };
+    if rest.iter().any(|v| *v < 0.0 || *v >= 60.0) || (!rest.is_empty() && deg.fract() != 0.0) {
+        return Err("分・秒は0以上60未満で指定してください".to_string());
+    }
+    let abs = deg.abs() + rest.first().unwrap_or(&0.0) / 60.0 + rest.get(1).unwrap_or(&0.0) / 3600.0;
+    Ok(if deg.is_sign_negative() { -abs } else { abs })
+}
+
+fn coordinates_from_tokens(tokens: &[CoordToken]) -> Result<(f64, f64), String> {
+    let hemispheres = tokens.iter().enumerate()
+        .filter_map(|(i, t)| match t { CoordToken::Hemisphere(h) => Some((i, *h)), _ => None })
+        .collect::<Vec<(usize, char)>>();
+    let numbers = |range: &[CoordToken]| range.iter()
+        .filter_map(|t| match t { CoordToken::Number(v) => Some(*v), _ => None })
+        .collect::<Vec<f64>>();
+
+    let (first, second, h1, h2) = match hemispheres.as_slice() {
+        [] => {
+            let all = numbers(tokens);
+            if all.is_empty() || all.len() % 2 != 0 {
+                return Err("緯度と経度の2つの値を指定してください".to_string());
+            }
+            let (a, b) = all.split_at(all.len() / 2);
+            (a.to_vec(), b.to_vec(), None, None)
+        }
+        [(i, h1), (j, h2)] => {
+            // N35 E139 のように方位が前にあるか、35N 139E のように後ろにあるか
+            if numbers(&tokens[..*i]).is_empty() {
+                (numbers(&tokens[i + 1..*j]), numbers(&tokens[j + 1..]), Some(*h1), Some(*h2))
+            } else {
+                (numbers(&tokens[..*i]), numbers(&tokens[i + 1..*j]), Some(*h1), Some(*h2))
+            }
+        }
+        _ => return Err("N/S/E/Wは緯度と経度に1つずつ指定してください".to_string()),
+    };
+
+    let (mut a, mut b) = (dms_value(&first)?, dms_value(&second)?);
+    let apply = |v: f64, h: Option<char>| match h {
+        Some('S') | Some('W') => -v.abs(),
+        Some(_) => v.abs(),
+        None => v,
+    };
+    a = apply(a, h1);
+    b = apply(b, h2);
+    match (h1, h2) {
+        (Some('E') | Some('W'), Some('N') | Some('S')) => Ok((b, a)),
+        (Some('N') | Some('S'), Some('N') | Some('S')) | (Some('E') | Some('W'), Some('E') | Some('W')) => {
+            Err("緯度と経度の方位が重複しています".to_string())
+        }
+        _ => Ok((a, b)),
+    }
+}
+
+fn url_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+// Googleマップの共有URL（!3d緯度!4d経度、@緯度,経度、?q=緯度,経度 など）から座標を取り出す
+fn coordinates_from_url(url: &str) -> Option<String> {
+    if let (Some(i), Some(j)) = (url.find("!3d"), url.find("!4d")) {
+        let lat = url[i + 3..].split('!').next()?;
+        let lng = url[j + 3..].split(|c| c == '!' || c == '?' || c == '/').next()?;
+        return Some(format!("{},{}", lat, lng));
+    }
+    if let Some(i) = url.find('@') {
+        let v = url[i + 1..].split(',').take(2).collect::<Vec<&str>>();
+        if v.len() == 2 {
+            return Some(v.join(","));
+        }
+    }
+    let query = url.split_once('?')?.1;
+    query.split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| ["q", "query", "ll", "center", "destination"].contains(k))
+        .map(|(_, v)| url_decode(v))
+}
+
+// 10進の度、度分秒（35°39'31.2"N 139°42'10.5"E）、度分（35°39.52'N）、
+// GoogleマップのURL、geo: URIを読み取り、(緯度, 経度)を返す
+pub fn parse_coordinates(s: &str) -> Result<(f64, f64), String> {
+    let s = s.trim();
+    let text = if let Some(rest) = s.strip_prefix("geo:") {
+        rest.split(|c| c == ';' || c == '?').next().unwrap_or("").split(',').take(2).collect::<Vec<&str>>().join(",")
+    } else if s.starts_with("http://") || s.starts_with("https://") {
+        coordinates_from_url(s).ok_or_else(|| "URLに座標が含まれていません".to_string())?
+    } else {
+        s.to_string()
+    };
+    let (lat, lng) = coordinates_from_tokens(&coord_tokens(&text))?;
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(format!("緯度は-90から90の範囲で指定してください: {}", lat));
+    }
+    if !(-180.0..=180.0).contains(&lng) {
+        return Err(format!("経度は-180から180の範囲で指定してください: {}", lng));
+    }
+    Ok((lat, lng))
+}
+
 define_enum!(
     GPSAltitudeRef {
         AboveSeaLevel = 0,
@@ -0,0 +1,234 @@
+use little_exif::exif_tag::ExifTag;
+use little_exif::metadata::Metadata;
+use serde_json::Value;
+
+use super::ExifEditData;
+use super::gps::Axis;
+use super::tag_value::{empty_tag, from_exiftool_name, parse_tag, TagValueError};
+use super::user::UserComment;
+
+#[derive(Clone, PartialEq, Default)]
+pub struct ImportReport {
+    pub applied: Vec<String>,
+    pub invalid: Vec<(String, String)>,
+    pub unsupported: Vec<String>,
+    // 配列の2つ目以降のオブジェクト（読み込まなかったもの）の数
+    pub skipped_objects: usize,
+    // exiftoolの出力（SourceFileを含む）だったか
+    pub from_exiftool: bool,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "反映: {}件、値が不正: {}件、未対応のタグ: {}件",
+            self.applied.len(), self.invalid.len(), self.unsupported.len()
+        )
+    }
+
+    // 読み込みで気をつけること
+    pub fn notes(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        if self.skipped_objects > 0 {
+            ret.push(format!("配列の先頭のオブジェクトだけを読み込み、残りの{}件は読み込んでいません。", self.skipped_objects));
+        }
+        if self.from_exiftool && !self.invalid.is_empty() {
+            ret.push("exiftoolの出力は、-nを付けて（exiftool -j -n）値を数値のまま書き出したものを読み込んでください。".to_string());
+        }
+        ret
+    }
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub struct ImportDocument {
+    pub entries: Vec<(String, Value)>,
+    pub skipped_objects: usize,
+}
+
+// exiftoolの出力に含まれる、Exifタグではない項目
+const IGNORED_KEYS: &[&str] = &[
+    "SourceFile", "ExifToolVersion", "FileName", "Directory", "FileSize",
+    "FileModifyDate", "FileAccessDate", "FileInodeChangeDate", "FilePermissions",
+    "FileType", "FileTypeExtension", "MIMEType",
+];
+
+const COORDINATE_TAGS: &[&str] = &["GPSLatitude", "GPSLongitude", "GPSDestLatitude", "GPSDestLongitude"];
+
+fn number_string(n: &serde_json::Number) -> String {
+    if let Some(u) = n.as_u64() {
+        u.to_string()
+    } else if let Some(i) = n.as_i64() {
+        i.to_string()
+    } else {
+        match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            Some(f) => f.to_string(),
+            None => n.to_string(),
+        }
+    }
+}
+
+fn byte_array(a: &[Value]) -> Option<Vec<u8>> {
+    a.iter().map(|v| v.as_u64().and_then(|u| u8::try_from(u).ok())).collect()
+}
+
+// exiftoolが-nなしで出力する表示用の値のうち、文字列のタグとしてそのまま書き込まれてしまうものを元の値に戻す。
+// 数値のタグの表示用の値（"Horizontal (normal)"など）は読み込めないものとして報告する
+fn printed_value(name: &str, value: &str) -> Option<&'static str> {
+    let raw = match (name, value) {
+        ("GPSLatitudeRef" | "GPSDestLatitudeRef", "North") => "N",
+        ("GPSLatitudeRef" | "GPSDestLatitudeRef", "South") => "S",
+        ("GPSLongitudeRef" | "GPSDestLongitudeRef", "East") => "E",
+        ("GPSLongitudeRef" | "GPSDestLongitudeRef", "West") => "W",
+        ("GPSTrackRef" | "GPSImgDirectionRef" | "GPSDestBearingRef", "True North") => "T",
+        ("GPSTrackRef" | "GPSImgDirectionRef" | "GPSDestBearingRef", "Magnetic North") => "M",
+        ("GPSSpeedRef", "km/h") => "K",
+        ("GPSSpeedRef", "mph") => "M",
+        ("GPSSpeedRef", "knots") => "N",
+        ("GPSDestDistanceRef", "Kilometers") => "K",
+        ("GPSDestDistanceRef", "Miles") => "M",
+        ("GPSDestDistanceRef", "Nautical Miles") => "N",
+        ("GPSStatus", "Measurement Active") => "A",
+        ("GPSStatus", "Measurement Void") => "V",
+        ("GPSMeasureMode", "2-Dimensional Measurement") => "2",
+        ("GPSMeasureMode", "3-Dimensional Measurement") => "3",
+        ("InteroperabilityIndex", v) if v.starts_with("R98 - ") => "R98",
+        ("InteroperabilityIndex", v) if v.starts_with("R03 - ") => "R03",
+        ("InteroperabilityIndex", v) if v.starts_with("THM - ") => "THM",
+        _ => return None,
+    };
+    Some(raw)
+}
+
+// JSONの値を、parse_tagが受け付ける文字列表現にする
+fn value_to_string(name: &str, value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(printed_value(name, s).map(|raw| raw.to_string()).unwrap_or_else(|| s.clone())),
+        Value::Number(n) => Some(number_string(n)),
+        Value::Array(a) => match name {
+            // バイト列として渡されたバージョンやコメントは、文字列に戻してから読み込む
+            "ExifVersion" | "FlashpixVersion" => byte_array(a).map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
+            "UserComment" => UserComment::from_vec(&byte_array(a)?).map(|uc| uc.decoded),
+            _ => a.iter().map(|ai| match ai {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(number_string(n)),
+                _ => None,
+            }).collect::<Option<Vec<String>>>().map(|v| v.join(", ")),
+        },
+        _ => None,
+    }
+}
+
+// exiftool -nは緯度・経度を符号付きの度で出力するので、参照（N/S, E/W）のタグと度分秒のタグに分ける
+fn coordinate_tags(name: &str, value: f64) -> Option<(ExifTag, ExifTag)> {
+    let axis = match name {
+        "GPSLatitude" | "GPSDestLatitude" => Axis::Latitude,
+        _ => Axis::Longitude,
+    };
+    let (r, v) = axis.encode(value, None)?;
+    Some(match name {
+        "GPSLatitude" => (ExifTag::GPSLatitudeRef(r), ExifTag::GPSLatitude(v)),
+        "GPSLongitude" => (ExifTag::GPSLongitudeRef(r), ExifTag::GPSLongitude(v)),
+        "GPSDestLatitude" => (ExifTag::GPSDestLatitudeRef(r), ExifTag::GPSDestLatitude(v)),
+        "GPSDestLongitude" => (ExifTag::GPSDestLongitudeRef(r), ExifTag::GPSDestLongitude(v)),
+        _ => return None,
+    })
+}
+
+pub fn json_to_tag(name: &str, value: &Value) -> Result<ExifTag, TagValueError> {
+    match value_to_string(name, value) {
+        Some(s) => parse_tag(name, &s),
+        None => Err(TagValueError::InvalidValue(name.to_string(), value.to_string())),
+    }
+}
+
+// 単純な{タグ名: 値}、分類ごとにまとめたエクスポート形式、exiftool -j -nの配列のいずれも受け付ける。
+// 配列は先頭のオブジェクトだけを読む
+pub fn entries(document: &Value) -> Result<ImportDocument, String> {
+    let (object, skipped_objects) = match document {
+        Value::Object(map) => (map, 0),
+        Value::Array(a) => match a.first() {
+            Some(Value::Object(map)) => (map, a.len() - 1),
+            _ => return Err("JSONの配列にオブジェクトが含まれていません".to_string()),
+        },
+        _ => return Err("JSONのオブジェクトまたは配列を指定してください".to_string()),
+    };
+
+    let mut ret = Vec::new();
+    for (key, value) in object.iter() {
+        match value {
+            Value::Object(group) => {
+                for (k, v) in group.iter() {
+                    ret.push((k.clone(), v.clone()));
+                }
+            }
+            _ => ret.push((key.clone(), value.clone())),
+        }
+    }
+    Ok(ImportDocument { entries: ret, skipped_objects })
+}
+
+pub fn parse_document(text: &str) -> Result<ImportDocument, String> {
+    let document = serde_json::from_str::<Value>(text).map_err(|e| format!("JSONを読み込めません: {}", e))?;
+    entries(&document)
+}
+
+impl ExifEditData {
+    pub fn import_entries(&mut self, document: &ImportDocument) -> ImportReport {
+        let mut report = ImportReport { skipped_objects: document.skipped_objects, ..Default::default() };
+        for (key, value) in document.entries.iter() {
+            // サムネイル（IFD1）のタグを本体に書き込まないよう、読み込まない
+            if key.starts_with("IFD1:") {
+                report.unsupported.push(key.clone());
+                continue;
+            }
+            // "EXIF:Make"のようなグループ付きの名前はグループを外す
+            let key = key.rsplit(':').next().unwrap_or(key);
+            if key == "SourceFile" {
+                report.from_exiftool = true;
+            }
+            if IGNORED_KEYS.contains(&key) {
+                continue;
+            }
+            let name = from_exiftool_name(key);
+            if empty_tag(name).is_none() {
+                report.unsupported.push(key.to_string());
+                continue;
+            }
+            if let (true, Some(f)) = (COORDINATE_TAGS.contains(&name), value.as_f64()) {
+                match coordinate_tags(name, f) {
+                    Some((ref_tag, tag)) => {
+                        // 正の値は符号のないExifの値で、参照のタグが別にある場合がある。そのときは参照のタグに従う
+                        let ref_name = format!("{}Ref", name);
+                        if f < 0.0 || !document.entries.iter().any(|(k, _)| k.rsplit(':').next() == Some(ref_name.as_str())) {
+                            self.update_tag(ref_tag);
+                            report.applied.push(ref_name);
+                        }
+                        self.update_tag(tag);
+                        report.applied.push(name.to_string());
+                    }
+                    None => report.invalid.push((name.to_string(), value.to_string())),
+                }
+                continue;
+            }
+            match json_to_tag(name, value) {
+                Ok(tag) => {
+                    self.update_tag(tag);
+                    report.applied.push(name.to_string());
+                }
+                Err(TagValueError::InvalidValue(_, v)) => report.invalid.push((name.to_string(), v)),
+                Err(_) => report.unsupported.push(key.to_string()),
+            }
+        }
+        report
+    }
+}
+
+// HEIC読み込み時にJS側で得たExif辞書など、JSONからMetadataを組み立てる
+pub fn metadata_from_json(document: &Value) -> Metadata {
+    let mut eed = ExifEditData::empty();
+    if let Ok(document) = entries(document) {
+        eed.import_entries(&document);
+    }
+    eed.metadata
+}
@@ -1,10 +1,6 @@
-use little_exif::exif_tag::ExifTag;
-use little_exif::metadata::Metadata;
-
-use super::utils::{AllList, RemoveTag, pick_v0, pick_v04};
+use super::utils::{pick_v0, pick_v04};
 
 use crate::{define_str_enum, define_struct};
-use crate::components::utils::ShowValue;
 
 define_struct!(
     InteropInfo,
@@ -0,0 +1,284 @@
+use little_exif::exif_tag::ExifTag;
+
+use super::ExifEditData;
+
+pub mod apple;
+pub mod canon;
+pub mod fujifilm;
+pub mod nikon;
+pub mod panasonic;
+pub mod sony;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    // "II"または"MM"
+    pub fn from_marker(marker: &[u8]) -> Option<Self> {
+        match marker {
+            b"II" => Some(ByteOrder::Little),
+            b"MM" => Some(ByteOrder::Big),
+            _ => None,
+        }
+    }
+
+    pub fn u16(&self, b: &[u8]) -> u16 {
+        match self {
+            ByteOrder::Little => u16::from_le_bytes([b[0], b[1]]),
+            ByteOrder::Big => u16::from_be_bytes([b[0], b[1]]),
+        }
+    }
+
+    pub fn u32(&self, b: &[u8]) -> u32 {
+        match self {
+            ByteOrder::Little => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            ByteOrder::Big => u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+        }
+    }
+
+    // 本体のTIFFと同じバイト順を使うメーカーのため、IFDとして読めるほうを選ぶ
+    pub fn guess(data: &[u8], ifd_offset: usize) -> Option<Self> {
+        [ByteOrder::Little, ByteOrder::Big].into_iter().find(|order| {
+            let Some(count) = ifd_offset.checked_add(2).and_then(|end| data.get(ifd_offset..end)).map(|b| order.u16(b) as usize) else { return false; };
+            if count == 0 || count > 512 || ifd_end(ifd_offset, count).is_none_or(|end| end > data.len()) { return false; }
+            (0..count.min(4)).all(|i| {
+                let at = ifd_offset + 2 + i * 12 + 2;
+                format_size(order.u16(&data[at..at + 2])).is_some()
+            })
+        })
+    }
+}
+
+// プライバシーの観点で注意が必要な項目の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sensitivity {
+    // シリアル番号や所有者名、写真ごとの固有IDなど、撮影機器や撮影者をたどれるもの
+    Device,
+    Location,
+    // 顔検出・顔認識の結果や名前
+    Person,
+    Time,
+}
+
+impl Sensitivity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Sensitivity::Device => "機器の識別情報",
+            Sensitivity::Location => "位置情報",
+            Sensitivity::Person => "人物の情報",
+            Sensitivity::Time => "日時",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TagInfo {
+    pub tag: u16,
+    pub name: &'static str,
+    pub sensitivity: Option<Sensitivity>,
+}
+
+pub const fn tag(tag: u16, name: &'static str, sensitivity: Option<Sensitivity>) -> TagInfo {
+    TagInfo { tag, name, sensitivity }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MakerNoteField {
+    pub tag: u16,
+    pub name: String,
+    pub value: String,
+    pub sensitivity: Option<Sensitivity>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MakerNote {
+    pub vendor: &'static str,
+    pub fields: Vec<MakerNoteField>,
+}
+
+// IFDの値のオフセットの起点
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OffsetBase {
+    // MakerNote内の位置
+    At(usize),
+    // 本体のTIFFヘッダ。MakerNoteの元の位置は分からないため、値がIFDの直後から並んでいるとみなして推定する
+    Tiff,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IfdLayout {
+    pub ifd_offset: usize,
+    pub order: ByteOrder,
+    pub base: OffsetBase,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IfdEntry {
+    pub tag: u16,
+    pub format: u16,
+    pub count: u32,
+    // オフセットがMakerNoteの外を指していればNone
+    pub data: Option<Vec<u8>>,
+}
+
+// エントリ数countのIFDの終わり（次のIFDへのポインタの手前）
+fn ifd_end(start: usize, count: usize) -> Option<usize> {
+    start.checked_add(2)?.checked_add(count.checked_mul(12)?)
+}
+
+fn format_size(format: u16) -> Option<usize> {
+    match format {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+pub fn read_ifd(data: &[u8], layout: &IfdLayout) -> Option<Vec<IfdEntry>> {
+    let order = layout.order;
+    let start = layout.ifd_offset;
+    let count = order.u16(data.get(start..start.checked_add(2)?)?) as usize;
+    let end = ifd_end(start, count)?;
+    if end > data.len() { return None; }
+
+    let mut raw = Vec::with_capacity(count);
+    for i in 0..count {
+        let at = start + 2 + i * 12;
+        let tag = order.u16(&data[at..at + 2]);
+        let format = order.u16(&data[at + 2..at + 4]);
+        let count = order.u32(&data[at + 4..at + 8]);
+        let Some(size) = format_size(format).and_then(|s| s.checked_mul(count as usize)) else { continue; };
+        raw.push((tag, format, count, size, &data[at + 8..at + 12]));
+    }
+
+    let shift = match layout.base {
+        OffsetBase::At(pos) => pos as i64,
+        OffsetBase::Tiff => raw.iter()
+            .filter(|(_, _, _, size, _)| *size > 4)
+            .map(|(_, _, _, _, field)| order.u32(field) as i64)
+            .min()
+            // 次のIFDへのポインタ（4バイト）の後ろ
+            .map(|min| end as i64 + 4 - min)
+            .unwrap_or(0),
+    };
+
+    Some(raw.into_iter().map(|(tag, format, count, size, field)| {
+        let data = if size <= 4 {
+            Some(field[..size].to_vec())
+        } else {
+            let offset = order.u32(field) as i64 + shift;
+            usize::try_from(offset).ok()
+                .and_then(|offset| data.get(offset..offset.checked_add(size)?))
+                .map(|b| b.to_vec())
+        };
+        IfdEntry { tag, format, count, data }
+    }).collect())
+}
+
+const MAX_VALUES: usize = 16;
+
+fn join_values<T: ToString>(values: impl Iterator<Item = T>, count: u32) -> String {
+    let mut ret = values.take(MAX_VALUES).map(|v| v.to_string()).collect::<Vec<String>>().join(" ");
+    if count as usize > MAX_VALUES {
+        ret.push_str(&format!(" …（全{}個）", count));
+    }
+    ret
+}
+
+impl IfdEntry {
+    pub fn value_string(&self, order: ByteOrder) -> String {
+        let Some(data) = self.data.as_ref() else { return "（読み取れません）".to_string(); };
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+        match self.format {
+            2 => text(data.split(|b| *b == 0).next().unwrap_or(&[])),
+            1 => join_values(data.iter(), self.count),
+            6 => join_values(data.iter().map(|b| *b as i8), self.count),
+            3 => join_values(data.chunks_exact(2).map(|c| order.u16(c)), self.count),
+            8 => join_values(data.chunks_exact(2).map(|c| order.u16(c) as i16), self.count),
+            4 | 13 => join_values(data.chunks_exact(4).map(|c| order.u32(c)), self.count),
+            9 => join_values(data.chunks_exact(4).map(|c| order.u32(c) as i32), self.count),
+            5 => join_values(data.chunks_exact(8).map(|c| format!("{}/{}", order.u32(&c[..4]), order.u32(&c[4..]))), self.count),
+            10 => join_values(data.chunks_exact(8).map(|c| format!("{}/{}", order.u32(&c[..4]) as i32, order.u32(&c[4..]) as i32)), self.count),
+            11 => join_values(data.chunks_exact(4).map(|c| f32::from_bits(order.u32(c))), self.count),
+            12 => join_values(data.chunks_exact(8).map(|c| {
+                let (hi, lo) = match order {
+                    ByteOrder::Little => (order.u32(&c[4..]), order.u32(&c[..4])),
+                    ByteOrder::Big => (order.u32(&c[..4]), order.u32(&c[4..])),
+                };
+                f64::from_bits(((hi as u64) << 32) | lo as u64)
+            }), self.count),
+            // UNDEFINED: 文字列として読めればそのまま、そうでなければ先頭を16進数で表示する
+            _ => {
+                let trimmed = data.split(|b| *b == 0).next().unwrap_or(&[]);
+                if trimmed.len() >= 2 && trimmed.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+                    text(trimmed)
+                } else {
+                    let mut hex = data.iter().take(32).map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ");
+                    if data.len() > 32 {
+                        hex.push_str(&format!(" …（{}バイト）", data.len()));
+                    }
+                    hex
+                }
+            }
+        }
+    }
+}
+
+pub trait MakerNoteParser {
+    fn vendor(&self) -> &'static str;
+    // ヘッダの識別子、またはExifのMakeで判定する
+    fn detect(&self, make: &str, data: &[u8]) -> bool;
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout>;
+    fn tags(&self) -> &'static [TagInfo];
+
+    fn parse(&self, data: &[u8]) -> Result<MakerNote, String> {
+        let layout = self.layout(data)
+            .ok_or_else(|| format!("{}のMakerNoteの形式を認識できません", self.vendor()))?;
+        let entries = read_ifd(data, &layout)
+            .ok_or_else(|| format!("{}のMakerNoteのIFDを読み込めません", self.vendor()))?;
+        let fields = entries.iter().map(|entry| {
+            let info = self.tags().iter().find(|t| t.tag == entry.tag);
+            MakerNoteField {
+                tag: entry.tag,
+                name: info.map(|t| t.name.to_string()).unwrap_or_else(|| format!("0x{:04X}", entry.tag)),
+                value: entry.value_string(layout.order),
+                sensitivity: info.and_then(|t| t.sensitivity),
+            }
+        }).collect();
+        Ok(MakerNote { vendor: self.vendor(), fields })
+    }
+}
+
+// 先頭から順に判定する。ヘッダで判定できるものを先に置く
+pub const PARSERS: &[&dyn MakerNoteParser] = &[
+    &apple::Apple,
+    &nikon::Nikon,
+    &fujifilm::Fujifilm,
+    &panasonic::Panasonic,
+    &sony::Sony,
+    &canon::Canon,
+];
+
+pub fn decode(make: &str, data: &[u8]) -> Option<Result<MakerNote, String>> {
+    let parser = PARSERS.iter().find(|p| p.detect(make, data))?;
+    Some(parser.parse(data))
+}
+
+impl ExifEditData {
+    // 対応していないメーカーか、MakerNoteがなければNone
+    pub fn maker_note(&self) -> Option<Result<MakerNote, String>> {
+        let data = self.metadata.get_ifds().iter()
+            .flat_map(|ifd| ifd.get_tags().iter())
+            .find_map(|tag| match tag {
+                ExifTag::MakerNote(v) => Some(v),
+                _ => None,
+            })?;
+        let make = self.basic_image_info.device_model.make.clone().unwrap_or_default();
+        decode(&make, data)
+    }
+}
@@ -0,0 +1,46 @@
+use super::{tag, ByteOrder, IfdLayout, MakerNoteParser, OffsetBase, Sensitivity, TagInfo};
+
+// "Apple iOS\0"、バージョン（2バイト）、"MM"の後にIFDが続く。オフセットはMakerNoteの先頭から
+const HEADER: &[u8] = b"Apple iOS\0";
+
+const TAGS: &[TagInfo] = &[
+    tag(0x0001, "MakerNoteVersion", None),
+    tag(0x0003, "RunTime", None),
+    tag(0x0008, "AccelerationVector", None),
+    tag(0x000A, "HDRImageType", None),
+    tag(0x000B, "BurstUUID", Some(Sensitivity::Device)),
+    tag(0x000C, "FocusDistanceRange", None),
+    tag(0x000F, "OISMode", None),
+    tag(0x0011, "ContentIdentifier", Some(Sensitivity::Device)),
+    tag(0x0014, "ImageCaptureType", None),
+    tag(0x0015, "ImageUniqueID", Some(Sensitivity::Device)),
+    tag(0x0017, "LivePhotoVideoIndex", None),
+    tag(0x0019, "ImageProcessingFlags", None),
+    tag(0x001F, "PhotosAppFeatureFlags", None),
+    tag(0x0020, "ImageCaptureRequestID", None),
+    tag(0x0021, "HDRHeadroom", None),
+    tag(0x002B, "PhotoIdentifier", Some(Sensitivity::Device)),
+    tag(0x002D, "ColorTemperature", None),
+    tag(0x002E, "CameraType", None),
+    tag(0x002F, "FocusPosition", None),
+    tag(0x0030, "HDRGain", None),
+    tag(0x0040, "SemanticStyle", None),
+    tag(0x004A, "FrontFacingCamera", None),
+];
+
+pub struct Apple;
+
+impl MakerNoteParser for Apple {
+    fn vendor(&self) -> &'static str { "Apple" }
+
+    fn detect(&self, _make: &str, data: &[u8]) -> bool {
+        data.starts_with(HEADER)
+    }
+
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout> {
+        let order = ByteOrder::from_marker(data.get(12..14)?)?;
+        Some(IfdLayout { ifd_offset: 14, order, base: OffsetBase::At(0) })
+    }
+
+    fn tags(&self) -> &'static [TagInfo] { TAGS }
+}
@@ -0,0 +1,58 @@
+use super::{tag, ByteOrder, IfdLayout, MakerNoteParser, OffsetBase, Sensitivity, TagInfo};
+
+// ヘッダはなく先頭からIFDが始まる。バイト順とオフセットの起点は本体のTIFFと同じ
+const TAGS: &[TagInfo] = &[
+    tag(0x0001, "CameraSettings", None),
+    tag(0x0002, "FocalLength", None),
+    tag(0x0004, "ShotInfo", None),
+    tag(0x0005, "Panorama", None),
+    tag(0x0006, "ImageType", None),
+    tag(0x0007, "FirmwareVersion", None),
+    tag(0x0008, "FileNumber", None),
+    tag(0x0009, "OwnerName", Some(Sensitivity::Device)),
+    tag(0x000C, "SerialNumber", Some(Sensitivity::Device)),
+    tag(0x000D, "CameraInfo", None),
+    tag(0x0010, "ModelID", None),
+    tag(0x0012, "AFInfo", None),
+    tag(0x0013, "ThumbnailImageValidArea", None),
+    tag(0x0015, "SerialNumberFormat", None),
+    tag(0x001C, "DateStampMode", None),
+    tag(0x001D, "MyColors", None),
+    tag(0x001E, "FirmwareRevision", None),
+    tag(0x0024, "FaceDetect1", Some(Sensitivity::Person)),
+    tag(0x0025, "FaceDetect2", Some(Sensitivity::Person)),
+    tag(0x0026, "AFInfo2", None),
+    tag(0x0028, "ImageUniqueID", Some(Sensitivity::Device)),
+    tag(0x002F, "FaceDetect3", Some(Sensitivity::Person)),
+    tag(0x0035, "TimeInfo", Some(Sensitivity::Time)),
+    tag(0x0093, "FileInfo", None),
+    tag(0x0095, "LensModel", None),
+    tag(0x0096, "InternalSerialNumber", Some(Sensitivity::Device)),
+    tag(0x0097, "DustRemovalData", None),
+    tag(0x0098, "CropInfo", None),
+    tag(0x00A0, "ProcessingInfo", None),
+    tag(0x00AA, "MeasuredColor", None),
+    tag(0x00B4, "ColorSpace", None),
+    tag(0x00E0, "SensorInfo", None),
+    tag(0x4001, "ColorData", None),
+    tag(0x4013, "AFMicroAdj", None),
+    tag(0x4015, "VignettingCorr", None),
+    tag(0x4019, "LensInfo", Some(Sensitivity::Device)),
+];
+
+pub struct Canon;
+
+impl MakerNoteParser for Canon {
+    fn vendor(&self) -> &'static str { "Canon" }
+
+    fn detect(&self, make: &str, _data: &[u8]) -> bool {
+        make.trim().to_lowercase().starts_with("canon")
+    }
+
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout> {
+        let order = ByteOrder::guess(data, 0)?;
+        Some(IfdLayout { ifd_offset: 0, order, base: OffsetBase::Tiff })
+    }
+
+    fn tags(&self) -> &'static [TagInfo] { TAGS }
+}
@@ -0,0 +1,60 @@
+use super::{tag, ByteOrder, IfdLayout, MakerNoteParser, OffsetBase, Sensitivity, TagInfo};
+
+// "FUJIFILM"の後にIFDの位置（4バイト）が続く。本体に関わらず常にリトルエンディアンで、オフセットはMakerNoteの先頭から
+const HEADER: &[u8] = b"FUJIFILM";
+
+const TAGS: &[TagInfo] = &[
+    tag(0x0000, "Version", None),
+    tag(0x0010, "InternalSerialNumber", Some(Sensitivity::Device)),
+    tag(0x1000, "Quality", None),
+    tag(0x1001, "Sharpness", None),
+    tag(0x1002, "WhiteBalance", None),
+    tag(0x1003, "Saturation", None),
+    tag(0x1004, "Contrast", None),
+    tag(0x1005, "ColorTemperature", None),
+    tag(0x100A, "WhiteBalanceFineTune", None),
+    tag(0x100E, "NoiseReduction", None),
+    tag(0x1010, "FujiFlashMode", None),
+    tag(0x1011, "FlashExposureComp", None),
+    tag(0x1021, "FocusMode", None),
+    tag(0x1022, "AFMode", None),
+    tag(0x1023, "FocusPixel", None),
+    tag(0x1031, "PictureMode", None),
+    tag(0x1100, "AutoBracketing", None),
+    tag(0x1101, "SequenceNumber", None),
+    tag(0x1300, "BlurWarning", None),
+    tag(0x1301, "FocusWarning", None),
+    tag(0x1302, "ExposureWarning", None),
+    tag(0x1400, "DynamicRange", None),
+    tag(0x1401, "FilmMode", None),
+    tag(0x1402, "DynamicRangeSetting", None),
+    tag(0x1404, "MinFocalLength", None),
+    tag(0x1405, "MaxFocalLength", None),
+    tag(0x1422, "ImageStabilization", None),
+    tag(0x1438, "ImageCount", Some(Sensitivity::Device)),
+    tag(0x1446, "FlickerReduction", None),
+    tag(0x4100, "FacesDetected", Some(Sensitivity::Person)),
+    tag(0x4103, "FacePositions", Some(Sensitivity::Person)),
+    tag(0x4282, "FaceRecInfo", Some(Sensitivity::Person)),
+    tag(0x8000, "FileSource", None),
+    tag(0x8002, "OrderNumber", None),
+    tag(0x8003, "FrameNumber", None),
+];
+
+pub struct Fujifilm;
+
+impl MakerNoteParser for Fujifilm {
+    fn vendor(&self) -> &'static str { "FUJIFILM" }
+
+    fn detect(&self, _make: &str, data: &[u8]) -> bool {
+        data.starts_with(HEADER)
+    }
+
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout> {
+        let order = ByteOrder::Little;
+        let ifd = Some(order.u32(data.get(8..12)?) as usize).filter(|at| *at < data.len())?;
+        Some(IfdLayout { ifd_offset: ifd, order, base: OffsetBase::At(0) })
+    }
+
+    fn tags(&self) -> &'static [TagInfo] { TAGS }
+}
@@ -0,0 +1,78 @@
+use super::{tag, ByteOrder, IfdLayout, MakerNoteParser, OffsetBase, Sensitivity, TagInfo};
+
+// "Nikon\0"、バージョン（4バイト）の後にTIFFヘッダが埋め込まれている（Type 3）。
+// 古い機種にはヘッダのないもの（Type 2）や、TIFFヘッダのないもの（Type 1）がある
+const HEADER: &[u8] = b"Nikon\0";
+const TIFF_OFFSET: usize = 10;
+
+const TAGS: &[TagInfo] = &[
+    tag(0x0001, "MakerNoteVersion", None),
+    tag(0x0002, "ISO", None),
+    tag(0x0003, "ColorMode", None),
+    tag(0x0004, "Quality", None),
+    tag(0x0005, "WhiteBalance", None),
+    tag(0x0006, "Sharpness", None),
+    tag(0x0007, "FocusMode", None),
+    tag(0x0008, "FlashSetting", None),
+    tag(0x0009, "FlashType", None),
+    tag(0x000D, "ProgramShift", None),
+    tag(0x000E, "ExposureDifference", None),
+    tag(0x0012, "FlashExposureComp", None),
+    tag(0x0016, "ImageBoundary", None),
+    tag(0x001D, "SerialNumber", Some(Sensitivity::Device)),
+    tag(0x001E, "ColorSpace", None),
+    tag(0x0021, "FaceDetect", Some(Sensitivity::Person)),
+    tag(0x0022, "ActiveD-Lighting", None),
+    tag(0x0023, "PictureControlData", None),
+    tag(0x0024, "WorldTime", Some(Sensitivity::Time)),
+    tag(0x0025, "ISOInfo", None),
+    tag(0x002A, "VignetteControl", None),
+    tag(0x0039, "LocationInfo", Some(Sensitivity::Location)),
+    tag(0x0083, "LensType", None),
+    tag(0x0084, "Lens", None),
+    tag(0x0087, "FlashMode", None),
+    tag(0x0088, "AFInfo", None),
+    tag(0x0089, "ShootingMode", None),
+    tag(0x008B, "LensFStops", None),
+    tag(0x0091, "ShotInfo", None),
+    tag(0x0095, "NoiseReduction", None),
+    tag(0x0098, "LensData", None),
+    tag(0x00A0, "SerialNumber", Some(Sensitivity::Device)),
+    tag(0x00A7, "ShutterCount", Some(Sensitivity::Device)),
+    tag(0x00AB, "VariProgram", None),
+    tag(0x00B1, "HighISONoiseReduction", None),
+    tag(0x00B6, "PowerUpTime", Some(Sensitivity::Time)),
+    tag(0x00B7, "AFInfo2", None),
+    tag(0x00B8, "FileInfo", None),
+    tag(0x00BB, "RetouchInfo", None),
+];
+
+pub struct Nikon;
+
+impl MakerNoteParser for Nikon {
+    fn vendor(&self) -> &'static str { "Nikon" }
+
+    fn detect(&self, make: &str, data: &[u8]) -> bool {
+        data.starts_with(HEADER) || make.trim().to_lowercase().starts_with("nikon")
+    }
+
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout> {
+        if !data.starts_with(HEADER) {
+            let order = ByteOrder::guess(data, 0)?;
+            return Some(IfdLayout { ifd_offset: 0, order, base: OffsetBase::Tiff });
+        }
+        match ByteOrder::from_marker(data.get(TIFF_OFFSET..TIFF_OFFSET + 2)?) {
+            Some(order) => {
+                let ifd = order.u32(data.get(TIFF_OFFSET + 4..TIFF_OFFSET + 8)?) as usize;
+                let ifd_offset = TIFF_OFFSET.checked_add(ifd).filter(|at| *at < data.len())?;
+                Some(IfdLayout { ifd_offset, order, base: OffsetBase::At(TIFF_OFFSET) })
+            }
+            None => {
+                let order = ByteOrder::guess(data, 8)?;
+                Some(IfdLayout { ifd_offset: 8, order, base: OffsetBase::Tiff })
+            }
+        }
+    }
+
+    fn tags(&self) -> &'static [TagInfo] { TAGS }
+}
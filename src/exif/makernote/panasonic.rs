@@ -0,0 +1,66 @@
+use super::{tag, ByteOrder, IfdLayout, MakerNoteParser, OffsetBase, Sensitivity, TagInfo};
+
+// "Panasonic\0\0\0"の後にIFDが続く。オフセットの起点は本体のTIFFと同じ
+const HEADER: &[u8] = b"Panasonic\0";
+const IFD_OFFSET: usize = 12;
+
+const TAGS: &[TagInfo] = &[
+    tag(0x0001, "ImageQuality", None),
+    tag(0x0002, "FirmwareVersion", None),
+    tag(0x0003, "WhiteBalance", None),
+    tag(0x0007, "FocusMode", None),
+    tag(0x000F, "AFAreaMode", None),
+    tag(0x001A, "ImageStabilization", None),
+    tag(0x001C, "MacroMode", None),
+    tag(0x001F, "ShootingMode", None),
+    tag(0x0020, "Audio", None),
+    tag(0x0024, "FlashBias", None),
+    tag(0x0025, "InternalSerialNumber", Some(Sensitivity::Device)),
+    tag(0x0026, "PanasonicExifVersion", None),
+    tag(0x0028, "ColorEffect", None),
+    tag(0x0029, "TimeSincePowerOn", None),
+    tag(0x002A, "BurstMode", None),
+    tag(0x002B, "SequenceNumber", None),
+    tag(0x002C, "ContrastMode", None),
+    tag(0x002D, "NoiseReduction", None),
+    tag(0x002E, "SelfTimer", None),
+    tag(0x0030, "Rotation", None),
+    tag(0x0032, "ColorMode", None),
+    tag(0x0033, "BabyAge", Some(Sensitivity::Person)),
+    tag(0x0034, "OpticalZoomMode", None),
+    tag(0x003C, "ProgramISO", None),
+    tag(0x004E, "FaceDetInfo", Some(Sensitivity::Person)),
+    tag(0x0051, "LensType", None),
+    tag(0x0052, "LensSerialNumber", Some(Sensitivity::Device)),
+    tag(0x0053, "AccessoryType", None),
+    tag(0x0054, "AccessorySerialNumber", Some(Sensitivity::Device)),
+    tag(0x0061, "FaceRecInfo", Some(Sensitivity::Person)),
+    tag(0x0065, "Title", None),
+    tag(0x0066, "BabyName", Some(Sensitivity::Person)),
+    tag(0x0067, "Location", Some(Sensitivity::Location)),
+    tag(0x0069, "Country", Some(Sensitivity::Location)),
+    tag(0x006B, "State", Some(Sensitivity::Location)),
+    tag(0x006D, "City", Some(Sensitivity::Location)),
+    tag(0x006F, "Landmark", Some(Sensitivity::Location)),
+    tag(0x0070, "IntelligentResolution", None),
+    tag(0x0079, "IntelligentD-Range", None),
+    tag(0x0089, "PhotoStyle", None),
+    tag(0x008A, "ShadingCompensation", None),
+];
+
+pub struct Panasonic;
+
+impl MakerNoteParser for Panasonic {
+    fn vendor(&self) -> &'static str { "Panasonic" }
+
+    fn detect(&self, _make: &str, data: &[u8]) -> bool {
+        data.starts_with(HEADER)
+    }
+
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout> {
+        let order = ByteOrder::guess(data, IFD_OFFSET)?;
+        Some(IfdLayout { ifd_offset: IFD_OFFSET, order, base: OffsetBase::Tiff })
+    }
+
+    fn tags(&self) -> &'static [TagInfo] { TAGS }
+}
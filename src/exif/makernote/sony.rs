@@ -0,0 +1,72 @@
+use super::{tag, ByteOrder, IfdLayout, MakerNoteParser, OffsetBase, Sensitivity, TagInfo};
+
+// "SONY DSC \0\0\0"などの12バイトのヘッダの後にIFDが続く（ヘッダのない機種もある）。
+// バイト順とオフセットの起点は本体のTIFFと同じ
+const HEADER: &[u8] = b"SONY";
+const HEADER_LEN: usize = 12;
+
+const TAGS: &[TagInfo] = &[
+    tag(0x0102, "Quality", None),
+    tag(0x0104, "FlashExposureComp", None),
+    tag(0x0105, "Teleconverter", None),
+    tag(0x0112, "WhiteBalanceFineTune", None),
+    tag(0x0114, "CameraSettings", None),
+    tag(0x0115, "WhiteBalance", None),
+    tag(0x0E00, "PrintIM", None),
+    tag(0x1000, "MultiBurstMode", None),
+    tag(0x2001, "PreviewImage", None),
+    tag(0x2002, "Rating", None),
+    tag(0x2004, "Contrast", None),
+    tag(0x2005, "Saturation", None),
+    tag(0x2006, "Sharpness", None),
+    tag(0x2007, "Brightness", None),
+    tag(0x2008, "LongExposureNoiseReduction", None),
+    tag(0x2009, "HighISONoiseReduction", None),
+    tag(0x200A, "HDR", None),
+    tag(0x200B, "MultiFrameNoiseReduction", None),
+    tag(0x200E, "PictureEffect", None),
+    tag(0x2011, "VignettingCorrection", None),
+    tag(0x2012, "LateralChromaticAberration", None),
+    tag(0x2013, "DistortionCorrectionSetting", None),
+    tag(0x2031, "SerialNumber", Some(Sensitivity::Device)),
+    tag(0xB000, "FileFormat", None),
+    tag(0xB001, "SonyModelID", None),
+    tag(0xB020, "CreativeStyle", None),
+    tag(0xB021, "ColorTemperature", None),
+    tag(0xB023, "SceneMode", None),
+    tag(0xB024, "ZoneMatching", None),
+    tag(0xB025, "DynamicRangeOptimizer", None),
+    tag(0xB026, "ImageStabilization", None),
+    tag(0xB027, "LensType", None),
+    tag(0xB029, "ColorMode", None),
+    tag(0xB02A, "LensSpec", None),
+    tag(0xB02B, "FullImageSize", None),
+    tag(0xB040, "Macro", None),
+    tag(0xB041, "ExposureMode", None),
+    tag(0xB042, "FocusMode", None),
+    tag(0xB043, "AFAreaMode", None),
+    tag(0xB047, "JPEGQuality", None),
+    tag(0xB04B, "AntiBlur", None),
+    tag(0xB04E, "LongExposureNoiseReduction", None),
+    tag(0xB04F, "DynamicRangeOptimizer", None),
+    tag(0xB052, "IntelligentAuto", None),
+    tag(0xB054, "WhiteBalance", None),
+];
+
+pub struct Sony;
+
+impl MakerNoteParser for Sony {
+    fn vendor(&self) -> &'static str { "Sony" }
+
+    fn detect(&self, make: &str, data: &[u8]) -> bool {
+        data.starts_with(HEADER) || make.trim().to_lowercase().starts_with("sony")
+    }
+
+    fn layout(&self, data: &[u8]) -> Option<IfdLayout> {
+        let ifd_offset = if data.starts_with(HEADER) { HEADER_LEN } else { 0 };
+        let order = ByteOrder::guess(data, ifd_offset)?;
+        Some(IfdLayout { ifd_offset, order, base: OffsetBase::Tiff })
+    }
+
+    fn tags(&self) -> &'static [TagInfo] { TAGS }
+}
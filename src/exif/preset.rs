@@ -0,0 +1,199 @@
+use little_exif::exif_tag::ExifTag;
+use little_exif::ifd::ExifTagGroup;
+
+use super::ExifEditData;
+use super::makernote::{MakerNoteField, Sensitivity};
+use super::tag_value::{is_unknown, tag_name, tags_by_key, TagKey};
+use crate::iptc::{IptcData, IptcDataset, APPLICATION_RECORD};
+use crate::xmp::{XmpPacket, XmpProperty, AUX_NS, DC_NS, EXIF_EX_NS, EXIF_NS, IPTC_CORE_NS, PHOTOSHOP_NS, TIFF_NS, XMP_NS};
+
+const DEVICE_IDENTITY: &[&str] = &[
+    "Make", "Model", "SerialNumber", "LensSerialNumber", "OwnerName", "ImageUniqueID",
+];
+
+const TIMESTAMPS: &[&str] = &[
+    "DateTimeOriginal", "OffsetTimeOriginal", "SubSecTimeOriginal",
+    "CreateDate", "OffsetTimeDigitized", "SubSecTimeDigitized",
+    "ModifyDate", "OffsetTime", "SubSecTime",
+    "GPSDateStamp", "GPSTimeStamp",
+];
+
+// SNSに載せる前提で、表示に必要なタグ（向き・色空間など）以外の個人に繋がる情報を消す
+const SOCIAL_MEDIA_EXTRA: &[&str] = &[
+    "LensMake", "LensModel", "Software", "Artist",
+    "ImageDescription", "UserComment", "MakerNote",
+];
+
+// Exifのタグと同じ情報を持つXMPの項目。exif:GPS*はすべて位置情報として扱う
+const XMP_LOCATION: &[(&str, &str)] = &[
+    (PHOTOSHOP_NS, "City"), (PHOTOSHOP_NS, "State"), (PHOTOSHOP_NS, "Country"),
+    (IPTC_CORE_NS, "Location"), (IPTC_CORE_NS, "CountryCode"),
+];
+
+const XMP_DEVICE_IDENTITY: &[(&str, &str)] = &[
+    (TIFF_NS, "Make"), (TIFF_NS, "Model"), (EXIF_NS, "ImageUniqueID"),
+    (AUX_NS, "SerialNumber"), (AUX_NS, "LensSerialNumber"), (AUX_NS, "OwnerName"),
+    (EXIF_EX_NS, "BodySerialNumber"), (EXIF_EX_NS, "LensSerialNumber"),
+    (EXIF_EX_NS, "CameraOwnerName"), (EXIF_EX_NS, "ImageUniqueID"),
+];
+
+const XMP_TIMESTAMPS: &[(&str, &str)] = &[
+    (EXIF_NS, "DateTimeOriginal"), (EXIF_NS, "DateTimeDigitized"), (EXIF_NS, "GPSTimeStamp"),
+    (XMP_NS, "CreateDate"), (XMP_NS, "ModifyDate"), (XMP_NS, "MetadataDate"),
+    (PHOTOSHOP_NS, "DateCreated"),
+];
+
+const XMP_SOCIAL_MEDIA_EXTRA: &[(&str, &str)] = &[
+    (TIFF_NS, "Software"), (TIFF_NS, "Artist"), (TIFF_NS, "ImageDescription"),
+    (XMP_NS, "CreatorTool"), (DC_NS, "creator"), (DC_NS, "description"),
+    (EXIF_NS, "UserComment"), (AUX_NS, "Lens"), (EXIF_EX_NS, "LensMake"), (EXIF_EX_NS, "LensModel"),
+];
+
+// アプリケーションレコード（2:xx）のデータセット番号
+const IPTC_LOCATION: &[u8] = &[90, 92, 95, 100, 101];
+// 作成日・時刻と、デジタル化した日・時刻
+const IPTC_TIMESTAMPS: &[u8] = &[55, 60, 62, 63];
+// 作成ソフトとそのバージョン、作者とその肩書き、説明とその作成者
+const IPTC_SOCIAL_MEDIA_EXTRA: &[u8] = &[65, 70, 80, 85, 120, 122];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ScrubPreset {
+    Location,
+    DeviceIdentity,
+    Timestamps,
+    SocialMedia,
+}
+
+impl ScrubPreset {
+    pub fn all() -> [Self; 4] {
+        [ScrubPreset::Location, ScrubPreset::DeviceIdentity, ScrubPreset::Timestamps, ScrubPreset::SocialMedia]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScrubPreset::Location => "位置情報を削除",
+            ScrubPreset::DeviceIdentity => "機器の識別情報を削除",
+            ScrubPreset::Timestamps => "撮影日時を削除",
+            ScrubPreset::SocialMedia => "SNS投稿向け",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ScrubPreset::Location => "GPSに関するタグと、XMP・IPTCの位置情報や地名をすべて削除します。",
+            ScrubPreset::DeviceIdentity => "メーカー・機種名やシリアル番号など、撮影機器を特定できるタグを、XMPのものも合わせて削除します。",
+            ScrubPreset::Timestamps => "撮影・作成・更新日時と、そのタイムゾーン・秒未満の値、GPS日時を、XMP・IPTCのものも合わせて削除します。",
+            ScrubPreset::SocialMedia => "位置情報・機器情報・日時に加え、作者やコメント、メーカー独自情報、未知のタグを、XMP・IPTCのものも合わせて削除します。向きや色空間は残します。",
+        }
+    }
+
+    // MakerNoteの中にこの種類の情報があれば、MakerNoteごと削除する
+    pub fn covers(&self, sensitivity: Sensitivity) -> bool {
+        match self {
+            ScrubPreset::Location => sensitivity == Sensitivity::Location,
+            ScrubPreset::DeviceIdentity => sensitivity == Sensitivity::Device,
+            ScrubPreset::Timestamps => sensitivity == Sensitivity::Time,
+            ScrubPreset::SocialMedia => true,
+        }
+    }
+
+    pub fn matches(&self, tag: &ExifTag) -> bool {
+        let name = tag_name(tag);
+        match self {
+            ScrubPreset::Location => tag.get_group() == ExifTagGroup::GPS,
+            ScrubPreset::DeviceIdentity => DEVICE_IDENTITY.contains(&name.as_str()),
+            ScrubPreset::Timestamps => TIMESTAMPS.contains(&name.as_str()),
+            ScrubPreset::SocialMedia => {
+                ScrubPreset::Location.matches(tag)
+                || ScrubPreset::DeviceIdentity.matches(tag)
+                || ScrubPreset::Timestamps.matches(tag)
+                || SOCIAL_MEDIA_EXTRA.contains(&name.as_str())
+                || is_unknown(tag)
+            }
+        }
+    }
+}
+
+    pub fn matches_xmp(&self, property: &XmpProperty) -> bool {
+        let key = (property.namespace.as_str(), property.name.as_str());
+        match self {
+            ScrubPreset::Location => (property.namespace == EXIF_NS && property.name.starts_with("GPS")) || XMP_LOCATION.contains(&key),
+            ScrubPreset::DeviceIdentity => XMP_DEVICE_IDENTITY.contains(&key),
+            ScrubPreset::Timestamps => XMP_TIMESTAMPS.contains(&key),
+            ScrubPreset::SocialMedia => {
+                ScrubPreset::Location.matches_xmp(property)
+                || ScrubPreset::DeviceIdentity.matches_xmp(property)
+                || ScrubPreset::Timestamps.matches_xmp(property)
+                || XMP_SOCIAL_MEDIA_EXTRA.contains(&key)
+            }
+        }
+    }
+
+    pub fn matches_iptc(&self, dataset: &IptcDataset) -> bool {
+        if dataset.record != APPLICATION_RECORD { return false; }
+        match self {
+            ScrubPreset::Location => IPTC_LOCATION.contains(&dataset.dataset),
+            ScrubPreset::DeviceIdentity => false,
+            ScrubPreset::Timestamps => IPTC_TIMESTAMPS.contains(&dataset.dataset),
+            ScrubPreset::SocialMedia => {
+                ScrubPreset::Location.matches_iptc(dataset)
+                || ScrubPreset::Timestamps.matches_iptc(dataset)
+                || IPTC_SOCIAL_MEDIA_EXTRA.contains(&dataset.dataset)
+            }
+        }
+    }
+
+    // 適用前の確認用に、XMP・IPTCで削除される項目を返す
+    pub fn xmp_targets(&self, packet: &XmpPacket) -> Vec<XmpProperty> {
+        packet.properties.iter().filter(|p| self.matches_xmp(p)).cloned().collect()
+    }
+
+    pub fn iptc_targets(&self, iptc: &IptcData) -> Vec<IptcDataset> {
+        iptc.datasets.iter().filter(|d| self.matches_iptc(d)).cloned().collect()
+    }
+
+    pub fn apply_xmp(&self, packet: &mut XmpPacket) -> usize {
+        let n = packet.properties.len();
+        packet.properties.retain(|p| !self.matches_xmp(p));
+        n - packet.properties.len()
+    }
+
+    pub fn apply_iptc(&self, iptc: &mut IptcData) -> usize {
+        let n = iptc.datasets.len();
+        iptc.datasets.retain(|d| !self.matches_iptc(d));
+        n - iptc.datasets.len()
+    }
+}
+
+impl ExifEditData {
+    // MakerNoteを解析できた場合の、プリセットの対象にあたる項目
+    pub fn maker_note_hits(&self, preset: ScrubPreset) -> Vec<MakerNoteField> {
+        match self.maker_note() {
+            Some(Ok(maker_note)) => maker_note.fields.into_iter()
+                .filter(|f| f.sensitivity.map(|s| preset.covers(s)).unwrap_or(false))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // 適用前の確認用に、プリセットで削除される（現在存在する）タグを、どのIFDのものかと合わせて返す
+    pub fn preset_targets(&self, preset: ScrubPreset) -> Vec<(TagKey, ExifTag)> {
+        let maker_note_hit = !self.maker_note_hits(preset).is_empty();
+        tags_by_key(&self.metadata).into_iter()
+            .filter(|(_, tag)| preset.matches(tag) || (maker_note_hit && matches!(tag, ExifTag::MakerNote(_))))
+            .collect()
+    }
+
+    pub fn apply_preset(&mut self, preset: ScrubPreset) -> usize {
+        let targets = self.preset_targets(preset);
+        let n = targets.len();
+        for (key, tag) in targets.into_iter() {
+            // delete_tagはIFD0からしか削除しないため、IFD1（サムネイル）のタグはIFD1から直接削除する
+            match key.ifd {
+                0 => self.delete_tag(tag),
+                ifd => { self.metadata.get_ifd_mut(tag.get_group(), ifd).remove_tag(tag); }
+            }
+        }
+        n
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use little_exif::exif_tag::ExifTag;
+use little_exif::metadata::Metadata;
+use little_exif::rational::{iR64, uR64};
+
+use super::rational::{approx_frac, ExifRational};
+use super::unknown_string_core;
+use super::user::{UserComment, UserCommentCode};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagValueError {
+    UnknownTag(String),
+    ReadOnly(String),
+    InvalidValue(String, String),
+}
+
+impl fmt::Display for TagValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagValueError::UnknownTag(name) => write!(f, "未対応のタグです: {}", name),
+            TagValueError::ReadOnly(name) => write!(f, "{}は値を指定して書き込めません", name),
+            TagValueError::InvalidValue(name, value) => write!(f, "{}に設定できない値です: {}", name, value),
+        }
+    }
+}
+
+fn split_values(value: &str) -> impl Iterator<Item = &str> {
+    value.split(|c: char| c == ',' || c.is_whitespace()).filter(|s| !s.is_empty())
+}
+
+fn parse_numbers<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    let v = split_values(value).map(|s| s.parse::<T>().ok()).collect::<Option<Vec<T>>>()?;
+    if v.is_empty() { None } else { Some(v) }
+}
+
+// "1/250"のような分数表記と"0.004"のような小数表記の両方を受け付ける
+fn parse_fraction(s: &str) -> Option<(i64, i64)> {
+    if let Some((n, d)) = s.split_once('/') {
+        let n = n.trim().parse::<i64>().ok()?;
+        let d = d.trim().parse::<i64>().ok()?;
+        if d == 0 { None } else { Some((n, d)) }
+    } else {
+        let f = s.parse::<f64>().ok()?;
+        if !f.is_finite() { return None; }
+        approx_frac(f).map(|(_, n, d)| (n as i64, d as i64))
+    }
+}
+
+fn parse_urationals(value: &str) -> Option<Vec<uR64>> {
+    let v = split_values(value).map(|s| {
+        let (n, d) = parse_fraction(s)?;
+        Some(uR64 { nominator: u32::try_from(n).ok()?, denominator: u32::try_from(d).ok()? })
+    }).collect::<Option<Vec<uR64>>>()?;
+    if v.is_empty() { None } else { Some(v) }
+}
+
+fn parse_irationals(value: &str) -> Option<Vec<iR64>> {
+    let v = split_values(value).map(|s| {
+        let (n, d) = parse_fraction(s)?;
+        Some(iR64 { nominator: i32::try_from(n).ok()?, denominator: i32::try_from(d).ok()? })
+    }).collect::<Option<Vec<iR64>>>()?;
+    if v.is_empty() { None } else { Some(v) }
+}
+
+fn join_numbers<T: fmt::Display>(v: &[T]) -> String {
+    v.iter().map(|vi| vi.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+fn join_rationals<T: ExifRational>(v: &[T]) -> String {
+    v.iter().map(|vi| vi.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+fn user_comment(value: &str) -> Vec<u8> {
+    let code = if value.is_ascii() { UserCommentCode::ASCII } else { UserCommentCode::Unicode };
+    UserComment::from_str(value, &code).data
+}
+
+pub fn is_unknown(tag: &ExifTag) -> bool {
+    matches!(tag,
+        ExifTag::UnknownSTRING(..) | ExifTag::UnknownINT8U(..) | ExifTag::UnknownINT16U(..)
+        | ExifTag::UnknownINT32U(..) | ExifTag::UnknownRATIONAL64U(..) | ExifTag::UnknownINT8S(..)
+        | ExifTag::UnknownUNDEF(..) | ExifTag::UnknownINT16S(..) | ExifTag::UnknownINT32S(..)
+        | ExifTag::UnknownRATIONAL64S(..) | ExifTag::UnknownFLOAT(..) | ExifTag::UnknownDOUBLE(..)
+    )
+}
+
+// IFD0とIFD1（サムネイル）に同じ名前のタグがあっても区別するためのキー
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TagKey {
+    // 0: 本体（Exif, GPS, Interopを含む）、1: サムネイル
+    pub ifd: u32,
+    pub name: String,
+}
+
+impl TagKey {
+    pub fn new(ifd: u32, name: &str) -> Self {
+        Self { ifd, name: name.to_string() }
+    }
+
+    pub fn label(&self) -> String {
+        match self.ifd {
+            0 => self.name.clone(),
+            ifd => format!("{}（IFD{}）", self.name, ifd),
+        }
+    }
+}
+
+pub fn tags_by_key(metadata: &Metadata) -> BTreeMap<TagKey, ExifTag> {
+    let mut map = BTreeMap::new();
+    for ifd in metadata.get_ifds() {
+        for tag in ifd.get_tags() {
+            map.insert(TagKey { ifd: ifd.get_generic_ifd_nr(), name: tag_name(tag) }, tag.clone());
+        }
+    }
+    map
+}
+
+// ファイル内の位置や長さを表すタグ。他の画像へ値をコピーすると壊れる
+pub const STRUCTURAL_TAGS: &[&str] = &[
+    "StripOffsets", "StripByteCounts", "ThumbnailOffset", "ThumbnailLength",
+    "ExifOffset", "GPSInfo", "InteropOffset",
+];
+
+// exiftoolとタグ名が異なるもの（左がこのツールでの名前）
+const EXIFTOOL_ALIASES: &[(&str, &str)] = &[
+    ("CellHeight", "CellLength"),
+    ("InteroperabilityIndex", "InteropIndex"),
+    ("InteroperabilityVersion", "InteropVersion"),
+    ("OECF", "Opto-ElectricConvFactor"),
+];
+
+pub fn exiftool_name(name: &str) -> &str {
+    EXIFTOOL_ALIASES.iter().find(|(n, _)| *n == name).map(|(_, e)| *e).unwrap_or(name)
+}
+
+pub fn from_exiftool_name(name: &str) -> &str {
+    EXIFTOOL_ALIASES.iter().find(|(_, e)| *e == name).map(|(n, _)| *n).unwrap_or(name)
+}
+
+macro_rules! tag_table {
+    (
+        string: [$($s:ident),* $(,)?],
+        number: [$($n:ident),* $(,)?],
+        urational: [$($u:ident),* $(,)?],
+        irational: [$($i:ident),* $(,)?],
+        ascii: [$($a:ident),* $(,)?],
+        text: [$($t:ident),* $(,)?],
+        structural: [$($x:ident),* $(,)?] $(,)?
+    ) => {
+        pub const TAG_NAMES: &[&str] = &[
+            $(stringify!($s),)* $(stringify!($n),)* $(stringify!($u),)*
+            $(stringify!($i),)* $(stringify!($a),)* $(stringify!($t),)* $(stringify!($x),)*
+        ];
+
+        // 値の空なタグを返す（削除時の指定に使う）
+        pub fn empty_tag(name: &str) -> Option<ExifTag> {
+            match name {
+                $(stringify!($s) => Some(ExifTag::$s(String::new())),)*
+                $(stringify!($n) => Some(ExifTag::$n(Vec::new())),)*
+                $(stringify!($u) => Some(ExifTag::$u(Vec::new())),)*
+                $(stringify!($i) => Some(ExifTag::$i(Vec::new())),)*
+                $(stringify!($a) => Some(ExifTag::$a(Vec::new())),)*
+                $(stringify!($t) => Some(ExifTag::$t(Vec::new())),)*
+                $(stringify!($x) => Some(ExifTag::$x(Vec::new(), Vec::new())),)*
+                _ => None,
+            }
+        }
+
+        pub fn parse_tag(name: &str, value: &str) -> Result<ExifTag, TagValueError> {
+            let invalid = || TagValueError::InvalidValue(name.to_string(), value.to_string());
+            match name {
+                $(stringify!($s) => Ok(ExifTag::$s(value.to_string())),)*
+                $(stringify!($n) => Ok(ExifTag::$n(parse_numbers(value).ok_or_else(invalid)?)),)*
+                $(stringify!($u) => Ok(ExifTag::$u(parse_urationals(value).ok_or_else(invalid)?)),)*
+                $(stringify!($i) => Ok(ExifTag::$i(parse_irationals(value).ok_or_else(invalid)?)),)*
+                $(stringify!($a) => Ok(ExifTag::$a(value.bytes().collect())),)*
+                $(stringify!($t) => Ok(ExifTag::$t(user_comment(value))),)*
+                $(stringify!($x) => Err(TagValueError::ReadOnly(name.to_string())),)*
+                _ => Err(TagValueError::UnknownTag(name.to_string())),
+            }
+        }
+
+        pub fn tag_name(tag: &ExifTag) -> String {
+            match tag {
+                $(ExifTag::$s(..) => stringify!($s).to_string(),)*
+                $(ExifTag::$n(..) => stringify!($n).to_string(),)*
+                $(ExifTag::$u(..) => stringify!($u).to_string(),)*
+                $(ExifTag::$i(..) => stringify!($i).to_string(),)*
+                $(ExifTag::$a(..) => stringify!($a).to_string(),)*
+                $(ExifTag::$t(..) => stringify!($t).to_string(),)*
+                $(ExifTag::$x(..) => stringify!($x).to_string(),)*
+                _ => unknown_string_core(tag.as_u16(), tag.get_group()),
+            }
+        }
+
+        // 整数や有理数の値を持つタグか
+        pub fn is_numeric(tag: &ExifTag) -> bool {
+            match tag {
+                $(ExifTag::$n(..) => true,)*
+                $(ExifTag::$u(..) => true,)*
+                $(ExifTag::$i(..) => true,)*
+                $(ExifTag::$x(..) => true,)*
+                _ => false,
+            }
+        }
+
+        // parse_tagで読み戻せる形式の文字列にする
+        pub fn value_string(tag: &ExifTag) -> String {
+            match tag {
+                $(ExifTag::$s(s) => s.clone(),)*
+                $(ExifTag::$n(v) => join_numbers(v),)*
+                $(ExifTag::$u(v) => join_rationals(v),)*
+                $(ExifTag::$i(v) => join_rationals(v),)*
+                $(ExifTag::$a(v) => String::from_utf8_lossy(v).to_string(),)*
+                $(ExifTag::$t(v) => match UserComment::from_vec(v) {
+                    Some(uc) => uc.decoded,
+                    None => String::new(),
+                },)*
+                $(ExifTag::$x(v, _) => join_numbers(v),)*
+                ExifTag::UnknownSTRING(s, _, _) => s.clone(),
+                ExifTag::UnknownINT8U(v, _, _) => join_numbers(v),
+                ExifTag::UnknownINT16U(v, _, _) => join_numbers(v),
+                ExifTag::UnknownINT32U(v, _, _) => join_numbers(v),
+                ExifTag::UnknownRATIONAL64U(v, _, _) => join_rationals(v),
+                ExifTag::UnknownINT8S(v, _, _) => join_numbers(v),
+                ExifTag::UnknownUNDEF(v, _, _) => join_numbers(v),
+                ExifTag::UnknownINT16S(v, _, _) => join_numbers(v),
+                ExifTag::UnknownINT32S(v, _, _) => join_numbers(v),
+                ExifTag::UnknownRATIONAL64S(v, _, _) => join_rationals(v),
+                ExifTag::UnknownFLOAT(v, _, _) => join_numbers(v),
+                ExifTag::UnknownDOUBLE(v, _, _) => join_numbers(v),
+            }
+        }
+    };
+}
+
+tag_table!(
+    string: [
+        Make, Model, Software, SerialNumber, OwnerName,
+        DateTimeOriginal, OffsetTimeOriginal, SubSecTimeOriginal,
+        CreateDate, OffsetTimeDigitized, SubSecTimeDigitized,
+        ModifyDate, OffsetTime, SubSecTime,
+        LensMake, LensModel, LensSerialNumber,
+        SpectralSensitivity, RelatedSoundFile, ImageUniqueID,
+        GPSLatitudeRef, GPSLongitudeRef, GPSMapDatum, GPSSatellites, GPSStatus, GPSMeasureMode,
+        GPSSpeedRef, GPSTrackRef, GPSImgDirectionRef,
+        GPSDestLatitudeRef, GPSDestLongitudeRef, GPSDestBearingRef, GPSDestDistanceRef,
+        GPSDateStamp,
+        InteroperabilityIndex,
+        ImageDescription, Artist, Copyright,
+    ],
+    number: [
+        ImageWidth, ImageHeight, ExifImageWidth, ExifImageHeight, ResolutionUnit,
+        Compression, PhotometricInterpretation, ColorSpace,
+        BitsPerSample, SamplesPerPixel, PlanarConfiguration, YCbCrSubSampling, YCbCrPositioning,
+        TransferFunction, ColorMap, StripByteCounts, RowsPerStrip,
+        Orientation, CellWidth, CellHeight,
+        ExposureProgram, ExposureMode, MeteringMode, LightSource, Flash,
+        SubjectArea, SubjectLocation,
+        SensitivityType, ISO, ISOSpeed, StandardOutputSensitivity, RecommendedExposureIndex,
+        ISOSpeedLatitudeyyy, ISOSpeedLatitudezzz,
+        ExifOffset, ComponentsConfiguration,
+        SensingMethod, FileSource, SceneType, CFAPattern,
+        OECF, SubjectDistanceRange, SpatialFrequencyResponse, FocalPlaneResolutionUnit,
+        WhiteBalance, FocalLengthIn35mmFormat, SceneCaptureType, GainControl,
+        Contrast, Saturation, Sharpness, CustomRendered, DeviceSettingDescription,
+        CompositeImage, CompositeImageCount, CompositeImageExposureTimes,
+        GPSAltitudeRef, GPSProcessingMethod, GPSAreaInformation, GPSDifferential, GPSVersionID, GPSInfo,
+        InteropOffset, InteroperabilityVersion,
+        ThumbnailLength,
+        MakerNote,
+    ],
+    urational: [
+        XResolution, YResolution, YCbCrCoefficients, WhitePoint, PrimaryChromaticities, ReferenceBlackWhite,
+        LensInfo, MaxApertureValue, ExposureTime, FNumber, ApertureValue, FocalLength, ExposureIndex,
+        CompressedBitsPerPixel, Humidity, Pressure, Acceleration, SubjectDistance, FlashEnergy,
+        FocalPlaneXResolution, FocalPlaneYResolution, DigitalZoomRatio, Gamma,
+        GPSLatitude, GPSLongitude, GPSAltitude, GPSDOP, GPSSpeed, GPSTrack, GPSImgDirection,
+        GPSDestLatitude, GPSDestLongitude, GPSDestBearing, GPSDestDistance,
+        GPSTimeStamp, GPSHPositioningError,
+    ],
+    irational: [
+        ShutterSpeedValue, ExposureCompensation, BrightnessValue,
+        AmbientTemperature, WaterDepth, CameraElevationAngle,
+    ],
+    ascii: [ExifVersion, FlashpixVersion],
+    text: [UserComment],
+    structural: [StripOffsets, ThumbnailOffset],
+);
@@ -1,9 +1,11 @@
 use little_exif::exif_tag::ExifTag;
-use little_exif::metadata::Metadata;
+use little_exif::ifd::ExifTagGroup;
 
-use super::utils::{RemoveTag, pick_v0};
+use super::ExifEditData;
+use super::utils::pick_v0;
 
 use crate::define_struct;
+use crate::jpeg;
 
 define_struct!(
     ThumbnailInfo,
@@ -17,4 +19,74 @@ define_struct!(
         // thumbnail_offset: 未実装
         thumbnail_offset: (ThumbnailOffset, |v0: &[u32], v1: &[u8]| Some((v0.to_vec(), v1.to_vec())))
     }
-);
\ No newline at end of file
+);
+
+// Exifセグメント全体を64KB以下に収めるため、サムネイルはこれより小さくする
+pub const MAX_THUMBNAIL_BYTES: usize = 60_000;
+// 長辺の画素数
+pub const THUMBNAIL_SIZE: u32 = 160;
+
+// 縦横比の差がこれ以下なら一致とみなす
+const ASPECT_TOLERANCE: f64 = 0.05;
+
+impl ExifEditData {
+    pub fn thumbnail(&self) -> Option<&[u8]> {
+        self.thumbnail_info.thumbnail_offset.as_ref()
+            .map(|(_, data)| data.as_slice())
+            .filter(|data| !data.is_empty())
+    }
+
+    // サムネイルのタグはIFD1にあるため、delete_tag（IFD0から削除）ではなくここで削除する
+    pub fn delete_thumbnail(&mut self) {
+        if self.thumbnail_info.thumbnail_offset.is_none() && self.thumbnail_info.thumbnail_length.is_none() {
+            return;
+        }
+        let ifd1 = self.metadata.get_ifd_mut(ExifTagGroup::GENERIC, 1);
+        ifd1.remove_tag(ExifTag::ThumbnailOffset(Vec::new(), Vec::new()));
+        ifd1.remove_tag(ExifTag::ThumbnailLength(Vec::new()));
+        self.thumbnail_info.thumbnail_offset = None;
+        self.thumbnail_info.thumbnail_length = None;
+    }
+
+    pub fn set_thumbnail(&mut self, data: Vec<u8>) -> Result<(), String> {
+        if jpeg::dimensions(&data).is_none() {
+            return Err("サムネイルはJPEG画像にしてください".to_string());
+        }
+        if data.len() > MAX_THUMBNAIL_BYTES {
+            return Err(format!("サムネイルが大きすぎます（{}バイトまで）", MAX_THUMBNAIL_BYTES));
+        }
+        let length = data.len() as u32;
+        let ifd1 = self.metadata.get_ifd_mut(ExifTagGroup::GENERIC, 1);
+        // 6: JPEG圧縮のサムネイル
+        ifd1.set_tag(ExifTag::Compression(vec![6]));
+        ifd1.set_tag(ExifTag::ThumbnailOffset(vec![0], data.clone()));
+        ifd1.set_tag(ExifTag::ThumbnailLength(vec![length]));
+        self.thumbnail_info.thumbnail_offset = Some((vec![0], data));
+        self.thumbnail_info.thumbnail_length = Some(length);
+        Ok(())
+    }
+
+    // 本体の画素数をタグから求める（JPEGならjpeg::dimensionsの方が確実）
+    pub fn pixel_dimensions(&self) -> Option<(u32, u32)> {
+        let format = &self.basic_image_info.image_format;
+        match (format.exif_image_width, format.exif_image_height) {
+            (Some(w), Some(h)) => Some((w as u32, h as u32)),
+            _ => Some((format.image_width?, format.image_height?)),
+        }
+    }
+}
+
+// サムネイルと本体の画素数（どちらも回転前）を比べ、食い違いを返す
+pub fn thumbnail_mismatches(thumbnail: (u32, u32), main: (u32, u32)) -> Vec<&'static str> {
+    let mut ret = Vec::new();
+    if thumbnail.0 == 0 || thumbnail.1 == 0 || main.0 == 0 || main.1 == 0 {
+        return ret;
+    }
+    let (t_ratio, m_ratio) = (thumbnail.0 as f64 / thumbnail.1 as f64, main.0 as f64 / main.1 as f64);
+    if (t_ratio > 1.0 && m_ratio < 1.0) || (t_ratio < 1.0 && m_ratio > 1.0) {
+        ret.push("サムネイルと本体で縦長・横長が逆になっています。");
+    } else if (t_ratio / m_ratio - 1.0).abs() > ASPECT_TOLERANCE {
+        ret.push("サムネイルと本体で縦横比が異なります。トリミング前の画像がサムネイルに残っている可能性があります（黒帯の付いたサムネイルの場合もあります）。");
+    }
+    ret
+}
@@ -1,9 +1,7 @@
 use encoding::all::ISO_2022_JP;
 use encoding::{DecoderTrap, EncoderTrap, Encoding};
-use little_exif::exif_tag::ExifTag;
-use little_exif::metadata::Metadata;
 
-use super::utils::{RemoveTag, some_string};
+use super::utils::some_string;
 
 use crate::define_struct;
 
@@ -209,6 +209,55 @@ all_list_f64!(3);
 all_list_f64!(4);
 all_list_f64!(6);
 
+pub trait ShowValue: PartialEq {
+    fn show_value(&self) -> String;
+}
+
+impl ShowValue for String { fn show_value(&self) -> String { self.clone() } }
+
+impl ShowValue for u16 { fn show_value(&self) -> String { self.to_string() } }
+impl ShowValue for u32 { fn show_value(&self) -> String { self.to_string() } }
+
+impl ShowValue for F64 { fn show_value(&self) -> String { self.value().to_string() } }
+
+impl ShowValue for NaiveDateTime { fn show_value(&self) -> String { format!("{:?}", self) } }
+impl ShowValue for TimeOffset { fn show_value(&self) -> String { self.to_string() } }
+
+macro_rules! show_value_vec {
+    ($type:ty) => {
+        impl ShowValue for Vec<$type> {
+            fn show_value(&self) -> String {
+                let v = self.iter().map(|vi| vi.to_string()).collect::<Vec<String>>();
+                v.join(", ")
+            }
+        }
+    };
+}
+
+show_value_vec!(u8);
+show_value_vec!(u16);
+show_value_vec!(u32);
+show_value_vec!(F64);
+
+macro_rules! show_value {
+    ($type:ty, $n:expr) => {
+        impl ShowValue for [$type; $n] {
+            fn show_value(&self) -> String {
+                let v = self.iter().map(|vi| vi.to_string()).collect::<Vec<String>>();
+                v.join(", ")
+            }
+        }
+    };
+}
+
+show_value!(u8, 4);
+show_value!(u16, 2);
+show_value!(u16, 768);
+show_value!(F64, 2);
+show_value!(F64, 3);
+show_value!(F64, 4);
+show_value!(F64, 6);
+
 #[macro_export]
 macro_rules! define_big_struct {
     (
@@ -226,6 +275,10 @@ macro_rules! define_big_struct {
                     $($field: $struct::new(),)*
                 }
             }
+
+            pub fn tag_groups() -> Vec<(&'static str, &'static [&'static str])> {
+                vec![$((stringify!($struct), $struct::TAG_NAMES),)*]
+            }
         }
     };
 }
@@ -249,21 +302,25 @@ macro_rules! define_struct {
         }
 
         impl $name {
+            pub const TAG_NAMES: &'static [&'static str] = &[
+                $(stringify!($tag_variant1),)* $(stringify!($tag_variant2),)*
+            ];
+
             pub fn new() -> Self {
                 Self {
                     $($field_name: None,)*
                 }
             }
 
-            pub fn update_tag(&mut self, meta: &mut Metadata, tag: ExifTag) {
+            pub fn update_tag(&mut self, meta: &mut ::little_exif::metadata::Metadata, tag: ::little_exif::exif_tag::ExifTag) {
                 match &tag {
                     $(
-                        ExifTag::$tag_variant1(v) => {
+                        ::little_exif::exif_tag::ExifTag::$tag_variant1(v) => {
                             self.$target_field1 = $func1(v);
                         }
                     )*
                     $(
-                        ExifTag::$tag_variant2(v, u) => {
+                        ::little_exif::exif_tag::ExifTag::$tag_variant2(v, u) => {
                             self.$target_field2 = $func2(v, u);
                         }
                     )*
@@ -272,21 +329,21 @@ macro_rules! define_struct {
                 meta.set_tag(tag);
             }
 
-            pub fn delete_tag(&mut self, meta: &mut Metadata, tag: ExifTag) {
+            pub fn delete_tag(&mut self, meta: &mut ::little_exif::metadata::Metadata, tag: ::little_exif::exif_tag::ExifTag) {
                 match &tag {
                     $(
-                        ExifTag::$tag_variant1(_) => {
+                        ::little_exif::exif_tag::ExifTag::$tag_variant1(_) => {
                             self.$target_field1 = None;
                         }
                     )*
                     $(
-                        ExifTag::$tag_variant2(_, _) => {
+                        ::little_exif::exif_tag::ExifTag::$tag_variant2(_, _) => {
                             self.$target_field2 = None;
                         }
                     )*
                     _ => { return; }
                 }
-                meta.remove_tag(tag);
+                $crate::exif::utils::RemoveTag::remove_tag(meta, tag);
             }
         }
     };
@@ -340,7 +397,7 @@ macro_rules! define_enum {
             pub fn unknown() -> Self { Self::UnknownValue(vec![$unknown]) }
         }
 
-        impl AllList for $name {
+        impl $crate::exif::utils::AllList for $name {
             fn to_u16(&self) -> u16 {
                 self.to_vec()[0]
             }
@@ -361,7 +418,7 @@ macro_rules! define_enum {
             }
         }
 
-        impl ShowValue for $name {
+        impl $crate::exif::utils::ShowValue for $name {
             fn show_value(&self) -> String { self.to_string() }
         }
 
@@ -418,7 +475,7 @@ macro_rules! define_str_enum {
             }
         }
 
-        impl AllList for $name {
+        impl $crate::exif::utils::AllList for $name {
             fn to_u16(&self) -> u16 {
                 let v = vec![$(Self::$variant),*,];
                 if let Some(i) = v.iter().position(|x| x == self) {
@@ -439,7 +496,7 @@ macro_rules! define_str_enum {
             }
         }
 
-        impl ShowValue for $name {
+        impl $crate::exif::utils::ShowValue for $name {
             fn show_value(&self) -> String { self.to_string() }
         }
 
@@ -0,0 +1,93 @@
+use little_exif::filetype::FileExtension;
+use little_exif::metadata::Metadata;
+
+use crate::heif;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileType {
+    JPEG,
+    HEIC,
+    PNG,
+    WEBP,
+    TIFF,
+}
+
+impl FileType {
+    // 拡張子ではなく先頭のマジックバイトで判定する
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(FileType::JPEG)
+        } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            Some(FileType::PNG)
+        } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(FileType::WEBP)
+        } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+            Some(FileType::TIFF)
+        } else if heif::is_heif(bytes) {
+            Some(FileType::HEIC)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileType::JPEG => "JPEG",
+            FileType::HEIC => "HEIC",
+            FileType::PNG => "PNG",
+            FileType::WEBP => "WebP",
+            FileType::TIFF => "TIFF",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileType::JPEG => "jpg",
+            FileType::HEIC => "heic",
+            FileType::PNG => "png",
+            FileType::WEBP => "webp",
+            FileType::TIFF => "tif",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            FileType::JPEG => "image/jpeg",
+            FileType::HEIC => "image/heic",
+            FileType::PNG => "image/png",
+            FileType::WEBP => "image/webp",
+            FileType::TIFF => "image/tiff",
+        }
+    }
+
+    // HEICはlittle_exifではなくheifモジュールで読み書きする
+    pub fn file_extension(&self) -> Option<FileExtension> {
+        match self {
+            FileType::JPEG => Some(FileExtension::JPEG),
+            FileType::HEIC => None,
+            FileType::PNG => Some(FileExtension::PNG { as_zTXt_chunk: true }),
+            FileType::WEBP => Some(FileExtension::WEBP),
+            FileType::TIFF => Some(FileExtension::TIFF),
+        }
+    }
+
+    pub fn read_metadata(&self, bytes: &[u8]) -> Result<Metadata, String> {
+        match self.file_extension() {
+            Some(ext) => Metadata::new_from_vec(&bytes.to_vec(), ext).map_err(|e| e.to_string()),
+            None => heif::read_metadata(bytes).map_err(|e| e.to_string()),
+        }
+    }
+
+    // 元のコンテナを保ったままExifを書き戻す
+    pub fn write_metadata(&self, bytes: &[u8], metadata: &Metadata) -> Result<Vec<u8>, String> {
+        match self.file_extension() {
+            Some(ext) => {
+                let mut bytes = bytes.to_vec();
+                metadata.write_to_vec(&mut bytes, ext)
+                    .map(|_| bytes)
+                    .map_err(|e| e.to_string())
+            }
+            None => heif::write_metadata(bytes, metadata).map_err(|e| e.to_string()),
+        }
+    }
+}
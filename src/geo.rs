@@ -0,0 +1,5 @@
+pub mod fuzz;
+pub mod geotag;
+pub mod track;
+pub mod waypoint;
+pub mod xml;
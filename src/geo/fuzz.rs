@@ -0,0 +1,188 @@
+use little_exif::exif_tag::ExifTag;
+use little_exif::rational::uR64;
+
+use crate::exif::ExifEditData;
+use crate::exif::tag_value::empty_tag;
+use crate::file_type::FileType;
+use crate::iptc::{self, IptcData, APPLICATION_RECORD};
+use crate::xmp::{container, XmpPacket, XmpValue, EXIF_NS, IPTC_CORE_NS};
+use crate::xmp::sync::{format_xmp_coordinate, parse_xmp_coordinate};
+
+// 緯度1度あたりの距離（m）
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+// 精度を下げた位置からは分からないはずの情報
+const PRECISE_TAGS: &[&str] = &[
+    "GPSAltitudeRef", "GPSAltitude",
+    "GPSImgDirectionRef", "GPSImgDirection",
+    "GPSDestLatitudeRef", "GPSDestLatitude",
+    "GPSDestLongitudeRef", "GPSDestLongitude",
+    "GPSDestBearingRef", "GPSDestBearing",
+    "GPSDestDistanceRef", "GPSDestDistance",
+    "GPSHPositioningError",
+];
+
+// IPTCのSub-location（2:92）。市区町村より細かい地名
+const IPTC_SUBLOCATION: u8 = 92;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FuzzMethod {
+    // 格子点に丸める
+    Grid,
+    // 半径内でランダムにずらす
+    Random,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct LocationFuzz {
+    pub method: FuzzMethod,
+    // 格子の間隔、またはずらす半径（m）
+    pub meters: f64,
+    pub seed: u64,
+}
+
+impl LocationFuzz {
+    pub fn grid(meters: f64) -> Self {
+        Self { method: FuzzMethod::Grid, meters, seed: 0 }
+    }
+
+    pub fn random(meters: f64, seed: u64) -> Self {
+        Self { method: FuzzMethod::Random, meters, seed }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.method {
+            FuzzMethod::Grid => format!("位置をぼかす: {}間隔の格子に丸める", format_meters(self.meters)),
+            FuzzMethod::Random => format!("位置をぼかす: 半径{}以内でランダムにずらす", format_meters(self.meters)),
+        }
+    }
+
+    // ぼかした後の位置と元の位置との最大の距離（m）
+    pub fn error_radius(&self) -> f64 {
+        match self.method {
+            FuzzMethod::Grid => self.meters * std::f64::consts::SQRT_2 / 2.0,
+            FuzzMethod::Random => self.meters,
+        }
+    }
+
+    pub fn fuzz(&self, lat: f64, lng: f64) -> (f64, f64) {
+        let (lat, lng) = match self.method {
+            FuzzMethod::Grid => snap_to_grid(lat, lng, self.meters),
+            FuzzMethod::Random => {
+                // 元の位置ごとにずらすと、近くで撮った何枚もの写真を平均して元の位置を推定できてしまう。
+                // 格子に丸めてから格子ごとに決まった分だけずらし、同じ格子の写真は同じ位置にする。
+                // 丸めとずらしのどちらも半径の半分以内に収める
+                let (lat, lng) = snap_to_grid(lat, lng, self.meters * std::f64::consts::SQRT_2 / 2.0);
+                let mut rng = SplitMix(self.seed ^ lat.to_bits() ^ lng.to_bits().rotate_left(32));
+                let distance = self.meters / 2.0 * rng.unit().sqrt();
+                let bearing = rng.unit() * std::f64::consts::TAU;
+                let dlat = distance * bearing.cos() / METERS_PER_DEGREE;
+                let dlng = distance * bearing.sin() / (METERS_PER_DEGREE * lat.to_radians().cos().max(0.01));
+                ((lat + dlat).clamp(-90.0, 90.0), lng + dlng)
+            }
+        };
+        (lat, wrap_longitude(lng))
+    }
+
+    // 位置がなければNoneを返す。その場合も高度や向きなどは削除する
+    pub fn apply(&self, eed: &mut ExifEditData) -> Option<(f64, f64)> {
+        let position = eed.position().map(|(lat, lng)| self.fuzz(lat, lng));
+        eed.drop_precise_location();
+        let (lat, lng) = position?;
+        if !eed.set_lat_lng(lat, lng) {
+            return None;
+        }
+        eed.update_tag(ExifTag::GPSHPositioningError(vec![
+            uR64 { nominator: self.error_radius().ceil() as u32, denominator: 1 }
+        ]));
+        Some((lat, lng))
+    }
+
+    // XMPのexif:GPSLatitude/GPSLongitudeを同じようにぼかし、細かい地名（Iptc4xmpCore:Location）も削除する
+    pub fn apply_xmp(&self, packet: &mut XmpPacket) -> Result<Option<(f64, f64)>, String> {
+        let position = packet.get_text(EXIF_NS, "GPSLatitude").and_then(|s| parse_xmp_coordinate(&s))
+            .zip(packet.get_text(EXIF_NS, "GPSLongitude").and_then(|s| parse_xmp_coordinate(&s)))
+            .map(|(lat, lng)| self.fuzz(lat, lng));
+        packet.properties.retain(|p| {
+            let precise = p.namespace == EXIF_NS && PRECISE_TAGS.contains(&p.name.as_str());
+            let location = p.namespace == IPTC_CORE_NS && p.name == "Location";
+            !precise && !location
+        });
+        let Some((lat, lng)) = position else { return Ok(None) };
+        packet.set(EXIF_NS, "GPSLatitude", XmpValue::Text(format_xmp_coordinate(lat, true)))?;
+        packet.set(EXIF_NS, "GPSLongitude", XmpValue::Text(format_xmp_coordinate(lng, false)))?;
+        packet.set(EXIF_NS, "GPSHPositioningError", XmpValue::Text(format!("{}/1", self.error_radius().ceil() as u32)))?;
+        Ok(Some((lat, lng)))
+    }
+
+    pub fn apply_iptc(&self, iptc: &mut IptcData) {
+        iptc.datasets.retain(|d| !(d.record == APPLICATION_RECORD && d.dataset == IPTC_SUBLOCATION));
+    }
+
+    // ファイルに埋め込まれたXMPとIPTCにぼかしを適用する。読み込めないXMP・IPTCは元の位置が残るため失敗とする
+    pub fn apply_embedded(&self, file_type: FileType, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        let xmp = container::read(file_type, &bytes).map(|text| XmpPacket::parse(&text)).transpose()?;
+        let iptc = iptc::read(file_type, &bytes).transpose()?;
+        let mut fuzzed_xmp = xmp.clone();
+        if let Some(packet) = fuzzed_xmp.as_mut() {
+            self.apply_xmp(packet)?;
+        }
+        let mut fuzzed_iptc = iptc.clone();
+        if let Some(data) = fuzzed_iptc.as_mut() {
+            self.apply_iptc(data);
+        }
+        let bytes = container::write_changes(file_type, bytes, fuzzed_xmp.as_ref(), xmp.as_ref())?;
+        iptc::write_changes(file_type, bytes, fuzzed_iptc.as_ref(), iptc.as_ref())
+    }
+}
+
+impl ExifEditData {
+    pub fn drop_precise_location(&mut self) {
+        for name in PRECISE_TAGS.iter() {
+            if let Some(tag) = empty_tag(name) {
+                self.delete_tag(tag);
+            }
+        }
+    }
+}
+
+pub fn format_meters(meters: f64) -> String {
+    if meters >= 1000.0 {
+        format!("{}km", meters / 1000.0)
+    } else {
+        format!("{}m", meters)
+    }
+}
+
+fn snap_to_grid(lat: f64, lng: f64, meters: f64) -> (f64, f64) {
+    let lat_step = meters / METERS_PER_DEGREE;
+    let lat = snap(lat, lat_step).clamp(-90.0, 90.0);
+    // 経度の間隔は丸めた後の緯度で決め、同じ格子の地点が同じ位置になるようにする
+    let lng_step = (meters / (METERS_PER_DEGREE * lat.to_radians().cos().max(0.01))).min(360.0);
+    (lat, snap(lng, lng_step))
+}
+
+fn snap(value: f64, step: f64) -> f64 {
+    (value / step).round() * step
+}
+
+fn wrap_longitude(lng: f64) -> f64 {
+    let lng = (lng + 180.0).rem_euclid(360.0) - 180.0;
+    if lng == -180.0 { 180.0 } else { lng }
+}
+
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
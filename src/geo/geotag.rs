@@ -0,0 +1,70 @@
+use std::rc::Rc;
+
+use chrono::Duration;
+use little_exif::exif_tag::ExifTag;
+use little_exif::rational::uR64;
+
+use crate::exif::ExifEditData;
+use crate::exif::clock::format_shift;
+use crate::exif::exif_capture::TimeOffset;
+
+use super::track::{Track, TrackPoint};
+
+#[derive(Clone, PartialEq)]
+pub struct GeotagOptions {
+    // 前後の地点がこれ以上離れていれば補間せず、これ以上離れた地点は使わない
+    pub max_gap: Duration,
+    // カメラの時計の遅れ（撮影日時にこの値を足したものを正しい時刻とみなす）
+    pub clock_offset: Duration,
+    // OffsetTimeOriginalがない画像のタイムゾーン
+    pub default_offset: TimeOffset,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Geotag {
+    pub track: Rc<Track>,
+    pub options: GeotagOptions,
+}
+
+impl Geotag {
+    pub fn describe(&self) -> String {
+        format!(
+            "ジオタグ: {}地点のトラック、最大間隔{}分、時計補正{}、既定のタイムゾーン{}",
+            self.track.points.len(),
+            self.options.max_gap.num_minutes(),
+            format_shift(&self.options.clock_offset),
+            self.options.default_offset.to_string(),
+        )
+    }
+
+    pub fn apply(&self, eed: &mut ExifEditData) -> Result<TrackPoint, String> {
+        let utc = eed.capture_time_utc(Some(&self.options.default_offset))
+            .ok_or_else(|| "撮影日時がありません".to_string())?
+            .checked_add_signed(self.options.clock_offset)
+            .ok_or_else(|| "時計補正後の撮影日時が扱える範囲を超えています".to_string())?;
+        let point = self.track.locate(utc, self.options.max_gap)
+            .ok_or_else(|| format!("撮影時刻 {} (UTC) に近い地点がトラックにありません", utc.format("%Y-%m-%d %H:%M:%S")))?;
+        eed.set_position(point.lat, point.lng, point.ele);
+        eed.set_gps_stamp(&point.time);
+        Ok(point)
+    }
+}
+
+impl ExifEditData {
+    // WGS-84の緯度・経度（度）と高度（m）を書き込む
+    pub fn set_position(&mut self, lat: f64, lng: f64, ele: Option<f64>) {
+        if !self.set_lat_lng(lat, lng) {
+            return;
+        }
+        if self.gps_info.location_info.gps_version_id.is_none() {
+            self.update_tag(ExifTag::GPSVersionID(vec![2, 3, 0, 0]));
+        }
+        if let Some(ele) = ele {
+            self.update_tag(ExifTag::GPSAltitudeRef(vec![if ele >= 0.0 { 0 } else { 1 }]));
+            self.update_tag(ExifTag::GPSAltitude(vec![
+                uR64 { nominator: (ele.abs() * 100.0).round() as u32, denominator: 100 }
+            ]));
+        }
+        self.update_tag(ExifTag::GPSMapDatum("WGS-84".to_string()));
+    }
+}
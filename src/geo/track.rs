@@ -0,0 +1,203 @@
+use chrono::{DateTime, Duration, NaiveDateTime};
+use serde_json::Value;
+
+use super::xml::{attribute, child_text, elements, text};
+
+#[derive(Clone, PartialEq)]
+pub struct TrackPoint {
+    // UTC
+    pub time: NaiveDateTime,
+    pub lat: f64,
+    pub lng: f64,
+    pub ele: Option<f64>,
+}
+
+impl TrackPoint {
+    fn interpolate(&self, next: &TrackPoint, time: NaiveDateTime) -> TrackPoint {
+        let total = (next.time - self.time).num_milliseconds() as f64;
+        let r = if total > 0.0 { (time - self.time).num_milliseconds() as f64 / total } else { 0.0 };
+        let ele = match (self.ele, next.ele) {
+            (Some(a), Some(b)) => Some(a + (b - a) * r),
+            (a, b) => if r < 0.5 { a.or(b) } else { b.or(a) },
+        };
+        TrackPoint {
+            time,
+            lat: self.lat + (next.lat - self.lat) * r,
+            lng: self.lng + (next.lng - self.lng) * r,
+            ele,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Track {
+    pub points: Vec<TrackPoint>,
+}
+
+impl Track {
+    // GPX、KML（gx:Track、TimeStamp付きのPlacemark）、GeoJSON（coordTimes付きのLineStringなど）を読み込む
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let trimmed = text.trim_start();
+        let mut points = if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            let document = serde_json::from_str::<Value>(text).map_err(|e| format!("GeoJSONを読み込めません: {}", e))?;
+            parse_geojson(&document)
+        } else if text.contains("<gpx") {
+            parse_gpx(text)
+        } else if text.contains("<kml") {
+            parse_kml(text)
+        } else {
+            return Err("GPX, KML, GeoJSONのいずれでもありません".to_string());
+        };
+        if points.is_empty() {
+            return Err("時刻付きの地点が含まれていません".to_string());
+        }
+        points.sort_by_key(|p| p.time);
+        points.dedup_by_key(|p| p.time);
+        Ok(Self { points })
+    }
+
+    pub fn start(&self) -> Option<NaiveDateTime> {
+        self.points.first().map(|p| p.time)
+    }
+
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        self.points.last().map(|p| p.time)
+    }
+
+    // 前後の地点の間隔がmax_gap以内なら線形補間し、そうでなければmax_gap以内にある最も近い地点を使う
+    pub fn locate(&self, time: NaiveDateTime, max_gap: Duration) -> Option<TrackPoint> {
+        let i = self.points.partition_point(|p| p.time < time);
+        let next = self.points.get(i);
+        let prev = if i > 0 { self.points.get(i - 1) } else { None };
+        match (prev, next) {
+            (_, Some(n)) if n.time == time => Some(n.clone()),
+            (Some(p), Some(n)) if n.time - p.time <= max_gap => Some(p.interpolate(n, time)),
+            _ => [prev, next].into_iter()
+                .flatten()
+                .filter(|p| (p.time - time).abs() <= max_gap)
+                .min_by_key(|p| (p.time - time).abs())
+                .map(|p| TrackPoint { time, ..p.clone() }),
+        }
+    }
+}
+
+pub fn parse_time(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.naive_utc());
+    }
+    // タイムゾーンのない時刻はUTCとみなす
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+fn point(time: Option<NaiveDateTime>, lat: Option<f64>, lng: Option<f64>, ele: Option<f64>) -> Option<TrackPoint> {
+    let (time, lat, lng) = (time?, lat?, lng?);
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+        return None;
+    }
+    Some(TrackPoint { time, lat, lng, ele })
+}
+
+fn parse_gpx(document: &str) -> Vec<TrackPoint> {
+    elements(document, "trkpt").into_iter()
+        .filter_map(|(attrs, inner)| point(
+            child_text(inner, "time").and_then(|t| parse_time(&t)),
+            attribute(attrs, "lat").and_then(|v| v.trim().parse().ok()),
+            attribute(attrs, "lon").and_then(|v| v.trim().parse().ok()),
+            child_text(inner, "ele").and_then(|v| v.trim().parse().ok()),
+        ))
+        .collect()
+}
+
+// KMLの座標は「経度,緯度[,高度]」、gx:coordは「経度 緯度 [高度]」
+fn kml_coord(s: &str) -> (Option<f64>, Option<f64>, Option<f64>) {
+    let v = s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<f64>().ok())
+        .collect::<Vec<Option<f64>>>();
+    (v.get(1).copied().flatten(), v.first().copied().flatten(), v.get(2).copied().flatten())
+}
+
+fn parse_kml(document: &str) -> Vec<TrackPoint> {
+    let mut ret = Vec::new();
+    for (_, track) in elements(document, "Track") {
+        let whens = elements(track, "when");
+        let coords = elements(track, "coord");
+        for ((_, when), (_, coord)) in whens.iter().zip(coords.iter()) {
+            let (lat, lng, ele) = kml_coord(&text(coord));
+            ret.extend(point(parse_time(&text(when)), lat, lng, ele));
+        }
+    }
+    for (_, placemark) in elements(document, "Placemark") {
+        let Some(when) = elements(placemark, "TimeStamp").first().and_then(|(_, t)| child_text(t, "when")) else { continue; };
+        let Some((_, pt)) = elements(placemark, "Point").into_iter().next() else { continue; };
+        if let Some(coordinates) = child_text(pt, "coordinates") {
+            let (lat, lng, ele) = kml_coord(&coordinates);
+            ret.extend(point(parse_time(&when), lat, lng, ele));
+        }
+    }
+    ret
+}
+
+fn geojson_coord(v: &Value) -> (Option<f64>, Option<f64>, Option<f64>) {
+    let c = v.as_array().map(|a| a.iter().map(|x| x.as_f64()).collect::<Vec<Option<f64>>>()).unwrap_or_default();
+    (c.get(1).copied().flatten(), c.first().copied().flatten(), c.get(2).copied().flatten())
+}
+
+fn geojson_time(v: &Value) -> Option<NaiveDateTime> {
+    match v {
+        Value::String(s) => parse_time(s),
+        // 数値はUNIX時間（ミリ秒）とみなす
+        Value::Number(n) => n.as_i64().and_then(DateTime::from_timestamp_millis).map(|dt| dt.naive_utc()),
+        _ => None,
+    }
+}
+
+fn geojson_feature(feature: &Value, ret: &mut Vec<TrackPoint>) {
+    let geometry = feature.get("geometry").unwrap_or(feature);
+    let properties = feature.get("properties");
+    let times = properties
+        .and_then(|p| p.get("coordTimes").or_else(|| p.get("times")))
+        .and_then(|t| t.as_array());
+    let coordinates = geometry.get("coordinates");
+
+    match geometry.get("type").and_then(|t| t.as_str()) {
+        Some("Point") => {
+            let time = properties.and_then(|p| p.get("time").or_else(|| p.get("timestamp"))).and_then(geojson_time);
+            let (lat, lng, ele) = coordinates.map(geojson_coord).unwrap_or((None, None, None));
+            ret.extend(point(time, lat, lng, ele));
+        }
+        Some("LineString") => {
+            let (Some(coords), Some(times)) = (coordinates.and_then(|c| c.as_array()), times) else { return; };
+            for (c, t) in coords.iter().zip(times.iter()) {
+                let (lat, lng, ele) = geojson_coord(c);
+                ret.extend(point(geojson_time(t), lat, lng, ele));
+            }
+        }
+        // coordTimesは線ごとの配列になる
+        Some("MultiLineString") => {
+            let (Some(lines), Some(times)) = (coordinates.and_then(|c| c.as_array()), times) else { return; };
+            for (line, line_times) in lines.iter().zip(times.iter()) {
+                let (Some(coords), Some(line_times)) = (line.as_array(), line_times.as_array()) else { continue; };
+                for (c, t) in coords.iter().zip(line_times.iter()) {
+                    let (lat, lng, ele) = geojson_coord(c);
+                    ret.extend(point(geojson_time(t), lat, lng, ele));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn parse_geojson(document: &Value) -> Vec<TrackPoint> {
+    let mut ret = Vec::new();
+    match document.get("features").and_then(|f| f.as_array()) {
+        Some(features) => {
+            for feature in features.iter() {
+                geojson_feature(feature, &mut ret);
+            }
+        }
+        None => geojson_feature(document, &mut ret),
+    }
+    ret
+}
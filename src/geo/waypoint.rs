@@ -0,0 +1,167 @@
+use chrono::NaiveDateTime;
+use serde_json::{json, Map, Value};
+
+use crate::exif::ExifEditData;
+use crate::exif::gps::GPSAltitudeRef;
+
+use super::xml::escape;
+
+#[derive(Clone, PartialEq)]
+pub struct Waypoint {
+    pub name: String,
+    pub lat: f64,
+    pub lng: f64,
+    pub ele: Option<f64>,
+    // UTC
+    pub time: Option<NaiveDateTime>,
+    pub direction: Option<f64>,
+}
+
+impl ExifEditData {
+    pub fn altitude(&self) -> Option<f64> {
+        let info = &self.gps_info.location_info;
+        let alt = info.gps_altitude.as_ref()?.value();
+        match info.gps_altitude_ref {
+            Some(GPSAltitudeRef::BelowSeaLevel) | Some(GPSAltitudeRef::NegativeSeaLevel) => Some(-alt),
+            _ => Some(alt),
+        }
+    }
+
+    // GPS日時があればそれを、なければ撮影日時とそのオフセットから求める
+    pub fn gps_time_utc(&self) -> Option<NaiveDateTime> {
+        let info = &self.gps_info.location_info;
+        match (info.gps_date_stamp, info.gps_time_stamp) {
+            (Some(nd), Some(nt)) => Some(nd.and_time(nt)),
+            _ => self.capture_time_utc(None),
+        }
+    }
+
+    pub fn waypoint(&self, name: &str) -> Option<Waypoint> {
+        let (lat, lng) = self.position()?;
+        Some(Waypoint {
+            name: name.to_string(),
+            lat,
+            lng,
+            ele: self.altitude(),
+            time: self.gps_time_utc(),
+            direction: self.gps_info.location_info.gps_img_direction.as_ref().map(|d| d.value()),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WaypointFormat {
+    Gpx,
+    Kml,
+    GeoJson,
+}
+
+impl WaypointFormat {
+    pub fn all() -> [Self; 3] {
+        [WaypointFormat::Gpx, WaypointFormat::Kml, WaypointFormat::GeoJson]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WaypointFormat::Gpx => "GPX",
+            WaypointFormat::Kml => "KML",
+            WaypointFormat::GeoJson => "GeoJSON",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            WaypointFormat::Gpx => "gpx",
+            WaypointFormat::Kml => "kml",
+            WaypointFormat::GeoJson => "geojson",
+        }
+    }
+
+    pub fn export(&self, waypoints: &[Waypoint]) -> String {
+        match self {
+            WaypointFormat::Gpx => to_gpx(waypoints),
+            WaypointFormat::Kml => to_kml(waypoints),
+            WaypointFormat::GeoJson => serde_json::to_string_pretty(&to_geojson(waypoints)).unwrap_or_default(),
+        }
+    }
+}
+
+fn iso_time(time: &NaiveDateTime) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+pub fn to_gpx(waypoints: &[Waypoint]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gpx version=\"1.1\" creator=\"exif-edit\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+    for wpt in waypoints.iter() {
+        out.push_str(&format!("  <wpt lat=\"{:.7}\" lon=\"{:.7}\">\n", wpt.lat, wpt.lng));
+        if let Some(ele) = wpt.ele {
+            out.push_str(&format!("    <ele>{}</ele>\n", ele));
+        }
+        if let Some(time) = &wpt.time {
+            out.push_str(&format!("    <time>{}</time>\n", iso_time(time)));
+        }
+        out.push_str(&format!("    <name>{}</name>\n", escape(&wpt.name)));
+        // GPXのwptには向きの要素がないため、説明に入れる
+        if let Some(direction) = wpt.direction {
+            out.push_str(&format!("    <desc>GPSImgDirection: {}</desc>\n", direction));
+        }
+        out.push_str("  </wpt>\n");
+    }
+    out.push_str("</gpx>\n");
+    out
+}
+
+pub fn to_kml(waypoints: &[Waypoint]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    for wpt in waypoints.iter() {
+        out.push_str("  <Placemark>\n");
+        out.push_str(&format!("    <name>{}</name>\n", escape(&wpt.name)));
+        if let Some(time) = &wpt.time {
+            out.push_str(&format!("    <TimeStamp><when>{}</when></TimeStamp>\n", iso_time(time)));
+        }
+        if let Some(direction) = wpt.direction {
+            out.push_str(&format!("    <Style><IconStyle><heading>{}</heading></IconStyle></Style>\n", direction));
+            out.push_str(&format!("    <ExtendedData><Data name=\"GPSImgDirection\"><value>{}</value></Data></ExtendedData>\n", direction));
+        }
+        let coordinates = match wpt.ele {
+            Some(ele) => format!("{:.7},{:.7},{}", wpt.lng, wpt.lat, ele),
+            None => format!("{:.7},{:.7}", wpt.lng, wpt.lat),
+        };
+        // 高度は海抜として扱う
+        let mode = if wpt.ele.is_some() { "<altitudeMode>absolute</altitudeMode>" } else { "" };
+        out.push_str(&format!("    <Point>{}<coordinates>{}</coordinates></Point>\n", mode, coordinates));
+        out.push_str("  </Placemark>\n");
+    }
+    out.push_str("</Document>\n</kml>\n");
+    out
+}
+
+pub fn to_geojson(waypoints: &[Waypoint]) -> Value {
+    let features = waypoints.iter().map(|wpt| {
+        let mut coordinates = vec![json!(wpt.lng), json!(wpt.lat)];
+        if let Some(ele) = wpt.ele {
+            coordinates.push(json!(ele));
+        }
+        let mut properties = Map::new();
+        properties.insert("name".to_string(), json!(wpt.name));
+        if let Some(time) = &wpt.time {
+            properties.insert("time".to_string(), json!(iso_time(time)));
+        }
+        if let Some(ele) = wpt.ele {
+            properties.insert("altitude".to_string(), json!(ele));
+        }
+        if let Some(direction) = wpt.direction {
+            properties.insert("direction".to_string(), json!(direction));
+        }
+        json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": coordinates },
+            "properties": properties,
+        })
+    }).collect::<Vec<Value>>();
+    json!({ "type": "FeatureCollection", "features": features })
+}
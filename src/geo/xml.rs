@@ -0,0 +1,115 @@
+// GPXやKMLの簡易的な読み書き。
+// 読み取りは同じ名前の要素の入れ子には対応せず、名前空間の接頭辞（gx:など）は無視して比較する
+
+// 名前がnameの要素ごとに、(開始タグの属性部分, 中身)を返す
+pub fn elements<'a>(text: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let mut ret = Vec::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('<') {
+        rest = &rest[i + 1..];
+        let end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/').unwrap_or(rest.len());
+        let full = &rest[..end];
+        let local = full.rsplit(':').next().unwrap_or(full);
+        if full.is_empty() || local != name {
+            continue;
+        }
+        let Some(close) = rest.find('>') else { break; };
+        let attrs = &rest[end..close];
+        if let Some(attrs) = attrs.strip_suffix('/') {
+            ret.push((attrs, ""));
+            rest = &rest[close + 1..];
+            continue;
+        }
+        let body = &rest[close + 1..];
+        let closing = format!("</{}>", full);
+        match body.find(&closing) {
+            Some(j) => {
+                ret.push((attrs, &body[..j]));
+                rest = &body[j + closing.len()..];
+            }
+            None => break,
+        }
+    }
+    ret
+}
+
+pub fn attribute(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(i) = rest.find(name) {
+        let before = rest[..i].chars().last();
+        let after = rest[i + name.len()..].trim_start();
+        rest = &rest[i + name.len()..];
+        if !before.map(|c| c.is_whitespace()).unwrap_or(false) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').map(|v| v.trim_start()) else { continue; };
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return value.find(quote).map(|j| unescape(&value[..j]));
+    }
+    None
+}
+
+pub fn text(inner: &str) -> String {
+    let inner = inner.trim();
+    match inner.strip_prefix("<![CDATA[").and_then(|s| s.strip_suffix("]]>")) {
+        Some(s) => s.to_string(),
+        None => unescape(inner),
+    }
+}
+
+pub fn child_text(inner: &str, name: &str) -> Option<String> {
+    elements(inner, name).first().map(|(_, t)| text(t))
+}
+
+// 文字参照を先頭から1回だけ戻す（"&amp;lt;"は"&lt;"のまま残す）
+pub fn unescape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        ret.push_str(&rest[..i]);
+        rest = &rest[i..];
+        match rest.find(';').and_then(|j| entity(&rest[1..j]).map(|c| (c, j))) {
+            Some((c, j)) => {
+                ret.push(c);
+                rest = &rest[j + 1..];
+            }
+            None => {
+                ret.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+// "lt"や"#10"、"#x3042"のような参照名を文字にする
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "amp" => Some('&'),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let n = match code.strip_prefix('x').or_else(|| code.strip_prefix('X')) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse::<u32>().ok()?,
+            };
+            char::from_u32(n)
+        }
+    }
+}
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
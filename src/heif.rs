@@ -0,0 +1,458 @@
+use std::fmt;
+
+use little_exif::metadata::Metadata;
+
+use crate::jpeg;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeifError {
+    Truncated,
+    MissingBox(&'static str),
+    Unsupported(&'static str),
+    InvalidExif,
+}
+
+impl fmt::Display for HeifError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeifError::Truncated => write!(f, "HEIFファイルが途中で途切れています"),
+            HeifError::MissingBox(name) => write!(f, "HEIFファイルに{}ボックスがありません", name),
+            HeifError::Unsupported(name) => write!(f, "未対応のHEIF構造です ({})", name),
+            HeifError::InvalidExif => write!(f, "HEIFファイル内のExifデータを解釈できません"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BoxHeader {
+    box_type: [u8; 4],
+    start: usize,
+    header_len: usize,
+    end: usize,
+}
+
+impl BoxHeader {
+    fn body(&self) -> usize {
+        self.start + self.header_len
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ItemInfo {
+    item_id: u32,
+    item_type: [u8; 4],
+}
+
+#[derive(Clone, Debug)]
+struct Extent {
+    index: u64,
+    offset: u64,
+    length: u64,
+}
+
+// base_offsetは読み込み時に各extentのoffsetへ畳み込んでおく
+#[derive(Clone, Debug)]
+struct ItemLocation {
+    item_id: u32,
+    construction_method: u8,
+    data_reference_index: u16,
+    extents: Vec<Extent>,
+}
+
+#[derive(Clone, Debug)]
+struct ItemLocations {
+    version: u8,
+    index_size: usize,
+    items: Vec<ItemLocation>,
+}
+
+fn read_u(data: &[u8], pos: usize, size: usize) -> Result<u64, HeifError> {
+    if size > 8 { return Err(HeifError::Unsupported("field size")); }
+    match data.get(pos..pos + size) {
+        Some(bytes) => Ok(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64)),
+        None => Err(HeifError::Truncated),
+    }
+}
+
+fn write_u(buf: &mut Vec<u8>, value: u64, size: usize) {
+    for i in (0..size).rev() {
+        buf.push((value >> (i * 8)) as u8);
+    }
+}
+
+fn make_box(box_type: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(body.len() + 8);
+    write_u(&mut ret, (body.len() + 8) as u64, 4);
+    ret.extend_from_slice(box_type);
+    ret.extend_from_slice(body);
+    ret
+}
+
+fn read_box(data: &[u8], pos: usize, limit: usize) -> Result<BoxHeader, HeifError> {
+    if pos + 8 > limit { return Err(HeifError::Truncated); }
+    let size = read_u(data, pos, 4)?;
+    let mut box_type = [0u8; 4];
+    box_type.copy_from_slice(&data[pos + 4..pos + 8]);
+    let (mut header_len, size) = match size {
+        0 => (8, limit - pos),
+        1 => (16, usize::try_from(read_u(data, pos + 8, 8)?).map_err(|_| HeifError::Truncated)?),
+        _ => (8, size as usize),
+    };
+    let end = pos.checked_add(size).ok_or(HeifError::Truncated)?;
+    if &box_type == b"uuid" { header_len += 16; }
+    if end > limit || end < pos + header_len { return Err(HeifError::Truncated); }
+    Ok(BoxHeader { box_type, start: pos, header_len, end })
+}
+
+fn children(data: &[u8], start: usize, end: usize) -> Result<Vec<BoxHeader>, HeifError> {
+    let mut ret = Vec::new();
+    let mut pos = start;
+    while pos < end {
+        let b = read_box(data, pos, end)?;
+        pos = b.end;
+        ret.push(b);
+    }
+    Ok(ret)
+}
+
+fn find(boxes: &[BoxHeader], box_type: &[u8; 4]) -> Option<BoxHeader> {
+    boxes.iter().find(|b| &b.box_type == box_type).copied()
+}
+
+fn parse_iinf(data: &[u8], iinf: &BoxHeader) -> Result<Vec<ItemInfo>, HeifError> {
+    let version = read_u(data, iinf.body(), 1)?;
+    let count_size = if version == 0 { 2 } else { 4 };
+    let mut ret = Vec::new();
+    for infe in children(data, iinf.body() + 4 + count_size, iinf.end)? {
+        if &infe.box_type != b"infe" { continue; }
+        let infe_version = read_u(data, infe.body(), 1)?;
+        // version 0/1のinfeはitem_typeを持たない
+        if infe_version < 2 { continue; }
+        let id_size = if infe_version == 2 { 2 } else { 4 };
+        let pos = infe.body() + 4;
+        let item_id = read_u(data, pos, id_size)? as u32;
+        let type_pos = pos + id_size + 2;
+        if type_pos + 4 > infe.end { return Err(HeifError::Truncated); }
+        let mut item_type = [0u8; 4];
+        item_type.copy_from_slice(&data[type_pos..type_pos + 4]);
+        ret.push(ItemInfo { item_id, item_type });
+    }
+    Ok(ret)
+}
+
+fn parse_iloc(data: &[u8], iloc: &BoxHeader) -> Result<ItemLocations, HeifError> {
+    let mut pos = iloc.body();
+    let version = read_u(data, pos, 1)? as u8;
+    if version > 2 { return Err(HeifError::Unsupported("iloc")); }
+    pos += 4;
+    let sizes = read_u(data, pos, 2)? as usize;
+    pos += 2;
+    let offset_size = (sizes >> 12) & 0xF;
+    let length_size = (sizes >> 8) & 0xF;
+    let base_offset_size = (sizes >> 4) & 0xF;
+    let index_size = if version > 0 { sizes & 0xF } else { 0 };
+    let id_size = if version < 2 { 2 } else { 4 };
+    let count = read_u(data, pos, id_size)?;
+    pos += id_size;
+
+    let mut items = Vec::new();
+    for _ in 0..count {
+        let item_id = read_u(data, pos, id_size)? as u32;
+        pos += id_size;
+        let construction_method = if version > 0 {
+            let v = read_u(data, pos, 2)?;
+            pos += 2;
+            (v & 0xF) as u8
+        } else { 0 };
+        let data_reference_index = read_u(data, pos, 2)? as u16;
+        pos += 2;
+        let base_offset = read_u(data, pos, base_offset_size)?;
+        pos += base_offset_size;
+        let extent_count = read_u(data, pos, 2)?;
+        pos += 2;
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            let index = read_u(data, pos, index_size)?;
+            pos += index_size;
+            let offset = read_u(data, pos, offset_size)?;
+            pos += offset_size;
+            let length = read_u(data, pos, length_size)?;
+            pos += length_size;
+            let offset = base_offset.checked_add(offset).ok_or(HeifError::Truncated)?;
+            extents.push(Extent { index, offset, length });
+        }
+        items.push(ItemLocation { item_id, construction_method, data_reference_index, extents });
+    }
+    if pos > iloc.end { return Err(HeifError::Truncated); }
+    Ok(ItemLocations { version, index_size, items })
+}
+
+// base_offset_sizeを0にして、全extentを絶対位置（またはidat内位置）で書き出す
+fn write_iloc(locs: &ItemLocations, offset_size: usize, length_size: usize) -> Vec<u8> {
+    let large_id = locs.items.iter().any(|item| item.item_id > 0xFFFF);
+    let version = if large_id { 2 } else { locs.version.max(1) };
+    let id_size = if version < 2 { 2 } else { 4 };
+    let mut body = vec![version, 0, 0, 0];
+    body.push(((offset_size << 4) | length_size) as u8);
+    body.push(locs.index_size as u8);
+    write_u(&mut body, locs.items.len() as u64, id_size);
+    for item in locs.items.iter() {
+        write_u(&mut body, item.item_id as u64, id_size);
+        write_u(&mut body, item.construction_method as u64, 2);
+        write_u(&mut body, item.data_reference_index as u64, 2);
+        write_u(&mut body, item.extents.len() as u64, 2);
+        for extent in item.extents.iter() {
+            write_u(&mut body, extent.index, locs.index_size);
+            write_u(&mut body, extent.offset, offset_size);
+            write_u(&mut body, extent.length, length_size);
+        }
+    }
+    make_box(b"iloc", &body)
+}
+
+fn item_bytes(data: &[u8], loc: &ItemLocation, idat: Option<&BoxHeader>) -> Result<Vec<u8>, HeifError> {
+    let (origin, limit) = match (loc.construction_method, idat) {
+        (0, _) => (0, data.len()),
+        (1, Some(idat)) => (idat.body(), idat.end),
+        (1, None) => return Err(HeifError::MissingBox("idat")),
+        _ => return Err(HeifError::Unsupported("construction_method")),
+    };
+    if loc.data_reference_index != 0 { return Err(HeifError::Unsupported("data_reference_index")); }
+    let mut ret = Vec::new();
+    for extent in loc.extents.iter() {
+        let offset = usize::try_from(extent.offset).map_err(|_| HeifError::Truncated)?;
+        let length = usize::try_from(extent.length).map_err(|_| HeifError::Truncated)?;
+        let start = origin.checked_add(offset).ok_or(HeifError::Truncated)?;
+        let end = start.checked_add(length).ok_or(HeifError::Truncated)?;
+        if end > limit { return Err(HeifError::Truncated); }
+        ret.extend_from_slice(&data[start..end]);
+    }
+    Ok(ret)
+}
+
+// Exifアイテムの先頭4バイトはTIFFヘッダまでのオフセット
+fn exif_item_to_tiff(item: &[u8]) -> Result<Vec<u8>, HeifError> {
+    let skip = read_u(item, 0, 4).map_err(|_| HeifError::InvalidExif)? as usize;
+    let mut tiff = item.get(4 + skip..).ok_or(HeifError::InvalidExif)?;
+    if tiff.starts_with(jpeg::EXIF_HEADER) {
+        tiff = &tiff[jpeg::EXIF_HEADER.len()..];
+    }
+    if !(tiff.starts_with(b"II") || tiff.starts_with(b"MM")) {
+        return Err(HeifError::InvalidExif);
+    }
+    Ok(tiff.to_vec())
+}
+
+// HEVCで符号化された静止画のHEIC（.heic）だけを受け付ける。
+// AVIFや画像シーケンスは書き出し時の拡張子やプレビューの扱いが異なるため対象外とする
+pub fn is_heif(data: &[u8]) -> bool {
+    const HEIC_BRANDS: &[&[u8]] = &[b"heic", b"heix", b"heim", b"heis"];
+    let Ok(ftyp) = read_box(data, 0, data.len()) else { return false; };
+    if &ftyp.box_type != b"ftyp" || ftyp.end < ftyp.body() + 8 { return false; }
+    let major = &data[ftyp.body()..ftyp.body() + 4];
+    // mif1のように汎用のブランドが先頭にある場合は、互換ブランドで判断する
+    HEIC_BRANDS.contains(&major)
+        || (major == b"mif1" && data[ftyp.body() + 8..ftyp.end].chunks_exact(4).any(|brand| HEIC_BRANDS.contains(&brand)))
+}
+
+// Exifアイテムが存在しない場合はNoneを返す
+pub fn read_exif(data: &[u8]) -> Result<Option<Vec<u8>>, HeifError> {
+    let top = children(data, 0, data.len())?;
+    let meta = find(&top, b"meta").ok_or(HeifError::MissingBox("meta"))?;
+    let meta_children = children(data, meta.body() + 4, meta.end)?;
+    let iinf = find(&meta_children, b"iinf").ok_or(HeifError::MissingBox("iinf"))?;
+    let iloc = find(&meta_children, b"iloc").ok_or(HeifError::MissingBox("iloc"))?;
+    let idat = find(&meta_children, b"idat");
+
+    let exif_id = match parse_iinf(data, &iinf)?.iter().find(|info| &info.item_type == b"Exif") {
+        Some(info) => info.item_id,
+        None => return Ok(None),
+    };
+    let locs = parse_iloc(data, &iloc)?;
+    let loc = locs.items.iter().find(|item| item.item_id == exif_id).ok_or(HeifError::MissingBox("iloc"))?;
+    let item = item_bytes(data, loc, idat.as_ref())?;
+    exif_item_to_tiff(&item).map(Some)
+}
+
+pub fn read_metadata(data: &[u8]) -> Result<Metadata, HeifError> {
+    match read_exif(data)? {
+        Some(tiff) => jpeg::metadata_from_tiff(&tiff).ok_or(HeifError::InvalidExif),
+        None => Ok(Metadata::new()),
+    }
+}
+
+pub fn write_metadata(data: &[u8], metadata: &Metadata) -> Result<Vec<u8>, HeifError> {
+    let tiff = jpeg::metadata_to_tiff(metadata).ok_or(HeifError::InvalidExif)?;
+    write_exif(data, &tiff)
+}
+
+// 新しいExifアイテムをファイル末尾のmdatに追加し、metaボックス内のiinf/iloc/irefを書き換える
+pub fn write_exif(data: &[u8], tiff: &[u8]) -> Result<Vec<u8>, HeifError> {
+    let top = children(data, 0, data.len())?;
+    let meta = find(&top, b"meta").ok_or(HeifError::MissingBox("meta"))?;
+    let meta_children = children(data, meta.body() + 4, meta.end)?;
+    let iinf = find(&meta_children, b"iinf").ok_or(HeifError::MissingBox("iinf"))?;
+    let iloc = find(&meta_children, b"iloc").ok_or(HeifError::MissingBox("iloc"))?;
+    let idat = find(&meta_children, b"idat");
+    let infos = parse_iinf(data, &iinf)?;
+    let mut locs = parse_iloc(data, &iloc)?;
+
+    let mut body = data.to_vec();
+
+    // サイズ0（ファイル末尾まで）のボックスの後ろには追記できないため、明示的なサイズに書き換える
+    if let Some(last) = top.last() {
+        if read_u(data, last.start, 4)? == 0 {
+            let size = u32::try_from(last.end - last.start).map_err(|_| HeifError::Unsupported("box size"))?;
+            body[last.start..last.start + 4].copy_from_slice(&size.to_be_bytes());
+        }
+    }
+
+    let exif_id = infos.iter().find(|info| &info.item_type == b"Exif").map(|info| info.item_id);
+    if let Some(id) = exif_id {
+        // 古いExifデータが孤立したまま残らないよう、ゼロで塗りつぶす
+        if let Some(loc) = locs.items.iter().find(|item| item.item_id == id) {
+            let origin = match loc.construction_method {
+                0 if loc.data_reference_index == 0 => Some(0),
+                1 => idat.map(|b| b.body()),
+                _ => None,
+            };
+            if let Some(origin) = origin {
+                for extent in loc.extents.iter() {
+                    let offset = usize::try_from(extent.offset).unwrap_or(usize::MAX);
+                    let length = usize::try_from(extent.length).unwrap_or(usize::MAX);
+                    let start = origin.saturating_add(offset).min(body.len());
+                    let end = start.saturating_add(length).min(body.len());
+                    body[start..end].fill(0);
+                }
+            }
+        }
+    }
+    let is_new_item = exif_id.is_none();
+    let exif_id = match exif_id {
+        Some(id) => id,
+        None => {
+            infos.iter().map(|info| info.item_id)
+                .chain(locs.items.iter().map(|item| item.item_id))
+                .max().unwrap_or(0) + 1
+        }
+    };
+    locs.items.retain(|item| item.item_id != exif_id);
+
+    let mut payload = Vec::with_capacity(tiff.len() + 10);
+    payload.extend_from_slice(&(jpeg::EXIF_HEADER.len() as u32).to_be_bytes());
+    payload.extend_from_slice(jpeg::EXIF_HEADER);
+    payload.extend_from_slice(tiff);
+
+    let mut replaced_iinf = None;
+    let mut replaced_iref = None;
+    let mut appended_iref = None;
+    if is_new_item {
+        replaced_iinf = Some(add_item_info(&body, &iinf, exif_id)?);
+        let pitm = find(&meta_children, b"pitm").ok_or(HeifError::MissingBox("pitm"))?;
+        let pitm_version = read_u(&body, pitm.body(), 1)?;
+        let primary_id = read_u(&body, pitm.body() + 4, if pitm_version == 0 { 2 } else { 4 })? as u32;
+        match find(&meta_children, b"iref") {
+            Some(iref) => replaced_iref = Some(add_reference(&body, Some(&iref), exif_id, primary_id)?),
+            None => appended_iref = Some(add_reference(&body, None, exif_id, primary_id)?),
+        }
+    }
+
+    let max_length = locs.items.iter()
+        .flat_map(|item| item.extents.iter().map(|extent| extent.length))
+        .chain(std::iter::once(payload.len() as u64))
+        .max().unwrap_or(0);
+    let length_size = if max_length > u32::MAX as u64 { 8 } else { 4 };
+    let offset_size = if (data.len() + payload.len()) as u64 + 0x10000 > u32::MAX as u64 { 8 } else { 4 };
+
+    let build_meta = |locs: &ItemLocations| -> Vec<u8> {
+        let mut meta_body = body[meta.body()..meta.body() + 4].to_vec();
+        for child in meta_children.iter() {
+            match &child.box_type {
+                b"iloc" => meta_body.extend(write_iloc(locs, offset_size, length_size)),
+                b"iinf" if replaced_iinf.is_some() => meta_body.extend_from_slice(replaced_iinf.as_ref().unwrap()),
+                b"iref" if replaced_iref.is_some() => meta_body.extend_from_slice(replaced_iref.as_ref().unwrap()),
+                _ => meta_body.extend_from_slice(&body[child.start..child.end]),
+            }
+        }
+        if let Some(iref) = appended_iref.as_ref() {
+            meta_body.extend_from_slice(iref);
+        }
+        make_box(b"meta", &meta_body)
+    };
+
+    let mut exif_loc = ItemLocation {
+        item_id: exif_id,
+        construction_method: 0,
+        data_reference_index: 0,
+        extents: vec![Extent { index: 0, offset: 0, length: payload.len() as u64 }],
+    };
+    locs.items.push(exif_loc.clone());
+
+    // ilocの長さはオフセットの値に依存しないので、仮のmetaからずれ幅を求められる
+    let delta = build_meta(&locs).len() as i64 - (meta.end - meta.start) as i64;
+    if delta != 0 && top.iter().any(|b| &b.box_type == b"moov" && b.start >= meta.end) {
+        return Err(HeifError::Unsupported("moov"));
+    }
+    locs.items.pop();
+    for item in locs.items.iter_mut() {
+        if item.construction_method != 0 || item.data_reference_index != 0 { continue; }
+        for extent in item.extents.iter_mut() {
+            if extent.offset >= meta.end as u64 {
+                extent.offset = (extent.offset as i64 + delta) as u64;
+            }
+        }
+    }
+    let exif_offset = body.len() as i64 + delta + 8;
+    exif_loc.extents[0].offset = exif_offset as u64;
+    locs.items.push(exif_loc);
+
+    let new_meta = build_meta(&locs);
+    let mut ret = Vec::with_capacity(body.len() + payload.len() + 0x100);
+    ret.extend_from_slice(&body[..meta.start]);
+    ret.extend(new_meta);
+    ret.extend_from_slice(&body[meta.end..]);
+    ret.extend(make_box(b"mdat", &payload));
+    Ok(ret)
+}
+
+fn add_item_info(data: &[u8], iinf: &BoxHeader, item_id: u32) -> Result<Vec<u8>, HeifError> {
+    let version = read_u(data, iinf.body(), 1)?;
+    let count_size = if version == 0 { 2 } else { 4 };
+    let count = read_u(data, iinf.body() + 4, count_size)? + 1;
+    if count_size == 2 && count > 0xFFFF { return Err(HeifError::Unsupported("iinf")); }
+
+    let mut infe = if item_id > 0xFFFF { vec![3, 0, 0, 0] } else { vec![2, 0, 0, 0] };
+    write_u(&mut infe, item_id as u64, if item_id > 0xFFFF { 4 } else { 2 });
+    write_u(&mut infe, 0, 2);
+    infe.extend_from_slice(b"Exif");
+    infe.push(0);
+
+    let mut iinf_body = data[iinf.body()..iinf.body() + 4].to_vec();
+    write_u(&mut iinf_body, count, count_size);
+    iinf_body.extend_from_slice(&data[iinf.body() + 4 + count_size..iinf.end]);
+    iinf_body.extend(make_box(b"infe", &infe));
+    Ok(make_box(b"iinf", &iinf_body))
+}
+
+// Exifアイテムからプライマリ画像へのcdsc参照を追加する
+fn add_reference(data: &[u8], iref: Option<&BoxHeader>, from_id: u32, to_id: u32) -> Result<Vec<u8>, HeifError> {
+    let (mut iref_body, id_size) = match iref {
+        Some(iref) => {
+            let version = read_u(data, iref.body(), 1)?;
+            let id_size = if version == 0 { 2 } else { 4 };
+            if id_size == 2 && from_id.max(to_id) > 0xFFFF {
+                return Err(HeifError::Unsupported("iref"));
+            }
+            (data[iref.body()..iref.end].to_vec(), id_size)
+        }
+        None => {
+            if from_id.max(to_id) > 0xFFFF { (vec![1, 0, 0, 0], 4) } else { (vec![0, 0, 0, 0], 2) }
+        }
+    };
+    let mut cdsc = Vec::new();
+    write_u(&mut cdsc, from_id as u64, id_size);
+    write_u(&mut cdsc, 1, 2);
+    write_u(&mut cdsc, to_id as u64, id_size);
+    iref_body.extend(make_box(b"cdsc", &cdsc));
+    Ok(make_box(b"iref", &iref_body))
+}
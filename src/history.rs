@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use little_exif::metadata::Metadata;
+
+use crate::exif::ExifEditData;
+use crate::exif::tag_value::{tags_by_key, value_string};
+use crate::iptc::IptcData;
+use crate::xmp::XmpPacket;
+
+const MAX_HISTORY: usize = 100;
+
+// 「すべて削除」などはExif・XMP・IPTCをまとめて変えるため、3つを組にして記録する
+#[derive(Clone, PartialEq)]
+pub struct Snapshot {
+    pub exif: ExifEditData,
+    pub xmp: Option<XmpPacket>,
+    pub iptc: Option<IptcData>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub snapshot: Snapshot,
+    pub description: String,
+}
+
+// undoには各操作の直前の状態を、redoには直後の状態を積む
+#[derive(Clone, PartialEq)]
+pub struct History {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    current: Option<Snapshot>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), current: None }
+    }
+
+    pub fn is_current(&self, snapshot: &Snapshot) -> bool {
+        self.current.as_ref() == Some(snapshot)
+    }
+
+    // 編集後の状態を受け取り、直前の状態との差分を1ステップとして記録する
+    pub fn record(&mut self, snapshot: &Snapshot) -> bool {
+        let before = match self.current.take() {
+            Some(before) => before,
+            None => {
+                self.current = Some(snapshot.clone());
+                return false;
+            }
+        };
+        if &before == snapshot {
+            self.current = Some(before);
+            return false;
+        }
+        let description = describe_snapshot_change(&before, snapshot);
+        self.undo.push(HistoryEntry { snapshot: before, description });
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.current = Some(snapshot.clone());
+        true
+    }
+
+    pub fn undo(&mut self) -> Option<Snapshot> {
+        let entry = self.undo.pop()?;
+        let after = self.current.replace(entry.snapshot.clone())?;
+        self.redo.push(HistoryEntry { snapshot: after, description: entry.description });
+        Some(entry.snapshot)
+    }
+
+    pub fn redo(&mut self) -> Option<Snapshot> {
+        let entry = self.redo.pop()?;
+        let before = self.current.replace(entry.snapshot.clone())?;
+        self.undo.push(HistoryEntry { snapshot: before, description: entry.description });
+        Some(entry.snapshot)
+    }
+
+    pub fn can_undo(&self) -> bool { !self.undo.is_empty() }
+    pub fn can_redo(&self) -> bool { !self.redo.is_empty() }
+
+    pub fn undo_description(&self) -> Option<&str> {
+        self.undo.last().map(|e| e.description.as_str())
+    }
+
+    pub fn redo_description(&self) -> Option<&str> {
+        self.redo.last().map(|e| e.description.as_str())
+    }
+
+    // 新しい順に、これまでの操作の説明を返す
+    pub fn descriptions(&self) -> Vec<String> {
+        self.undo.iter().rev().map(|e| e.description.clone()).collect()
+    }
+}
+
+impl Default for History {
+    fn default() -> Self { Self::new() }
+}
+
+fn describe_snapshot_change(before: &Snapshot, after: &Snapshot) -> String {
+    let mut parts = Vec::new();
+    if before.exif != after.exif {
+        parts.push(describe_change(&before.exif.metadata, &after.exif.metadata));
+    }
+    if before.xmp != after.xmp {
+        parts.push(format!("XMPを{}", change_kind(before.xmp.is_some(), after.xmp.is_some())));
+    }
+    if before.iptc != after.iptc {
+        parts.push(format!("IPTCを{}", change_kind(before.iptc.is_some(), after.iptc.is_some())));
+    }
+    parts.join("、")
+}
+
+fn change_kind(before: bool, after: bool) -> &'static str {
+    match (before, after) {
+        (false, true) => "追加",
+        (true, false) => "削除",
+        _ => "変更",
+    }
+}
+
+// IFD1（サムネイル）のタグは"XResolution（IFD1）"のように本体のものと区別する
+fn tag_map(metadata: &Metadata) -> BTreeMap<String, String> {
+    tags_by_key(metadata).into_iter()
+        .map(|(key, tag)| (key.label(), value_string(&tag)))
+        .collect()
+}
+
+pub fn describe_change(before: &Metadata, after: &Metadata) -> String {
+    let before = tag_map(before);
+    let after = tag_map(after);
+
+    let mut changes = Vec::new();
+    for (name, value) in after.iter() {
+        match before.get(name) {
+            None => changes.push((name.clone(), "追加")),
+            Some(old) if old != value => changes.push((name.clone(), "変更")),
+            _ => {}
+        }
+    }
+    for name in before.keys() {
+        if !after.contains_key(name) {
+            changes.push((name.clone(), "削除"));
+        }
+    }
+
+    match changes.as_slice() {
+        [] => "変更なし".to_string(),
+        [(name, kind)] => format!("{}を{}", name, kind),
+        [(_, kind), rest @ ..] => {
+            let kind = if rest.iter().all(|(_, k)| k == kind) { *kind } else { "変更" };
+            format!("{}件のタグを{}", changes.len(), kind)
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::exif::ExifEditData;
+use crate::exif::basic_image::ColorSpace;
+use crate::exif::interop::InteroperabilityIndex;
+use crate::file_type::FileType;
+use crate::jpeg;
+
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+// APP2の最大長から、長さ・識別子・連番・総数を除いた分
+const MAX_CHUNK_LEN: usize = 65535 - 2 - 14;
+const PROFILE_HEADER_LEN: usize = 128;
+
+// 置き換え用のsRGBプロファイル（ICC v2, 約2.5KB）
+pub const SRGB_PROFILE: &[u8] = include_bytes!("icc/srgb.icc");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderingIntent {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+    Unknown(u32),
+}
+
+impl RenderingIntent {
+    fn from_u32(v: u32) -> Self {
+        match v {
+            0 => RenderingIntent::Perceptual,
+            1 => RenderingIntent::RelativeColorimetric,
+            2 => RenderingIntent::Saturation,
+            3 => RenderingIntent::AbsoluteColorimetric,
+            v => RenderingIntent::Unknown(v),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            RenderingIntent::Perceptual => "知覚的".to_string(),
+            RenderingIntent::RelativeColorimetric => "相対的な色域を維持".to_string(),
+            RenderingIntent::Saturation => "彩度".to_string(),
+            RenderingIntent::AbsoluteColorimetric => "絶対的な色域を維持".to_string(),
+            RenderingIntent::Unknown(v) => format!("不明 ({})", v),
+        }
+    }
+}
+
+// 説明文から推定した、よく使われるRGBプロファイルの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileFamily {
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+    ProPhotoRgb,
+    Other,
+}
+
+impl ProfileFamily {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProfileFamily::Srgb => "sRGB",
+            ProfileFamily::DisplayP3 => "Display P3",
+            ProfileFamily::AdobeRgb => "Adobe RGB",
+            ProfileFamily::ProPhotoRgb => "ProPhoto RGB",
+            ProfileFamily::Other => "その他",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IccProfile {
+    pub size: usize,
+    pub version: String,
+    pub device_class: String,
+    pub color_space: String,
+    pub pcs: String,
+    pub rendering_intent: RenderingIntent,
+    pub description: Option<String>,
+}
+
+fn signature(data: &[u8], at: usize) -> String {
+    data.get(at..at + 4).map(|b| String::from_utf8_lossy(b).trim_end().to_string()).unwrap_or_default()
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at.checked_add(4)?).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+impl IccProfile {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < PROFILE_HEADER_LEN + 4 || data.get(36..40) != Some(&b"acsp"[..]) {
+            return Err("ICCプロファイルではありません".to_string());
+        }
+        let size = be_u32(data, 0).unwrap_or(0) as usize;
+        if size > data.len() {
+            return Err("ICCプロファイルが途中で切れています".to_string());
+        }
+        // ヘッダとタグ数より短いサイズは壊れている
+        if size < PROFILE_HEADER_LEN + 4 {
+            return Err("ICCプロファイルのサイズが不正です".to_string());
+        }
+        let data = &data[..size];
+        let (major, minor) = (data.get(8).copied().unwrap_or(0), data.get(9).copied().unwrap_or(0));
+        Ok(Self {
+            size,
+            version: format!("{}.{}.{}", major, minor >> 4, minor & 0x0F),
+            device_class: signature(data, 12),
+            color_space: signature(data, 16),
+            pcs: signature(data, 20),
+            rendering_intent: RenderingIntent::from_u32(be_u32(data, 64).unwrap_or(0)),
+            description: description(data),
+        })
+    }
+
+    pub fn device_class_label(&self) -> String {
+        match self.device_class.as_str() {
+            "mntr" => "ディスプレイ".to_string(),
+            "scnr" => "入力機器".to_string(),
+            "prtr" => "出力機器".to_string(),
+            "spac" => "色空間変換".to_string(),
+            "link" => "デバイスリンク".to_string(),
+            "abst" => "抽象".to_string(),
+            "nmcl" => "名前付きカラー".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn family(&self) -> ProfileFamily {
+        if self.color_space != "RGB" { return ProfileFamily::Other; }
+        let Some(description) = self.description.as_ref().map(|d| d.to_lowercase()) else { return ProfileFamily::Other; };
+        if description.contains("srgb") || description.contains("61966-2") {
+            ProfileFamily::Srgb
+        } else if description.contains("p3") {
+            ProfileFamily::DisplayP3
+        } else if description.contains("adobe rgb") || description.contains("adobergb") || description.contains("compatible with adobe") {
+            ProfileFamily::AdobeRgb
+        } else if description.contains("prophoto") || description.contains("romm") {
+            ProfileFamily::ProPhotoRgb
+        } else {
+            ProfileFamily::Other
+        }
+    }
+}
+
+// descタグ（v2はtextDescriptionType、v4はmultiLocalizedUnicodeType）を読む
+fn description(data: &[u8]) -> Option<String> {
+    // タグ数は信用できないため、データに収まる数までに制限する
+    let count = (be_u32(data, PROFILE_HEADER_LEN)? as usize)
+        .min(data.len().saturating_sub(PROFILE_HEADER_LEN + 4) / 12);
+    let at = (0..count).map(|i| PROFILE_HEADER_LEN + 4 + i * 12)
+        .find(|at| data.get(*at..at + 4) == Some(&b"desc"[..]))?;
+    let (offset, length) = (be_u32(data, at + 4)? as usize, be_u32(data, at + 8)? as usize);
+    let tag = data.get(offset..offset.checked_add(length)?)?;
+    let text = match tag.get(0..4)? {
+        b"desc" => {
+            let len = be_u32(tag, 8)? as usize;
+            let ascii = tag.get(12..12usize.checked_add(len)?)?;
+            String::from_utf8_lossy(ascii).trim_end_matches('\0').to_string()
+        }
+        b"mluc" => {
+            let record_size = be_u32(tag, 12)? as usize;
+            if record_size < 12 { return None; }
+            let records = (be_u32(tag, 8)? as usize).min(tag.len().saturating_sub(16) / record_size);
+            // 英語の記述があればそれを使う
+            let record = (0..records).filter_map(|i| i.checked_mul(record_size)?.checked_add(16))
+                .find(|at| tag.get(*at..at + 2) == Some(&b"en"[..]))
+                .unwrap_or(16);
+            let len = be_u32(tag, record + 4)? as usize;
+            let start = be_u32(tag, record + 8)? as usize;
+            let utf16 = tag.get(start..start.checked_add(len)?)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect::<Vec<u16>>();
+            String::from_utf16_lossy(&utf16).trim_end_matches('\0').to_string()
+        }
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+// ExifのColorSpaceやInteroperabilityIndexと、プロファイルの色空間が食い違っていないか
+pub fn exif_mismatches(eed: &ExifEditData, profile: &IccProfile) -> Vec<String> {
+    let family = profile.family();
+    let mut ret = Vec::new();
+    if family == ProfileFamily::Other { return ret; }
+
+    match eed.basic_image_info.image_format.color_space.as_ref() {
+        Some(ColorSpace::sRGB) if family != ProfileFamily::Srgb => {
+            ret.push(format!("ColorSpaceはsRGBですが、ICCプロファイルは{}です。", family.label()));
+        }
+        Some(ColorSpace::AdobeRGB) if family != ProfileFamily::AdobeRgb => {
+            ret.push(format!("ColorSpaceはAdobeRGBですが、ICCプロファイルは{}です。", family.label()));
+        }
+        _ => {}
+    }
+    match eed.interop_info.interoperability_index.as_ref() {
+        Some(InteroperabilityIndex::R98_DCFBasicFile_sRGB) if family != ProfileFamily::Srgb => {
+            ret.push(format!("InteroperabilityIndexはR98（sRGB）ですが、ICCプロファイルは{}です。", family.label()));
+        }
+        Some(InteroperabilityIndex::R03_DCFOptionFile_AdobeRGB) if family != ProfileFamily::AdobeRgb => {
+            ret.push(format!("InteroperabilityIndexはR03（AdobeRGB）ですが、ICCプロファイルは{}です。", family.label()));
+        }
+        _ => {}
+    }
+    ret
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IccAction {
+    Keep,
+    Strip,
+    ReplaceSrgb,
+}
+
+pub fn is_supported(file_type: FileType) -> bool {
+    file_type == FileType::JPEG
+}
+
+// 複数のAPP2に分割されたプロファイルを連番順につなげる
+pub fn read(file_type: FileType, bytes: &[u8]) -> Option<Result<Vec<u8>, String>> {
+    if !is_supported(file_type) { return None; }
+    let mut chunks = Vec::new();
+    for segment in jpeg::segments(bytes)? {
+        if segment.marker != jpeg::APP2 { continue; }
+        if let Some(payload) = segment.payload(bytes).strip_prefix(ICC_HEADER) {
+            if payload.len() < 2 {
+                return Some(Err("ICCプロファイルのチャンクが壊れています".to_string()));
+            }
+            chunks.push((payload[0], payload[1], &payload[2..]));
+        }
+    }
+    if chunks.is_empty() { return None; }
+
+    let count = chunks[0].1;
+    chunks.sort_by_key(|(seq, _, _)| *seq);
+    let complete = chunks.len() == count as usize
+        && chunks.iter().enumerate().all(|(i, (seq, total, _))| *seq as usize == i + 1 && *total == count);
+    if !complete {
+        return Some(Err("ICCプロファイルのチャンクが欠けているか、重複しています".to_string()));
+    }
+    Some(Ok(chunks.iter().flat_map(|(_, _, data)| data.iter().copied()).collect()))
+}
+
+// profileがNoneならICCプロファイルを削除する
+pub fn write(file_type: FileType, bytes: &[u8], profile: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    if !is_supported(file_type) {
+        return match profile {
+            Some(_) => Err(format!("{}のICCプロファイルには対応していません", file_type.label())),
+            None => Ok(bytes.to_vec()),
+        };
+    }
+    let mut segments = Vec::new();
+    if let Some(profile) = profile {
+        let chunks = profile.chunks(MAX_CHUNK_LEN).collect::<Vec<&[u8]>>();
+        let count = u8::try_from(chunks.len()).map_err(|_| "ICCプロファイルが大きすぎます".to_string())?;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let payload = [ICC_HEADER, &[i as u8 + 1, count][..], *chunk].concat();
+            segments.push(jpeg::segment(jpeg::APP2, &payload).ok_or_else(|| "ICCプロファイルが大きすぎます".to_string())?);
+        }
+    }
+    // 元のAPP2の位置、なければ先頭のAPPnの並びの直後に置く
+    jpeg::replace_segments(bytes, |marker, payload| marker == jpeg::APP2 && payload.starts_with(ICC_HEADER), &segments)
+}
+
+pub fn write_changes(file_type: FileType, bytes: Vec<u8>, action: IccAction) -> Result<Vec<u8>, String> {
+    match action {
+        IccAction::Keep => Ok(bytes),
+        IccAction::Strip => write(file_type, &bytes, None),
+        IccAction::ReplaceSrgb => write(file_type, &bytes, Some(SRGB_PROFILE)),
+    }
+}
@@ -0,0 +1,312 @@
+// IPTC-IIM（JPEGのAPP13 Photoshop IRBの中のリソース0x0404）の読み書き。
+// 書き出すときは常にUTF-8（1:90にESC % G）で書く
+
+use encoding::all::WINDOWS_1252;
+use encoding::{DecoderTrap, Encoding};
+
+use crate::file_type::FileType;
+use crate::jpeg;
+
+const PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+// APP13の最大長から、長さと識別子を除いた分
+const MAX_CHUNK_LEN: usize = 65535 - 2 - 14;
+const IRB_SIGNATURE: &[u8] = b"8BIM";
+const IPTC_RESOURCE: u16 = 0x0404;
+// IPTCのMD5ダイジェスト。IPTCを書き換えると合わなくなるため削除する
+const IPTC_DIGEST_RESOURCE: u16 = 0x0425;
+
+const TAG_MARKER: u8 = 0x1C;
+const ENVELOPE_RECORD: u8 = 1;
+pub const APPLICATION_RECORD: u8 = 2;
+const CODED_CHARACTER_SET: u8 = 90;
+const RECORD_VERSION: u8 = 0;
+const UTF8_ESCAPE: &[u8] = b"\x1B%G";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IptcField {
+    pub dataset: u8,
+    pub name: &'static str,
+    pub label: &'static str,
+    pub repeatable: bool,
+    // IIMで決められた最大のバイト数
+    pub max_len: usize,
+}
+
+const fn field(dataset: u8, name: &'static str, label: &'static str, repeatable: bool, max_len: usize) -> IptcField {
+    IptcField { dataset, name, label, repeatable, max_len }
+}
+
+// キーワードは画面上で別に扱う
+pub const KEYWORDS: IptcField = field(25, "Keywords", "キーワード", true, 64);
+
+// IPTC Coreに対応するアプリケーションレコード（2:xx）
+pub const FIELDS: &[IptcField] = &[
+    field(5, "ObjectName", "タイトル", false, 64),
+    field(105, "Headline", "見出し", false, 256),
+    field(120, "Caption-Abstract", "説明", false, 2000),
+    field(122, "Writer-Editor", "説明の作成者", false, 32),
+    KEYWORDS,
+    field(80, "By-line", "作者", true, 32),
+    field(85, "By-lineTitle", "作者の肩書き", true, 32),
+    field(110, "Credit", "クレジット", false, 32),
+    field(115, "Source", "提供元", false, 32),
+    field(116, "CopyrightNotice", "著作権表示", false, 128),
+    field(40, "SpecialInstructions", "使用上の注意", false, 256),
+    field(55, "DateCreated", "作成日（YYYYMMDD）", false, 8),
+    field(60, "TimeCreated", "作成時刻（HHMMSS±HHMM）", false, 11),
+    field(92, "Sub-location", "地区", false, 32),
+    field(90, "City", "都市", false, 32),
+    field(95, "Province-State", "都道府県・州", false, 32),
+    field(101, "Country-PrimaryLocationName", "国", false, 64),
+    field(100, "Country-PrimaryLocationCode", "国コード", false, 3),
+    field(103, "OriginalTransmissionReference", "ジョブID", false, 32),
+];
+
+pub fn find_field(name: &str) -> Option<IptcField> {
+    FIELDS.iter().find(|f| f.name == name).copied()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IptcDataset {
+    pub record: u8,
+    pub dataset: u8,
+    pub data: Vec<u8>,
+}
+
+impl IptcDataset {
+    // FIELDSにあるものは項目名、それ以外は"2:62"のような番号
+    pub fn name(&self) -> String {
+        match FIELDS.iter().find(|f| self.record == APPLICATION_RECORD && f.dataset == self.dataset) {
+            Some(field) => field.name.to_string(),
+            None => format!("{}:{}", self.record, self.dataset),
+        }
+    }
+
+    // バイナリのデータセット以外は、読み込み時にUTF-8へ変換しておく
+    fn is_text(&self) -> bool {
+        self.record == APPLICATION_RECORD && self.dataset != RECORD_VERSION
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IptcData {
+    pub datasets: Vec<IptcDataset>,
+}
+
+impl IptcData {
+    pub fn parse(iim: &[u8]) -> Result<Self, String> {
+        let mut raw = Vec::new();
+        let mut pos = 0;
+        while pos < iim.len() {
+            // 末尾の埋め草は無視する
+            if iim[pos] != TAG_MARKER {
+                if iim[pos..].iter().all(|b| *b == 0) { break; }
+                return Err(format!("IPTCのデータセットが壊れています（{}バイト目）", pos));
+            }
+            if pos + 5 > iim.len() {
+                return Err("IPTCのデータセットが途中で終わっています".to_string());
+            }
+            let (record, dataset) = (iim[pos + 1], iim[pos + 2]);
+            let mut length = u16::from_be_bytes([iim[pos + 3], iim[pos + 4]]) as usize;
+            pos += 5;
+            // 拡張データセット: 下位15ビットが長さを表すバイト数
+            if length & 0x8000 != 0 {
+                let n = length & 0x7FFF;
+                if n > 4 || pos + n > iim.len() {
+                    return Err("IPTCの拡張データセットに対応していません".to_string());
+                }
+                length = iim[pos..pos + n].iter().fold(0, |acc, b| (acc << 8) | *b as usize);
+                pos += n;
+            }
+            if pos + length > iim.len() {
+                return Err("IPTCのデータセットが途中で終わっています".to_string());
+            }
+            raw.push(IptcDataset { record, dataset, data: iim[pos..pos + length].to_vec() });
+            pos += length;
+        }
+
+        let utf8 = raw.iter().any(|d| d.record == ENVELOPE_RECORD && d.dataset == CODED_CHARACTER_SET && d.data == UTF8_ESCAPE);
+        let datasets = raw.into_iter()
+            .filter(|d| !(d.record == ENVELOPE_RECORD && d.dataset == CODED_CHARACTER_SET))
+            .map(|mut d| {
+                if d.is_text() {
+                    d.data = decode(&d.data, utf8).into_bytes();
+                }
+                d
+            })
+            .collect();
+        Ok(Self { datasets })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut datasets = vec![IptcDataset { record: ENVELOPE_RECORD, dataset: CODED_CHARACTER_SET, data: UTF8_ESCAPE.to_vec() }];
+        datasets.extend(self.datasets.iter().filter(|d| d.record == ENVELOPE_RECORD).cloned());
+        // アプリケーションレコードはRecordVersionから始める
+        let has_application = self.datasets.iter().any(|d| d.record == APPLICATION_RECORD && d.dataset != RECORD_VERSION);
+        if has_application {
+            let version = self.datasets.iter()
+                .find(|d| d.record == APPLICATION_RECORD && d.dataset == RECORD_VERSION)
+                .cloned()
+                .unwrap_or(IptcDataset { record: APPLICATION_RECORD, dataset: RECORD_VERSION, data: vec![0, 4] });
+            datasets.push(version);
+            datasets.extend(self.datasets.iter().filter(|d| d.is_text()).cloned());
+        }
+        datasets.extend(self.datasets.iter().filter(|d| d.record > APPLICATION_RECORD).cloned());
+
+        let mut out = Vec::new();
+        for d in datasets.iter() {
+            out.extend_from_slice(&[TAG_MARKER, d.record, d.dataset]);
+            if d.data.len() < 0x8000 {
+                out.extend_from_slice(&(d.data.len() as u16).to_be_bytes());
+            } else {
+                out.extend_from_slice(&0x8004u16.to_be_bytes());
+                out.extend_from_slice(&(d.data.len() as u32).to_be_bytes());
+            }
+            out.extend_from_slice(&d.data);
+        }
+        out
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.datasets.iter().any(|d| d.is_text())
+    }
+
+    pub fn get(&self, field: &IptcField) -> Vec<String> {
+        self.datasets.iter()
+            .filter(|d| d.record == APPLICATION_RECORD && d.dataset == field.dataset)
+            .map(|d| String::from_utf8_lossy(&d.data).to_string())
+            .collect()
+    }
+
+    // 空の値は削除として扱う。既存の位置を保ったまま置き換える
+    pub fn set(&mut self, field: &IptcField, values: &[String]) -> Result<(), String> {
+        let values = values.iter().map(|v| v.trim()).filter(|v| !v.is_empty()).collect::<Vec<&str>>();
+        if values.len() > 1 && !field.repeatable {
+            return Err(format!("{}は1つしか設定できません", field.name));
+        }
+        if let Some(v) = values.iter().find(|v| v.len() > field.max_len) {
+            return Err(format!("{}は{}バイトまでです: {}", field.name, field.max_len, v));
+        }
+        let is_target = |d: &IptcDataset| d.record == APPLICATION_RECORD && d.dataset == field.dataset;
+        let at = self.datasets.iter().position(is_target).unwrap_or(self.datasets.len());
+        self.datasets.retain(|d| !is_target(d));
+        let new = values.iter().map(|v| IptcDataset { record: APPLICATION_RECORD, dataset: field.dataset, data: v.as_bytes().to_vec() });
+        let at = at.min(self.datasets.len());
+        self.datasets.splice(at..at, new);
+        Ok(())
+    }
+}
+
+// 文字コードの指定がなければ、UTF-8として読めるか試してからWindows-1252とみなす
+fn decode(data: &[u8], utf8: bool) -> String {
+    if utf8 {
+        return String::from_utf8_lossy(data).to_string();
+    }
+    match std::str::from_utf8(data) {
+        Ok(s) => s.to_string(),
+        Err(_) => WINDOWS_1252.decode(data, DecoderTrap::Replace).unwrap_or_else(|_| String::from_utf8_lossy(data).to_string()),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Resource {
+    id: u16,
+    // 長さのバイトと偶数長にする埋め草を含むパスカル文字列
+    name: Vec<u8>,
+    data: Vec<u8>,
+}
+
+fn parse_resources(irb: &[u8]) -> Option<Vec<Resource>> {
+    let mut ret = Vec::new();
+    let mut pos = 0;
+    while pos + 4 <= irb.len() && &irb[pos..pos + 4] == IRB_SIGNATURE {
+        let id = u16::from_be_bytes([*irb.get(pos + 4)?, *irb.get(pos + 5)?]);
+        pos += 6;
+        let name_len = *irb.get(pos)? as usize + 1;
+        let name_len = name_len + (name_len & 1);
+        let name = irb.get(pos..pos + name_len)?.to_vec();
+        pos += name_len;
+        let size = u32::from_be_bytes(irb.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let data = irb.get(pos..pos + size)?.to_vec();
+        pos += size + (size & 1);
+        ret.push(Resource { id, name, data });
+    }
+    Some(ret)
+}
+
+fn write_resources(resources: &[Resource]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for r in resources.iter() {
+        out.extend_from_slice(IRB_SIGNATURE);
+        out.extend_from_slice(&r.id.to_be_bytes());
+        out.extend_from_slice(&r.name);
+        out.extend_from_slice(&(r.data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&r.data);
+        if r.data.len() & 1 == 1 {
+            out.push(0);
+        }
+    }
+    out
+}
+
+pub fn is_supported(file_type: FileType) -> bool {
+    file_type == FileType::JPEG
+}
+
+// 長いIRBは複数のAPP13に分かれていることがあるため、つなげて読む
+fn photoshop_irb(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut irb = Vec::new();
+    let mut found = false;
+    for segment in jpeg::segments(bytes)? {
+        if segment.marker != jpeg::APP13 { continue; }
+        if let Some(payload) = segment.payload(bytes).strip_prefix(PHOTOSHOP_HEADER) {
+            irb.extend_from_slice(payload);
+            found = true;
+        }
+    }
+    found.then_some(irb)
+}
+
+pub fn read(file_type: FileType, bytes: &[u8]) -> Option<Result<IptcData, String>> {
+    if !is_supported(file_type) { return None; }
+    let resources = parse_resources(&photoshop_irb(bytes)?)?;
+    let iim = resources.iter().find(|r| r.id == IPTC_RESOURCE)?;
+    Some(IptcData::parse(&iim.data))
+}
+
+// iptcがNoneならIPTCのリソースだけを削除し、他のリソースが残らなければAPP13ごと削除する
+pub fn write(file_type: FileType, bytes: &[u8], iptc: Option<&IptcData>) -> Result<Vec<u8>, String> {
+    if !is_supported(file_type) {
+        return match iptc {
+            Some(_) => Err(format!("{}のIPTCには対応していません", file_type.label())),
+            None => Ok(bytes.to_vec()),
+        };
+    }
+    let mut resources = photoshop_irb(bytes)
+        .map(|irb| parse_resources(&irb).ok_or_else(|| "Photoshopのリソースを読み込めません".to_string()))
+        .transpose()?
+        .unwrap_or_default();
+    let at = resources.iter().position(|r| r.id == IPTC_RESOURCE).unwrap_or(resources.len());
+    resources.retain(|r| r.id != IPTC_RESOURCE && r.id != IPTC_DIGEST_RESOURCE);
+    if let Some(iptc) = iptc.filter(|iptc| !iptc.is_empty()) {
+        let at = at.min(resources.len());
+        resources.insert(at, Resource { id: IPTC_RESOURCE, name: vec![0, 0], data: iptc.to_bytes() });
+    }
+
+    // 1つのAPP13に収まらないIRBは、続きを次のAPP13に分けて書く
+    let segments = write_resources(&resources).chunks(MAX_CHUNK_LEN)
+        .map(|chunk| jpeg::segment(jpeg::APP13, &[PHOTOSHOP_HEADER, chunk].concat()))
+        .collect::<Option<Vec<Vec<u8>>>>()
+        .ok_or_else(|| "IPTCを書き出せません".to_string())?;
+    // 元のAPP13の位置、なければ先頭のAPPnの並びの直後に置く
+    jpeg::replace_segments(bytes, |marker, payload| marker == jpeg::APP13 && payload.starts_with(PHOTOSHOP_HEADER), &segments)
+}
+
+// 読み込み時から変わっていなければ元のAPP13をそのまま残す
+pub fn write_changes(file_type: FileType, bytes: Vec<u8>, iptc: Option<&IptcData>, original: Option<&IptcData>) -> Result<Vec<u8>, String> {
+    if iptc == original {
+        return Ok(bytes);
+    }
+    write(file_type, &bytes, iptc)
+}
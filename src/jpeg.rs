@@ -0,0 +1,407 @@
+use little_exif::filetype::FileExtension;
+use little_exif::metadata::Metadata;
+
+pub const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+pub const SOS: u8 = 0xDA;
+pub const APP0: u8 = 0xE0;
+pub const APP1: u8 = 0xE1;
+pub const APP2: u8 = 0xE2;
+pub const APP13: u8 = 0xED;
+const APP15: u8 = 0xEF;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub marker: u8,
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl Segment {
+    pub fn payload<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let start = self.offset + 4;
+        let end = self.offset + self.length;
+        if start > end { &data[end..end] } else { &data[start..end] }
+    }
+}
+
+// SOSまでのマーカーセグメントを列挙する（エントロピー符号化データは読まない）
+pub fn segments(data: &[u8]) -> Option<Vec<Segment>> {
+    if data.len() < 2 || data[0] != 0xFF || data[1] != SOI { return None; }
+    let mut ret = Vec::new();
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF { return None; }
+        let marker = data[pos + 1];
+        if marker == 0xFF { pos += 1; continue; }
+        if marker == EOI { break; }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize + 2;
+        if pos + length > data.len() { return None; }
+        ret.push(Segment { marker, offset: pos, length });
+        if marker == SOS { break; }
+        pos += length;
+    }
+    Some(ret)
+}
+
+// APP1 Exifセグメントから"Exif\0\0"以降のTIFFデータを取り出す
+pub fn exif_tiff(data: &[u8]) -> Option<&[u8]> {
+    for segment in segments(data)? {
+        if segment.marker != APP1 { continue; }
+        let payload = segment.payload(data);
+        if payload.starts_with(EXIF_HEADER) {
+            return Some(&payload[EXIF_HEADER.len()..]);
+        }
+    }
+    None
+}
+
+// TIFFデータをAPP1 Exifセグメントだけを持つ最小のJPEGに包む
+pub fn wrap_exif_tiff(tiff: &[u8]) -> Option<Vec<u8>> {
+    let length = u16::try_from(2 + EXIF_HEADER.len() + tiff.len()).ok()?;
+    let mut ret = Vec::with_capacity(tiff.len() + 16);
+    ret.extend_from_slice(&[0xFF, SOI, 0xFF, APP1]);
+    ret.extend_from_slice(&length.to_be_bytes());
+    ret.extend_from_slice(EXIF_HEADER);
+    ret.extend_from_slice(tiff);
+    ret.extend_from_slice(&[0xFF, EOI]);
+    Some(ret)
+}
+
+// JPEG以外のコンテナから取り出したTIFFデータをMetadataとして読み込む
+pub fn metadata_from_tiff(tiff: &[u8]) -> Option<Metadata> {
+    let jpeg = wrap_exif_tiff(tiff)?;
+    Metadata::new_from_vec(&jpeg, FileExtension::JPEG).ok()
+}
+
+// Metadataを空のJPEGに書き込み、APP1からTIFFデータだけを取り出す
+pub fn metadata_to_tiff(metadata: &Metadata) -> Option<Vec<u8>> {
+    let mut jpeg = vec![0xFF, SOI, 0xFF, EOI];
+    metadata.write_to_vec(&mut jpeg, FileExtension::JPEG).ok()?;
+    exif_tiff(&jpeg).map(|tiff| tiff.to_vec())
+}
+
+// SOFセグメントから画像の幅と高さを読む
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    for segment in segments(data)? {
+        // 0xC4（DHT）、0xC8（JPG）、0xCC（DAC）はSOFではない
+        if !(0xC0..=0xCF).contains(&segment.marker) || matches!(segment.marker, 0xC4 | 0xC8 | 0xCC) { continue; }
+        let payload = segment.payload(data);
+        if payload.len() < 5 { return None; }
+        let height = u16::from_be_bytes([payload[1], payload[2]]) as u32;
+        let width = u16::from_be_bytes([payload[3], payload[4]]) as u32;
+        return Some((width, height));
+    }
+    None
+}
+
+// APP1 Exifセグメントを取り除く（ブラウザにOrientationで回転させずに描画するため）
+pub fn strip_exif(data: &[u8]) -> Option<Vec<u8>> {
+    let mut ret = Vec::with_capacity(data.len());
+    ret.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+    for segment in segments(data)? {
+        if segment.marker == APP1 && segment.payload(data).starts_with(EXIF_HEADER) {
+            ret.extend_from_slice(&data[pos..segment.offset]);
+            pos = segment.offset + segment.length;
+        }
+    }
+    ret.extend_from_slice(&data[pos..]);
+    Some(ret)
+}
+
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+const TEM: u8 = 0x01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SegmentKind {
+    Jfif,
+    Exif,
+    Xmp,
+    ExtendedXmp,
+    Icc,
+    Mpf,
+    Jumbf,
+    Photoshop,
+    Adobe,
+    OtherApp(u8),
+    Comment,
+    // DQT, DHT, DRIなど、復号に必要なテーブル
+    Table,
+    Frame,
+    Scan,
+    EntropyData,
+    // SOI, EOI, RSTn
+    Marker,
+    // EOIより後ろのデータ
+    Trailer,
+    // セグメントの間にある、どのマーカーにも属さないデータ
+    Stray,
+}
+
+impl SegmentKind {
+    fn classify(marker: u8, payload: &[u8]) -> Self {
+        let starts = |prefix: &[u8]| payload.starts_with(prefix);
+        match marker {
+            0xE0 if starts(b"JFIF\0") || starts(b"JFXX\0") => SegmentKind::Jfif,
+            0xE1 if starts(EXIF_HEADER) => SegmentKind::Exif,
+            0xE1 if starts(b"http://ns.adobe.com/xap/1.0/\0") => SegmentKind::Xmp,
+            0xE1 if starts(b"http://ns.adobe.com/xmp/extension/\0") => SegmentKind::ExtendedXmp,
+            0xE2 if starts(b"ICC_PROFILE\0") => SegmentKind::Icc,
+            0xE2 if starts(b"MPF\0") => SegmentKind::Mpf,
+            0xEB if starts(b"JP") => SegmentKind::Jumbf,
+            0xED if starts(b"Photoshop 3.0\0") => SegmentKind::Photoshop,
+            0xEE if starts(b"Adobe") => SegmentKind::Adobe,
+            0xE0..=0xEF => SegmentKind::OtherApp(marker - 0xE0),
+            0xFE => SegmentKind::Comment,
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => SegmentKind::Frame,
+            SOS => SegmentKind::Scan,
+            SOI | EOI | TEM | RST0..=RST7 => SegmentKind::Marker,
+            _ => SegmentKind::Table,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SegmentKind::Jfif => "JFIF".to_string(),
+            SegmentKind::Exif => "Exif".to_string(),
+            SegmentKind::Xmp => "XMP".to_string(),
+            SegmentKind::ExtendedXmp => "拡張XMP".to_string(),
+            SegmentKind::Icc => "ICCプロファイル".to_string(),
+            SegmentKind::Mpf => "MPF（複数画像）".to_string(),
+            SegmentKind::Jumbf => "JUMBF（C2PAなど）".to_string(),
+            SegmentKind::Photoshop => "Photoshop IRB（IPTCなど）".to_string(),
+            SegmentKind::Adobe => "Adobe".to_string(),
+            SegmentKind::OtherApp(n) => format!("APP{}", n),
+            SegmentKind::Comment => "コメント".to_string(),
+            SegmentKind::Table => "テーブル".to_string(),
+            SegmentKind::Frame => "フレームヘッダ".to_string(),
+            SegmentKind::Scan => "スキャンヘッダ".to_string(),
+            SegmentKind::EntropyData => "画像データ".to_string(),
+            SegmentKind::Marker => "マーカー".to_string(),
+            SegmentKind::Trailer => "EOI以降のデータ".to_string(),
+            SegmentKind::Stray => "不明なデータ".to_string(),
+        }
+    }
+
+    // 画像の復号に関わらないものだけ削除できる。Exif, XMP, IPTCはそれぞれのタブで編集する
+    pub fn is_removable(&self) -> bool {
+        matches!(
+            self,
+            SegmentKind::Jfif | SegmentKind::Icc | SegmentKind::Mpf | SegmentKind::Jumbf
+            | SegmentKind::Adobe | SegmentKind::OtherApp(_) | SegmentKind::Comment | SegmentKind::Trailer
+            | SegmentKind::Stray
+        )
+    }
+
+    pub fn caution(&self) -> Option<&'static str> {
+        match self {
+            SegmentKind::Icc => Some("削除すると色が変わって表示されることがあります。"),
+            SegmentKind::Adobe => Some("CMYKの画像では、削除すると色が正しく表示されなくなることがあります。"),
+            SegmentKind::Mpf => Some("EOI以降の画像（深度マップや3D用の画像など）の索引です。"),
+            SegmentKind::Trailer => Some("MPFの2枚目以降の画像や、他のソフトが追記したデータが含まれることがあります。"),
+            SegmentKind::Stray => Some("セグメントの間に紛れ込んだデータです。多くのソフトは読み飛ばします。"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentInfo {
+    // 画像データとEOI以降のデータはNone
+    pub marker: Option<u8>,
+    pub offset: usize,
+    pub length: usize,
+    pub kind: SegmentKind,
+    pub detail: String,
+}
+
+impl SegmentInfo {
+    fn new(data: &[u8], marker: u8, offset: usize, length: usize) -> Self {
+        let payload = Segment { marker, offset, length }.payload(data);
+        Self { marker: Some(marker), offset, length, kind: SegmentKind::classify(marker, payload), detail: detail(marker, payload) }
+    }
+
+    fn raw(offset: usize, length: usize, kind: SegmentKind) -> Self {
+        Self { marker: None, offset, length, kind, detail: String::new() }
+    }
+
+    pub fn marker_name(&self) -> String {
+        match self.marker {
+            Some(marker) => marker_name(marker),
+            None => "-".to_string(),
+        }
+    }
+}
+
+pub fn marker_name(marker: u8) -> String {
+    match marker {
+        SOI => "SOI".to_string(),
+        EOI => "EOI".to_string(),
+        SOS => "SOS".to_string(),
+        0xC4 => "DHT".to_string(),
+        0xC8 => "JPG".to_string(),
+        0xCC => "DAC".to_string(),
+        0xC0..=0xCF => format!("SOF{}", marker - 0xC0),
+        0xDB => "DQT".to_string(),
+        0xDD => "DRI".to_string(),
+        0xE0..=0xEF => format!("APP{}", marker - 0xE0),
+        0xFE => "COM".to_string(),
+        RST0..=RST7 => format!("RST{}", marker - RST0),
+        _ => format!("FF{:02X}", marker),
+    }
+}
+
+// 中身から読み取れる補足（コメントの本文、フレームの大きさなど）
+fn detail(marker: u8, payload: &[u8]) -> String {
+    match marker {
+        0xFE => String::from_utf8_lossy(payload).chars().take(80).collect(),
+        0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) && payload.len() >= 6 => {
+            let height = u16::from_be_bytes([payload[1], payload[2]]);
+            let width = u16::from_be_bytes([payload[3], payload[4]]);
+            format!("{}×{}, {}成分", width, height, payload[5])
+        }
+        0xE0..=0xEF => {
+            // 識別子（NUL終端の文字列）
+            let id = payload.iter().take(40).take_while(|b| **b != 0).copied().collect::<Vec<u8>>();
+            if !id.is_empty() && id.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+                String::from_utf8_lossy(&id).to_string()
+            } else {
+                String::new()
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+// SOIからEOIまでの全マーカー、スキャンごとの画像データ、EOI以降のデータを列挙する
+pub fn inspect(data: &[u8]) -> Option<Vec<SegmentInfo>> {
+    if data.len() < 2 || data[0] != 0xFF || data[1] != SOI { return None; }
+    let mut ret = vec![SegmentInfo::new(data, SOI, 0, 2)];
+    let mut pos = 2;
+    while pos + 2 <= data.len() {
+        // マーカーの間のゴミは、次の0xFFまでを1つにまとめて読み飛ばす
+        if data[pos] != 0xFF {
+            let end = data[pos..].iter().position(|b| *b == 0xFF).map_or(data.len(), |i| pos + i);
+            ret.push(SegmentInfo::raw(pos, end - pos, SegmentKind::Stray));
+            pos = end;
+            continue;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xFF { pos += 1; continue; }
+        if marker == EOI || marker == TEM || (RST0..=RST7).contains(&marker) {
+            ret.push(SegmentInfo::new(data, marker, pos, 2));
+            pos += 2;
+            if marker == EOI { break; }
+            continue;
+        }
+        if pos + 4 > data.len() { return None; }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize + 2;
+        if pos + length > data.len() { return None; }
+        ret.push(SegmentInfo::new(data, marker, pos, length));
+        pos += length;
+
+        if marker == SOS {
+            // 0xFF00（バイトスタッフィング）とRSTnは画像データの一部として扱う
+            let start = pos;
+            while pos + 1 < data.len() {
+                let next = data[pos + 1];
+                if data[pos] == 0xFF && next != 0x00 && !(RST0..=RST7).contains(&next) { break; }
+                pos += 1;
+            }
+            if pos + 1 >= data.len() { pos = data.len(); }
+            ret.push(SegmentInfo::raw(start, pos - start, SegmentKind::EntropyData));
+        }
+    }
+    if pos < data.len() {
+        ret.push(SegmentInfo::raw(pos, data.len() - pos, SegmentKind::Trailer));
+    }
+    Some(ret)
+}
+
+// MPFの索引は、MPFセグメント内の位置を起点にEOI以降の画像を指す。
+// MPFを残したままそれより後ろのセグメントを削除すると位置がずれるため、そのようなoffsetsを返す
+pub fn mpf_conflicts(segments: &[SegmentInfo], offsets: &[usize]) -> Vec<usize> {
+    let Some(mpf) = segments.iter().find(|s| s.kind == SegmentKind::Mpf && !offsets.contains(&s.offset)) else {
+        return Vec::new();
+    };
+    let mut ret = offsets.iter().copied().filter(|offset| *offset > mpf.offset).collect::<Vec<usize>>();
+    ret.sort();
+    ret.dedup();
+    ret
+}
+
+// offsetsの位置にあるセグメントを取り除く。画像データには触れない
+pub fn strip_segments(data: &[u8], offsets: &[usize]) -> Result<Vec<u8>, String> {
+    let segments = inspect(data).ok_or_else(|| "JPEGとして読み込めません".to_string())?;
+    if !mpf_conflicts(&segments, offsets).is_empty() {
+        return Err("MPFより後ろのセグメントを削除すると、MPFが指す画像の位置がずれます。MPFも合わせて削除してください".to_string());
+    }
+    let mut targets = Vec::new();
+    for offset in offsets.iter() {
+        let segment = segments.iter().find(|s| s.offset == *offset)
+            .ok_or_else(|| format!("0x{:X}にセグメントがありません", offset))?;
+        if !segment.kind.is_removable() {
+            return Err(format!("{}（{}）は削除できません", segment.marker_name(), segment.kind.label()));
+        }
+        targets.push(segment);
+    }
+    targets.sort_by_key(|s| s.offset);
+    targets.dedup_by_key(|s| s.offset);
+
+    let mut ret = Vec::with_capacity(data.len());
+    let mut pos = 0;
+    for segment in targets.iter() {
+        ret.extend_from_slice(&data[pos..segment.offset]);
+        pos = segment.offset + segment.length;
+    }
+    ret.extend_from_slice(&data[pos..]);
+    Ok(ret)
+}
+
+// マーカーとペイロードからセグメントを組み立てる。長さが2バイトに収まらなければNone
+pub fn segment(marker: u8, payload: &[u8]) -> Option<Vec<u8>> {
+    let length = u16::try_from(2 + payload.len()).ok()?;
+    let mut ret = Vec::with_capacity(payload.len() + 4);
+    ret.extend_from_slice(&[0xFF, marker]);
+    ret.extend_from_slice(&length.to_be_bytes());
+    ret.extend_from_slice(payload);
+    Some(ret)
+}
+
+// removeに当てはまるセグメント（マーカーとペイロードで判定する）を取り除き、new_segmentsを
+// 最初に取り除いた位置、なければ先頭のAPPnの並びの直後に置く。画像データには触れない
+pub fn replace_segments(data: &[u8], remove: impl Fn(u8, &[u8]) -> bool, new_segments: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    let segments = segments(data)
+        // 画像データ（SOS）まで読めなければ途中で切れている
+        .filter(|segments| segments.last().is_some_and(|segment| segment.marker == SOS))
+        .ok_or_else(|| "JPEGとして読み込めません".to_string())?;
+    let mut ret = Vec::with_capacity(data.len());
+    ret.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+    let mut insert_at = None;
+    let mut after_app = 2;
+    for segment in segments.iter() {
+        if remove(segment.marker, segment.payload(data)) {
+            ret.extend_from_slice(&data[pos..segment.offset]);
+            pos = segment.offset + segment.length;
+            insert_at.get_or_insert(ret.len());
+            continue;
+        }
+        let out_offset = ret.len() + segment.offset - pos;
+        if (APP0..=APP15).contains(&segment.marker) && after_app == out_offset {
+            after_app = out_offset + segment.length;
+        }
+    }
+    ret.extend_from_slice(&data[pos..]);
+
+    let tail = ret.split_off(insert_at.unwrap_or(after_app));
+    for segment in new_segments.iter() {
+        ret.extend_from_slice(segment);
+    }
+    ret.extend_from_slice(&tail);
+    Ok(ret)
+}
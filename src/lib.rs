@@ -1,3 +1,14 @@
+pub mod batch;
+#[cfg(feature = "ui")]
 pub mod components;
 pub mod exif;
-pub mod exif_heic;
\ No newline at end of file
+pub mod exif_heic;
+pub mod file_type;
+pub mod geo;
+pub mod heif;
+pub mod history;
+pub mod icc;
+pub mod iptc;
+pub mod jpeg;
+pub mod xmp;
+pub mod zip;
@@ -1,24 +1,43 @@
 use base64::Engine;
 use chrono::Local;
-use little_exif::filetype::FileExtension;
+use gloo::events::EventListener;
 use little_exif::metadata::Metadata;
 
-use serde_wasm_bindgen::from_value;
 use wasm_bindgen::prelude::*;
-use web_sys::js_sys::Uint8Array;
-use web_sys::{js_sys, HtmlInputElement, Url};
+use web_sys::{js_sys, HtmlInputElement, HtmlTextAreaElement};
 use yew::prelude::*;
 
 use exif_edit::components::basic_image::BasicImageInfo;
+use exif_edit::components::batch::BatchMode;
+use exif_edit::components::changes::Changes;
+use exif_edit::components::clock::ClockShiftForm;
+use exif_edit::components::copy::CopyMetadata;
 use exif_edit::components::exif_capture::ExifCaptureInfo;
+use exif_edit::components::fuzz::LocationFuzzForm;
+use exif_edit::components::geotag::GeotagForm;
 use exif_edit::components::gps::GPSInfo;
+use exif_edit::components::import::ImportMetadata;
 use exif_edit::components::interop::InteropInfo;
+use exif_edit::components::iptc::IptcInfo;
+use exif_edit::components::preset::ScrubPresets;
+use exif_edit::components::segments::SegmentInspector;
 use exif_edit::components::thumbnail::ThumbnailInfo;
 use exif_edit::components::user::UserInfo;
 use exif_edit::components::uneditable::Uneditable;
 use exif_edit::components::tabs::TabItem;
+use exif_edit::components::utils::download_bytes;
+use exif_edit::components::xmp::XmpInfo;
 use exif_edit::exif::ExifEditData;
-use exif_edit::exif_heic::metadata_heic;
+use exif_edit::exif::clock::{ClockShift, GPS_OFFSET_MISSING};
+use exif_edit::exif::export::ExportFormat;
+use exif_edit::file_type::FileType;
+use exif_edit::geo::fuzz::LocationFuzz;
+use exif_edit::geo::geotag::Geotag;
+use exif_edit::geo::waypoint::WaypointFormat;
+use exif_edit::history::{History, Snapshot};
+use exif_edit::icc::{self, IccAction};
+use exif_edit::iptc::{self, IptcData};
+use exif_edit::xmp::{container as xmp_container, XmpPacket};
 
 const MAX_FILE_SIZE: usize = 1_073_741_824;
 
@@ -29,9 +48,9 @@ extern "C" {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-enum FileType {
-    JPEG,
-    HEIC,
+enum AppMode {
+    Single,
+    Batch,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -43,6 +62,11 @@ enum Tabs {
     ThumbnailInfo,
     UserInfo,
     Uneditable,
+    Changes,
+    Copy,
+    Xmp,
+    Iptc,
+    Segments,
 }
 
 #[function_component(App)]
@@ -50,11 +74,22 @@ fn app() -> Html {
     let file_input = use_node_ref();
     let file_name = use_state(|| None);
     let file_size = use_state(|| None);
+    let file_type = use_state(|| FileType::JPEG);
     let file_bytes = use_state(|| None);
+    let preview_bytes = use_state(|| None);
     let img_data_url = use_state(|| None);
     let exif = use_state(|| None);
+    let original_exif = use_state(|| None);
+    let xmp = use_state(|| None::<XmpPacket>);
+    let original_xmp = use_state(|| None::<XmpPacket>);
+    let xmp_error = use_state(|| None::<String>);
+    let iptc = use_state(|| None::<IptcData>);
+    let original_iptc = use_state(|| None::<IptcData>);
+    let iptc_error = use_state(|| None::<String>);
+    let icc_action = use_state(|| IccAction::Keep);
 
     let is_converting = use_state(|| false);
+    let app_mode = use_state(|| AppMode::Single);
     let selected_tab = use_state(|| Tabs::BasicImageInfo);
 
     let show_error = use_state(|| None);
@@ -63,12 +98,25 @@ fn app() -> Html {
     let final_img_url = use_state(|| None);
     let final_img_ndt = use_state(|| None);
 
+    let history = use_state(History::new);
+
     let process_file = {
+        let history = history.clone();
         let file_name = file_name.clone();
         let file_size = file_size.clone();
+        let file_type = file_type.clone();
         let file_bytes = file_bytes.clone();
+        let preview_bytes = preview_bytes.clone();
         let img_data_url = img_data_url.clone();
         let exif = exif.clone();
+        let original_exif = original_exif.clone();
+        let xmp = xmp.clone();
+        let original_xmp = original_xmp.clone();
+        let xmp_error = xmp_error.clone();
+        let iptc = iptc.clone();
+        let original_iptc = original_iptc.clone();
+        let iptc_error = iptc_error.clone();
+        let icc_action = icc_action.clone();
         let is_converting = is_converting.clone();
         let show_error = show_error.clone();
         let show_toast = show_toast.clone();
@@ -76,28 +124,41 @@ fn app() -> Html {
         let final_img_ndt = final_img_ndt.clone();
 
         Callback::from(move |file: web_sys::File| {
-            let file_type = if file.name().to_lowercase().ends_with(".heic") {
-                FileType::HEIC
-            } else {
-                FileType::JPEG
-            };
-
             let file_name_value = file.name();
             let promise = ensure_jpeg_bytes(file);
 
             file_name.set(None);
             file_size.set(None);
             file_bytes.set(None);
+            preview_bytes.set(None);
             img_data_url.set(None);
             exif.set(None);
+            original_exif.set(None);
+            xmp.set(None);
+            original_xmp.set(None);
+            xmp_error.set(None);
+            iptc.set(None);
+            original_iptc.set(None);
+            iptc_error.set(None);
+            icc_action.set(IccAction::Keep);
+            history.set(History::new());
             final_img_url.set(None);
             final_img_ndt.set(None);
 
             let file_name = file_name.clone();
             let file_size = file_size.clone();
+            let file_type = file_type.clone();
             let file_bytes = file_bytes.clone();
+            let preview_bytes = preview_bytes.clone();
             let img_data_url = img_data_url.clone();
             let exif = exif.clone();
+            let original_exif = original_exif.clone();
+            let xmp = xmp.clone();
+            let original_xmp = original_xmp.clone();
+            let xmp_error = xmp_error.clone();
+            let iptc = iptc.clone();
+            let original_iptc = original_iptc.clone();
+            let iptc_error = iptc_error.clone();
             let is_converting = is_converting.clone();
             let show_error = show_error.clone();
             let show_toast = show_toast.clone();
@@ -108,38 +169,69 @@ fn app() -> Html {
                     Ok(js_val) => {
                         match (
                             js_sys::Reflect::get(&js_val, &"jpeg".into()),
-                            js_sys::Reflect::get(&js_val, &"exif".into()),
+                            js_sys::Reflect::get(&js_val, &"original".into()),
                         ) {
-                            (Ok(v), Ok(ex)) => {
+                            (Ok(v), Ok(orig)) => {
                                 let jpeg_u8 = js_sys::Uint8Array::new(&v).to_vec();
+                                let original_u8 = js_sys::Uint8Array::new(&orig).to_vec();
 
-                                if jpeg_u8.len() > MAX_FILE_SIZE {
+                                if original_u8.len() > MAX_FILE_SIZE {
                                     show_error.set(Some("ファイルサイズが大きすぎます (最大1GBまで対応) 。".to_string()));
                                     is_converting.set(false);
                                     return;
                                 }
-                                if let Ok(metadata) = match file_type {
-                                    FileType::JPEG => Metadata::new_from_vec(&jpeg_u8, FileExtension::JPEG),
-                                    FileType::HEIC => {
-                                        match from_value::<serde_json::Value>(ex) {
-                                            Ok(exif_dict) => Ok(metadata_heic(&exif_dict)),
-                                            Err(_) => Ok(Metadata::new()),
-                                        }
+                                let file_type_value = match FileType::detect(&original_u8) {
+                                    Some(file_type_value) => file_type_value,
+                                    None => {
+                                        file_name.set(None);
+                                        file_size.set(None);
+                                        exif.set(None);
+                                        show_error.set(Some("対応していないファイル形式です。JPEG, HEIC, PNG, WebP, TIFFのいずれかを選択してください。".to_string()));
+                                        is_converting.set(false);
+                                        return;
                                     }
-                                } {
-                                    file_name.set(Some(file_name_value));
-                                    file_size.set(Some(jpeg_u8.len()));
-                                    exif.set(Some(ExifEditData::new(&metadata)));
-                                } else {
-                                    file_name.set(Some(file_name_value));
-                                    file_size.set(Some(jpeg_u8.len()));
-                                    exif.set(Some(ExifEditData::new(&Metadata::new())));
-                                }
-
-                                let jpeg_base64 = base64::engine::general_purpose::STANDARD.encode(&jpeg_u8);
-                                let data_url = format!("data:image/jpeg;base64,{}", jpeg_base64);
+                                };
+                                let metadata = file_type_value.read_metadata(&original_u8).unwrap_or_else(|_| Metadata::new());
+                                let eed = ExifEditData::new(&metadata);
+                                file_name.set(Some(file_name_value));
+                                file_size.set(Some(original_u8.len()));
+                                // 差分表示のために、読み込み時点の状態を別に残しておく
+                                original_exif.set(Some(eed.clone()));
+                                exif.set(Some(eed));
+
+                                let (packet, parse_error) = match xmp_container::read(file_type_value, &original_u8).map(|s| XmpPacket::parse(&s)) {
+                                    Some(Ok(packet)) => (Some(packet), None),
+                                    // 解析できないXMPは空のパケットとして扱い、編集されない限りそのまま残す
+                                    Some(Err(e)) => (Some(XmpPacket::default()), Some(e)),
+                                    None => (None, None),
+                                };
+                                original_xmp.set(packet.clone());
+                                xmp.set(packet);
+                                xmp_error.set(parse_error);
+
+                                let (data, parse_error) = match iptc::read(file_type_value, &original_u8) {
+                                    Some(Ok(data)) => (Some(data), None),
+                                    Some(Err(e)) => (Some(IptcData::default()), Some(e)),
+                                    None => (None, None),
+                                };
+                                original_iptc.set(data.clone());
+                                iptc.set(data);
+                                iptc_error.set(parse_error);
+
+                                // HEICの場合、プレビュー用のJPEGは書き出しには使わない
+                                let preview_type = match file_type_value {
+                                    FileType::HEIC => FileType::JPEG,
+                                    _ => file_type_value,
+                                };
+                                let preview_base64 = base64::engine::general_purpose::STANDARD.encode(&jpeg_u8);
+                                let data_url = format!("data:{};base64,{}", preview_type.mime(), preview_base64);
                                 img_data_url.set(Some(data_url));
-                                file_bytes.set(Some(jpeg_u8));
+                                preview_bytes.set(match file_type_value {
+                                    FileType::HEIC => Some(jpeg_u8),
+                                    _ => None,
+                                });
+                                file_type.set(file_type_value);
+                                file_bytes.set(Some(original_u8));
                                 show_toast.set(Some("画像の読み込みと変換に成功しました。".to_string()));
                             }
                             _ => {
@@ -198,86 +290,328 @@ fn app() -> Html {
 
     let on_download = {
         let file_name = file_name.clone();
+        let file_type = file_type.clone();
         let file_bytes = file_bytes.clone();
         let exif = exif.clone();
+        let xmp = xmp.clone();
+        let original_xmp = original_xmp.clone();
+        let iptc = iptc.clone();
+        let original_iptc = original_iptc.clone();
+        let icc_action = icc_action.clone();
+        let show_error = show_error.clone();
         Callback::from(move |_: MouseEvent| {
             if let (
                 Some(name), 
                 Some(bytes), 
                 Some(eed)
             ) = (file_name.as_ref(), file_bytes.as_ref(), exif.as_ref()) {
-                let mut bytes = bytes.clone();
-                if let Ok(()) = eed.metadata.write_to_vec(&mut bytes, FileExtension::JPEG) {
-                    let u8_array = Uint8Array::new_with_length(bytes.len() as u32);
-                    u8_array.copy_from(&bytes);
-                    let array = js_sys::Array::new();
-                    array.push(&u8_array.buffer());
-
-                    if let Ok(blob) = web_sys::Blob::new_with_u8_array_sequence(&array) {
-                        if let Some(window) = web_sys::window() {
-                            if let Some(document) = window.document() {
-                                if let (Ok(anchor), Ok(url)) = (
-                                    document.create_element("a"), 
-                                    Url::create_object_url_with_blob(&blob)
-                                ) {
-                                    let savename = format!("{}_{}.jpg",
-                                        name.split(".").collect::<Vec<&str>>()[0],
-                                        Local::now().naive_local().format("%Y_%m_%dT%H_%M_%S").to_string()
-                                    );
-                                    if let (Some(body), Ok(()), Ok(())) = (
-                                        document.body(),
-                                        anchor.set_attribute("href", &url),
-                                        anchor.set_attribute("download", &savename)
-                                    ) {
-                                        if let (Ok(_), Some(dr)) = (
-                                            body.append_child(&anchor),
-                                            anchor.dyn_ref::<web_sys::HtmlElement>()
-                                        ) {
-                                            dr.click();
-                                            match Url::revoke_object_url(&url) { Ok(_) => {} Err(_) => {}}
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                let bytes = match file_type.write_metadata(bytes, &eed.metadata) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        show_error.set(Some(format!("Exif情報の書き込みに失敗しました: {}", e)));
+                        return;
                     }
+                };
+                let bytes = match xmp_container::write_changes(*file_type, bytes, xmp.as_ref(), original_xmp.as_ref()) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        show_error.set(Some(format!("XMPの書き込みに失敗しました: {}", e)));
+                        return;
+                    }
+                };
+                let bytes = match iptc::write_changes(*file_type, bytes, iptc.as_ref(), original_iptc.as_ref()) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        show_error.set(Some(format!("IPTCの書き込みに失敗しました: {}", e)));
+                        return;
+                    }
+                };
+                let bytes = match icc::write_changes(*file_type, bytes, *icc_action) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        show_error.set(Some(format!("ICCプロファイルの書き込みに失敗しました: {}", e)));
+                        return;
+                    }
+                };
+                let savename = format!("{}_{}.{}",
+                    name.split(".").collect::<Vec<&str>>()[0],
+                    Local::now().naive_local().format("%Y_%m_%dT%H_%M_%S").to_string(),
+                    file_type.extension()
+                );
+                if download_bytes(&bytes, &savename).is_err() {
+                    show_error.set(Some("ファイルのダウンロードに失敗しました。".to_string()));
                 }
             }
         })
     };
 
     let on_show_final = {
+        let file_type = file_type.clone();
         let file_bytes = file_bytes.clone();
+        let preview_bytes = preview_bytes.clone();
         let exif = exif.clone();
+        let xmp = xmp.clone();
+        let original_xmp = original_xmp.clone();
+        let iptc = iptc.clone();
+        let original_iptc = original_iptc.clone();
+        let icc_action = icc_action.clone();
         let final_img_url = final_img_url.clone();
         let final_img_ndt = final_img_ndt.clone();
         Callback::from(move |_: MouseEvent| {
-            if let (Some(bytes), Some(eed)) = (file_bytes.as_ref(), exif.as_ref()) {
-                let mut bytes = bytes.clone();
-                if let Ok(()) = eed.metadata.write_to_vec(&mut bytes, FileExtension::JPEG) {
-                    final_img_url.set(Some(format!("data:image/jpeg;base64,{}", base64::engine::general_purpose::STANDARD.encode(&bytes))));
+            // HEICはブラウザで表示できないため、プレビュー用JPEGに同じExifを書き込んで表示する
+            let (source, preview_type) = match (*preview_bytes).as_ref() {
+                Some(bytes) => (Some(bytes), FileType::JPEG),
+                None => ((*file_bytes).as_ref(), *file_type),
+            };
+            if let (Some(bytes), Some(eed)) = (source, exif.as_ref()) {
+                let bytes = preview_type.write_metadata(bytes, &eed.metadata).and_then(|bytes| {
+                    match (*preview_bytes).as_ref() {
+                        Some(_) => Ok(bytes),
+                        None => xmp_container::write_changes(preview_type, bytes, xmp.as_ref(), original_xmp.as_ref())
+                            .and_then(|bytes| iptc::write_changes(preview_type, bytes, iptc.as_ref(), original_iptc.as_ref()))
+                            .and_then(|bytes| icc::write_changes(preview_type, bytes, *icc_action)),
+                    }
+                });
+                if let Ok(bytes) = bytes {
+                    final_img_url.set(Some(format!("data:{};base64,{}", preview_type.mime(), base64::engine::general_purpose::STANDARD.encode(&bytes))));
                     final_img_ndt.set(Some(Local::now().naive_local()));
                 }
             }
         })
     };
 
+    // どの編集コンポーネントもexif・xmp・iptcを差し替えるので、その変化を1ステップとして記録する
+    {
+        let history = history.clone();
+        use_effect_with(
+            (exif.clone(), xmp.clone(), iptc.clone()),
+            move |(exif, xmp, iptc): &(UseStateHandle<Option<ExifEditData>>, UseStateHandle<Option<XmpPacket>>, UseStateHandle<Option<IptcData>>)| {
+                match exif.as_ref() {
+                    Some(eed) => {
+                        let snapshot = Snapshot { exif: eed.clone(), xmp: (**xmp).clone(), iptc: (**iptc).clone() };
+                        if !history.is_current(&snapshot) {
+                            let mut h = (*history).clone();
+                            h.record(&snapshot);
+                            history.set(h);
+                        }
+                    }
+                    None => {
+                        if *history != History::new() {
+                            history.set(History::new());
+                        }
+                    }
+                }
+                || ()
+            }
+        );
+    }
+
+    let on_undo = {
+        let history = history.clone();
+        let exif = exif.clone();
+        let xmp = xmp.clone();
+        let iptc = iptc.clone();
+        Callback::from(move |_: ()| {
+            let mut h = (*history).clone();
+            if let Some(snapshot) = h.undo() {
+                history.set(h);
+                exif.set(Some(snapshot.exif));
+                xmp.set(snapshot.xmp);
+                iptc.set(snapshot.iptc);
+            }
+        })
+    };
+
+    let on_redo = {
+        let history = history.clone();
+        let exif = exif.clone();
+        let xmp = xmp.clone();
+        let iptc = iptc.clone();
+        Callback::from(move |_: ()| {
+            let mut h = (*history).clone();
+            if let Some(snapshot) = h.redo() {
+                history.set(h);
+                exif.set(Some(snapshot.exif));
+                xmp.set(snapshot.xmp);
+                iptc.set(snapshot.iptc);
+            }
+        })
+    };
+
+    // リスナーは一度だけ登録し、最新のundo/redoをここから読む
+    let shortcuts = use_mut_ref(|| (on_undo.clone(), on_redo.clone()));
+    *shortcuts.borrow_mut() = (on_undo.clone(), on_redo.clone());
+    {
+        let shortcuts = shortcuts.clone();
+        use_effect_with(
+            (),
+            move |_| {
+                let listener = web_sys::window().map(|window| {
+                    EventListener::new(&window, "keydown", move |event| {
+                        let Some(event) = event.dyn_ref::<web_sys::KeyboardEvent>() else { return; };
+                        if !(event.ctrl_key() || event.meta_key()) {
+                            return;
+                        }
+                        // 入力欄の中ではブラウザ標準の元に戻すを優先する
+                        let editing = event.target().is_some_and(|t| {
+                            t.dyn_ref::<HtmlInputElement>().is_some()
+                                || t.dyn_ref::<HtmlTextAreaElement>().is_some()
+                                || t.dyn_ref::<web_sys::HtmlElement>().is_some_and(|e| e.is_content_editable())
+                        });
+                        if editing {
+                            return;
+                        }
+                        let (on_undo, on_redo) = shortcuts.borrow().clone();
+                        match event.key().to_lowercase().as_str() {
+                            "z" if event.shift_key() => {
+                                event.prevent_default();
+                                on_redo.emit(());
+                            }
+                            "z" => {
+                                event.prevent_default();
+                                on_undo.emit(());
+                            }
+                            "y" => {
+                                event.prevent_default();
+                                on_redo.emit(());
+                            }
+                            _ => {}
+                        }
+                    })
+                });
+                move || drop(listener)
+            }
+        );
+    }
+
+    let on_export = {
+        let file_name = file_name.clone();
+        let exif = exif.clone();
+        let show_error = show_error.clone();
+        Callback::from(move |format: ExportFormat| {
+            let file_name = file_name.clone();
+            let exif = exif.clone();
+            let show_error = show_error.clone();
+            Callback::from(move |_: MouseEvent| {
+                if let (Some(name), Some(eed)) = (file_name.as_ref(), exif.as_ref()) {
+                    let text = format.export(&[(name.as_str(), eed)]);
+                    let savename = format!("{}_{}", name.split(".").collect::<Vec<&str>>()[0], format.file_suffix());
+                    if download_bytes(text.as_bytes(), &savename).is_err() {
+                        show_error.set(Some("ファイルのダウンロードに失敗しました。".to_string()));
+                    }
+                }
+            })
+        })
+    };
+
+    let on_export_waypoint = {
+        let file_name = file_name.clone();
+        let exif = exif.clone();
+        let show_error = show_error.clone();
+        Callback::from(move |format: WaypointFormat| {
+            let file_name = file_name.clone();
+            let exif = exif.clone();
+            let show_error = show_error.clone();
+            Callback::from(move |_: MouseEvent| {
+                if let (Some(name), Some(eed)) = (file_name.as_ref(), exif.as_ref()) {
+                    let Some(waypoint) = eed.waypoint(name) else {
+                        show_error.set(Some("位置情報がありません。".to_string()));
+                        return;
+                    };
+                    let text = format.export(&[waypoint]);
+                    let savename = format!("{}.{}", name.split(".").collect::<Vec<&str>>()[0], format.extension());
+                    if download_bytes(text.as_bytes(), &savename).is_err() {
+                        show_error.set(Some("ファイルのダウンロードに失敗しました。".to_string()));
+                    }
+                }
+            })
+        })
+    };
+
     let on_delete_all = {
         let exif = exif.clone();
+        let xmp = xmp.clone();
+        let iptc = iptc.clone();
         Callback::from(move |_: MouseEvent| {
             if let Some(eed) = exif.as_ref() {
                 let mut eed = eed.clone();
-                let metadata = eed.metadata.clone();
-                for ifd in metadata.get_ifds() {
-                    for tag in ifd.get_tags() {
-                        eed.delete_tag(tag.clone());
-                    }
+                eed.delete_all();
+                exif.set(Some(eed));
+                // XMPやIPTCにも同じ情報が残っていることが多いため、合わせて削除する
+                xmp.set(None);
+                iptc.set(None);
+            }
+        })
+    };
+
+    let on_clock_shift = {
+        let exif = exif.clone();
+        let show_error = show_error.clone();
+        Callback::from(move |shift: ClockShift| {
+            if let Some(eed) = exif.as_ref() {
+                let mut eed = eed.clone();
+                if !shift.apply(&mut eed) {
+                    show_error.set(Some(format!("日時は補正しましたが、{}。", GPS_OFFSET_MISSING)));
                 }
                 exif.set(Some(eed));
             }
         })
     };
 
+    let on_geotag = {
+        let exif = exif.clone();
+        let show_error = show_error.clone();
+        let show_toast = show_toast.clone();
+        Callback::from(move |geotag: Geotag| {
+            if let Some(eed) = exif.as_ref() {
+                let mut eed = eed.clone();
+                match geotag.apply(&mut eed) {
+                    Ok(point) => {
+                        exif.set(Some(eed));
+                        show_toast.set(Some(format!("位置情報を設定しました ({:.6}, {:.6})。", point.lat, point.lng)));
+                    }
+                    Err(e) => show_error.set(Some(format!("位置情報を設定できません: {}", e))),
+                }
+            }
+        })
+    };
+
+    let on_fuzz = {
+        let exif = exif.clone();
+        let xmp = xmp.clone();
+        let iptc = iptc.clone();
+        let show_error = show_error.clone();
+        let show_toast = show_toast.clone();
+        Callback::from(move |fuzz: LocationFuzz| {
+            if let Some(eed) = exif.as_ref() {
+                let mut eed = eed.clone();
+                let position = fuzz.apply(&mut eed);
+                // XMPやIPTCにも同じ位置や地名が残っていることが多いため、合わせてぼかす
+                let mut packet = (*xmp).clone();
+                let xmp_position = match packet.as_mut().map(|packet| fuzz.apply_xmp(packet)).transpose() {
+                    Ok(p) => p.flatten(),
+                    Err(e) => {
+                        show_error.set(Some(format!("XMPの位置をぼかせません: {}", e)));
+                        return;
+                    }
+                };
+                let mut data = (*iptc).clone();
+                if let Some(data) = data.as_mut() {
+                    fuzz.apply_iptc(data);
+                }
+                match position.or(xmp_position) {
+                    Some((lat, lng)) => {
+                        exif.set(Some(eed));
+                        xmp.set(packet);
+                        iptc.set(data);
+                        show_toast.set(Some(format!("位置をぼかしました ({:.6}, {:.6})。", lat, lng)));
+                    }
+                    None => show_error.set(Some("位置情報がありません。".to_string())),
+                }
+            }
+        })
+    };
+
     {
         let show_toast = show_toast.clone();
         use_effect_with(
@@ -305,7 +639,7 @@ fn app() -> Html {
                             <div class="spinner-border text-primary" role="status" style="width: 3rem; height: 3rem;">
                                 <span class="visually-hidden">{ "読み込み中..." }</span>
                             </div>
-                            <p class="mt-3 fw-bold text-primary">{ "HEICをJPEGに変換中..." }</p>
+                            <p class="mt-3 fw-bold text-primary">{ "画像を読み込み中..." }</p>
                         </div>
                     </div>
                 }
@@ -317,152 +651,279 @@ fn app() -> Html {
 
         <div class="card shadow-sm mb-4">
         <div class="card-body text-center">
-            <h1 class="h4 mb-2">{"JPEG/HEIC Exif情報編集ツール"}</h1>
-            <p class="text-muted mb-0">{ "JPEG/HEIC画像をアップロードして、Exifデータを編集・JPEGで保存できます。" }</p>
+            <h1 class="h4 mb-2">{"画像Exif情報編集ツール"}</h1>
+            <p class="text-muted mb-0">{ "JPEG/HEIC/PNG/WebP/TIFF画像をアップロードして、Exifデータを編集・元の形式のまま保存できます。" }</p>
         </div>
         </div>
 
-        <div
-            class="card shadow-sm mb-4"
-            ondragover={on_drag_over}
-            ondrop={on_drop}
-        >
-            <div class="card-body text-center">
-            <h2 class="h5 mb-3">{ "ステップ1: 画像をアップロード" }</h2>
-            <p class="mb-3">{ "HEIC または JPEG 形式の画像をドラッグ＆ドロップするか、ボタンで選択してください。" }</p>
-            <div class="mx-auto" style="max-width: 360px;">
-                <input 
-                    id="fileUpload"
-                    type="file" 
-                    accept="image/jpeg,image/heic" 
-                    ref={file_input} 
-                    class="form-control mb-3"
-                    onchange={on_file_change}
-                    aria-describedby="fileHelp" />
-                <div id="fileHelp" class="form-text">
-                    { "対応形式: JPEG, HEIC" }
-                </div>
-            </div>
-            {
-                if let Some(file_size) = *file_size {
-                    html! { <p class="text-muted small">{ format!("ファイルサイズ: {:.2} KB", file_size as f64 / 1024.0) }</p> }
-                } else {
-                    html! {}
-                }
-            }
-            {
-                /*if *is_converting {
-                    html! {
-                        <div class="mt-3">
-                            <div class="spinner-border text-primary" role="status">
-                                <span class="visually-hidden">{ "変換中..." }</span>
-                            </div>
-                            <div class="mt-2 text-muted small">{ "HEICをJPEGに変換中です..." }</div>
+        <ul class="nav nav-pills nav-fill mb-4">
+        <TabItem<AppMode> tab={AppMode::Single} selected_tab={app_mode.clone()} message={"1枚ずつ編集"} icon={"file-earmark-image"} />
+        <TabItem<AppMode> tab={AppMode::Batch} selected_tab={app_mode.clone()} message={"一括処理"} icon={"files"} />
+        </ul>
+
+        { if *app_mode == AppMode::Batch {
+            html! { <BatchMode /> }
+        } else {
+            html! {
+                <>
+                <div
+                    class="card shadow-sm mb-4"
+                    ondragover={on_drag_over}
+                    ondrop={on_drop}
+                >
+                    <div class="card-body text-center">
+                    <h2 class="h5 mb-3">{ "ステップ1: 画像をアップロード" }</h2>
+                    <p class="mb-3">{ "JPEG, HEIC, PNG, WebP, TIFF 形式の画像をドラッグ＆ドロップするか、ボタンで選択してください。" }</p>
+                    <div class="mx-auto" style="max-width: 360px;">
+                        <input 
+                            id="fileUpload"
+                            type="file" 
+                            accept="image/jpeg,image/heic,image/heif,image/png,image/webp,image/tiff,.heic,.heif,.tif,.tiff" 
+                            ref={file_input} 
+                            class="form-control mb-3"
+                            onchange={on_file_change}
+                            aria-describedby="fileHelp" />
+                        <div id="fileHelp" class="form-text">
+                            { "対応形式: JPEG, HEIC, PNG, WebP, TIFF" }
                         </div>
+                    </div>
+                    {
+                        if let Some(file_size) = *file_size {
+                            html! { <p class="text-muted small">{ format!("ファイルサイズ: {:.2} KB", file_size as f64 / 1024.0) }</p> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        /*if *is_converting {
+                            html! {
+                                <div class="mt-3">
+                                    <div class="spinner-border text-primary" role="status">
+                                        <span class="visually-hidden">{ "変換中..." }</span>
+                                    </div>
+                                    <div class="mt-2 text-muted small">{ "HEICをJPEGに変換中です..." }</div>
+                                </div>
+                            }
+                        } else */
+                        if let Some(url) = (*img_data_url).clone() {
+                            html! {
+                                <>
+                                <div class="mb-3">
+                                    <img
+                                        src={url}
+                                        alt="アップロードされた画像のプレビュー"
+                                        class="img-fluid rounded shadow-sm d-block mx-auto"
+                                        style="max-height: 50vh; width: auto;"
+                                    />
+                                    { if *file_type == FileType::TIFF {
+                                        html! { <p class="text-muted small mt-2 mb-0">{ "※TIFFはブラウザによってはプレビューを表示できません。" }</p> }
+                                    } else {
+                                        html! {}
+                                    } }
+                                </div>
+                                <div class="mb-3 d-flex flex-column gap-3">
+                                    <button type="button" class="btn btn-primary w-100" onclick={on_download.clone()}>{ format!("編集後のファイルをダウンロード ({})", file_type.label()) }</button>
+                                    <p class="my-0">{"↓スマートフォンの場合は、こちらで表示した画像を長押ししてダウンロードしてください。"}</p>
+                                    <button type="button" class="btn btn-info w-100" onclick={on_show_final.clone()}>
+                                    { if (*final_img_url).is_some() { "編集後の画像を再表示する" } else { "編集後の画像を表示する" } }
+                                    </button>
+                                </div>
+                                {
+                                    if let Some(url) = (*final_img_url).clone() {
+                                        html! {
+                                            <div class="card shadow-sm mb-3">
+                                                <div class="card-body text-center">
+                                                    <h5 class="card-title">{ "編集後の画像" }</h5>
+                                                    { if let Some(ndt) = (*final_img_ndt).clone() {
+                                                        html! { <p>{format!("最終更新: {}", ndt.format("%Y年%m月%d日 %H時%M分%S秒").to_string())}</p> }
+                                                    } else {
+                                                        html! {}
+                                                    } }
+                                            
+                                                    <p style="color: red;">{"※Exif情報を更新した場合は、再表示してください。"}</p>
+                                                    <p>{"※Chromeを使用している場合、長押ししても画像を保存できない場合があります。その際はFirefox等の他のブラウザを使用するか、↑のボタンからダウンロードしてください（その場合「フォト」ではなく「ファイル」に保存されます）"}</p>
+                                                    <img
+                                                        src={url}
+                                                        alt="編集後の画像のプレビュー"
+                                                        class="img-fluid rounded shadow-sm d-block mx-auto"
+                                                        style="max-height: 50vh; width: auto;"
+                                                    />
+                                                </div>
+                                            </div>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                                </>
+                            }
+                        } else {
+                            html! {}
+                        }
                     }
-                } else */
-                if let Some(url) = (*img_data_url).clone() {
+                </div>
+                </div>
+                { if let Some(_) = exif.as_ref() {
                     html! {
-                        <>
-                        <div class="mb-3">
-                            <img
-                                src={url}
-                                alt="アップロードされた画像のプレビュー"
-                                class="img-fluid rounded shadow-sm d-block mx-auto"
-                                style="max-height: 50vh; width: auto;"
-                            />
-                        </div>
-                        <div class="mb-3 d-flex flex-column gap-3">
-                            <button type="button" class="btn btn-primary w-100" onclick={on_download.clone()}>{ "編集後のファイルをダウンロード (JPEG)" }</button>
-                            <p class="my-0">{"↓スマートフォンの場合は、こちらで表示した画像を長押ししてダウンロードしてください。"}</p>
-                            <button type="button" class="btn btn-info w-100" onclick={on_show_final.clone()}>
-                            { if (*final_img_url).is_some() { "編集後の画像を再表示する" } else { "編集後の画像を表示する" } }
+                        <div class="card shadow-sm mb-4">
+                        <div class="card-body">
+                        <h2 class="h5 mb-3 text-center">{ "ステップ2: Exif情報の確認・編集" }</h2>
+                        <p class="text-muted small text-center mb-4">
+                            { "必要に応じてExif情報を削除または編集できます。編集タブを切り替えて内容を確認してください。" }
+                        </p>
+
+                        <div class="d-flex gap-2 mb-2">
+                            <button type="button" class="btn btn-outline-secondary flex-fill"
+                                title="Ctrl+Z"
+                                disabled={!history.can_undo()}
+                                onclick={on_undo.reform(|_: MouseEvent| ())}>
+                                <i class="bi bi-arrow-counterclockwise me-1" aria-hidden="true"></i>
+                                { match history.undo_description() {
+                                    Some(d) => format!("元に戻す: {}", d),
+                                    None => "元に戻す".to_string(),
+                                } }
+                            </button>
+                            <button type="button" class="btn btn-outline-secondary flex-fill"
+                                title="Ctrl+Shift+Z"
+                                disabled={!history.can_redo()}
+                                onclick={on_redo.reform(|_: MouseEvent| ())}>
+                                <i class="bi bi-arrow-clockwise me-1" aria-hidden="true"></i>
+                                { match history.redo_description() {
+                                    Some(d) => format!("やり直す: {}", d),
+                                    None => "やり直す".to_string(),
+                                } }
                             </button>
                         </div>
                         {
-                            if let Some(url) = (*final_img_url).clone() {
+                            if history.can_undo() {
                                 html! {
-                                    <div class="card shadow-sm mb-3">
-                                        <div class="card-body text-center">
-                                            <h5 class="card-title">{ "編集後の画像" }</h5>
-                                            { if let Some(ndt) = (*final_img_ndt).clone() {
-                                                html! { <p>{format!("最終更新: {}", ndt.format("%Y年%m月%d日 %H時%M分%S秒").to_string())}</p> }
-                                            } else {
-                                                html! {}
-                                            } }
-                                            
-                                            <p style="color: red;">{"※Exif情報を更新した場合は、再表示してください。"}</p>
-                                            <p>{"※Chromeを使用している場合、長押ししても画像を保存できない場合があります。その際はFirefox等の他のブラウザを使用するか、↑のボタンからダウンロードしてください（その場合「フォト」ではなく「ファイル」に保存されます）"}</p>
-                                            <img
-                                                src={url}
-                                                alt="編集後の画像のプレビュー"
-                                                class="img-fluid rounded shadow-sm d-block mx-auto"
-                                                style="max-height: 50vh; width: auto;"
-                                            />
-                                        </div>
+                                    <details class="small text-muted mb-3">
+                                        <summary>{ "編集履歴" }</summary>
+                                        <ol class="mb-0">
+                                        { for history.descriptions().iter().map(|d| html! { <li>{ d.clone() }</li> }) }
+                                        </ol>
+                                    </details>
+                                }
+                            } else { html! {} }
+                        }
+
+                        <div class="mb-3 d-flex flex-wrap gap-2 justify-content-center">
+                            { for ExportFormat::all().iter().map(|format| html! {
+                                <button type="button" class="btn btn-outline-primary btn-sm" onclick={on_export.emit(*format)}>
+                                    <i class="bi bi-download me-1" aria-hidden="true"></i>
+                                    { format!("{}で書き出す", format.label()) }
+                                </button>
+                            }) }
+                        </div>
+                        {
+                            if exif.as_ref().and_then(|eed| eed.position()).is_some() {
+                                html! {
+                                    <div class="mb-3 d-flex flex-wrap gap-2 justify-content-center">
+                                        { for WaypointFormat::all().iter().map(|format| html! {
+                                            <button type="button" class="btn btn-outline-success btn-sm" onclick={on_export_waypoint.emit(*format)}>
+                                                <i class="bi bi-geo-alt me-1" aria-hidden="true"></i>
+                                                { format!("撮影地点を{}で書き出す", format.label()) }
+                                            </button>
+                                        }) }
                                     </div>
                                 }
-                            } else {
-                                html! {}
-                            }
+                            } else { html! {} }
                         }
-                        </>
-                    }
-                } else {
-                    html! {}
-                }
-            }
-        </div>
-        </div>
-        { if let Some(_) = exif.as_ref() {
-            html! {
-                <div class="card shadow-sm mb-4">
-                <div class="card-body">
-                <h2 class="h5 mb-3 text-center">{ "ステップ2: Exif情報の確認・編集" }</h2>
-                <p class="text-muted small text-center mb-4">
-                    { "必要に応じてExif情報を削除または編集できます。編集タブを切り替えて内容を確認してください。" }
-                </p>
-
-                <div class="mb-3">
-                    <button type="button" class="btn btn-danger w-100" onclick={on_delete_all.clone()}>{ "すべてのExif情報を削除" }</button>
-                </div>
 
-                <ul class="nav nav-tabs flex-nowrap mb-3">
-                <TabItem<Tabs> tab={Tabs::BasicImageInfo} selected_tab={selected_tab.clone()} message={"基本情報"} icon={"image"} />
-                <TabItem<Tabs> tab={Tabs::ExifCaptureInfo} selected_tab={selected_tab.clone()} message={"詳細情報"} icon={"database"} />
-                <TabItem<Tabs> tab={Tabs::GPSInfo} selected_tab={selected_tab.clone()} message={"位置情報"} icon={"geo-alt"} />
-                <TabItem<Tabs> tab={Tabs::InteropInfo} selected_tab={selected_tab.clone()} message={"相互運用性"} icon={"arrow-left-right"} />
-                <TabItem<Tabs> tab={Tabs::ThumbnailInfo} selected_tab={selected_tab.clone()} message={"サムネイル情報"} icon={"search"} />
-                <TabItem<Tabs> tab={Tabs::UserInfo} selected_tab={selected_tab.clone()} message={"ユーザ情報"} icon={"person-circle"} />
-                <TabItem<Tabs> tab={Tabs::Uneditable} selected_tab={selected_tab.clone()} message={"変更不可"} icon={"slash-circle"} />
-                </ul>
-
-                <div class="mb-3">
-                    <p>
-                        <i class="bi bi-exclamation-triangle-fill text-warning ms-2" aria-hidden="true"></i>
-                        { ": 編集によりファイルが破損しうる" }
-                    </p>
-                    <p>
-                        <i class="bi bi-slash-circle-fill text-danger ms-2" aria-hidden="true"></i>
-                        { ": 編集不能" }
-                    </p>
-                </div>
+                        <ImportMetadata exif={exif.clone()} />
+
+                        <details class="mb-3">
+                            <summary>{ "撮影日時をまとめて補正" }</summary>
+                            <div class="mt-2">
+                                <ClockShiftForm on_submit={on_clock_shift} button={"日時を補正する"} />
+                            </div>
+                        </details>
+
+                        <details class="mb-3">
+                            <summary>{ "トラックログから位置情報を設定" }</summary>
+                            <div class="mt-2">
+                                <GeotagForm on_submit={on_geotag} button={"位置情報を設定する"} />
+                            </div>
+                        </details>
+
+                        <details class="mb-3">
+                            <summary>{ "位置情報の精度を下げる" }</summary>
+                            <div class="mt-2">
+                                <LocationFuzzForm on_submit={on_fuzz} button={"位置をぼかす"} />
+                            </div>
+                        </details>
+
+                        <div class="mb-3">
+                            <button type="button" class="btn btn-danger w-100" onclick={on_delete_all.clone()}>{ "すべてのExif情報・XMP・IPTCを削除" }</button>
+                        </div>
+
+                        <div class="mb-3">
+                            <p class="small text-muted text-center mb-2">{ "目的に合わせて、削除するタグを確認してからまとめて削除できます。" }</p>
+                            <ScrubPresets exif={exif.clone()} xmp={xmp.clone()} iptc={iptc.clone()} />
+                        </div>
+
+                        <ul class="nav nav-tabs flex-nowrap mb-3">
+                        <TabItem<Tabs> tab={Tabs::BasicImageInfo} selected_tab={selected_tab.clone()} message={"基本情報"} icon={"image"} />
+                        <TabItem<Tabs> tab={Tabs::ExifCaptureInfo} selected_tab={selected_tab.clone()} message={"詳細情報"} icon={"database"} />
+                        <TabItem<Tabs> tab={Tabs::GPSInfo} selected_tab={selected_tab.clone()} message={"位置情報"} icon={"geo-alt"} />
+                        <TabItem<Tabs> tab={Tabs::InteropInfo} selected_tab={selected_tab.clone()} message={"相互運用性"} icon={"arrow-left-right"} />
+                        <TabItem<Tabs> tab={Tabs::ThumbnailInfo} selected_tab={selected_tab.clone()} message={"サムネイル情報"} icon={"search"} />
+                        <TabItem<Tabs> tab={Tabs::UserInfo} selected_tab={selected_tab.clone()} message={"ユーザ情報"} icon={"person-circle"} />
+                        <TabItem<Tabs> tab={Tabs::Uneditable} selected_tab={selected_tab.clone()} message={"変更不可"} icon={"slash-circle"} />
+                        <TabItem<Tabs> tab={Tabs::Changes} selected_tab={selected_tab.clone()} message={"変更点"} icon={"list-check"} />
+                        <TabItem<Tabs> tab={Tabs::Copy} selected_tab={selected_tab.clone()} message={"他の画像からコピー"} icon={"copy"} />
+                        <TabItem<Tabs> tab={Tabs::Xmp} selected_tab={selected_tab.clone()} message={"XMP"} icon={"code-slash"} />
+                        <TabItem<Tabs> tab={Tabs::Iptc} selected_tab={selected_tab.clone()} message={"IPTC"} icon={"newspaper"} />
+                        <TabItem<Tabs> tab={Tabs::Segments} selected_tab={selected_tab.clone()} message={"セグメント"} icon={"diagram-3"} />
+                        </ul>
 
-                {
-                    match *selected_tab {
-                        Tabs::BasicImageInfo =>  html! { <BasicImageInfo exif={exif.clone()} /> },
-                        Tabs::ExifCaptureInfo =>  html! { <ExifCaptureInfo exif={exif.clone()} /> },
-                        Tabs::GPSInfo => html! { <GPSInfo exif={exif.clone()} /> },
-                        Tabs::InteropInfo => html! { <InteropInfo exif={exif.clone()} /> },
-                        Tabs::ThumbnailInfo => html! { <ThumbnailInfo exif={exif.clone()} /> },
-                        Tabs::UserInfo => html! { <UserInfo exif={exif.clone()} /> },
-                        Tabs::Uneditable => html! { <Uneditable exif={exif.clone()} /> }
+                        <div class="mb-3">
+                            <p>
+                                <i class="bi bi-exclamation-triangle-fill text-warning ms-2" aria-hidden="true"></i>
+                                { ": 編集によりファイルが破損しうる" }
+                            </p>
+                            <p>
+                                <i class="bi bi-slash-circle-fill text-danger ms-2" aria-hidden="true"></i>
+                                { ": 編集不能" }
+                            </p>
+                        </div>
+
+                        {
+                            match *selected_tab {
+                                Tabs::BasicImageInfo =>  html! {
+                                    <BasicImageInfo exif={exif.clone()} file_type={*file_type}
+                                        file_bytes={file_bytes.clone()} icc_action={icc_action.clone()} />
+                                },
+                                Tabs::ExifCaptureInfo =>  html! { <ExifCaptureInfo exif={exif.clone()} /> },
+                                Tabs::GPSInfo => html! { <GPSInfo exif={exif.clone()} /> },
+                                Tabs::InteropInfo => html! { <InteropInfo exif={exif.clone()} /> },
+                                Tabs::ThumbnailInfo => html! {
+                                    <ThumbnailInfo exif={exif.clone()} file_type={*file_type}
+                                        file_bytes={file_bytes.clone()} preview_bytes={preview_bytes.clone()} />
+                                },
+                                Tabs::UserInfo => html! { <UserInfo exif={exif.clone()} /> },
+                                Tabs::Uneditable => html! { <Uneditable exif={exif.clone()} /> },
+                                Tabs::Changes => html! { <Changes exif={exif.clone()} original={original_exif.clone()} /> },
+                                Tabs::Copy => html! { <CopyMetadata exif={exif.clone()} /> },
+                                Tabs::Xmp => html! {
+                                    <XmpInfo exif={exif.clone()} xmp={xmp.clone()} file_type={*file_type}
+                                        parse_error={(*xmp_error).clone()} />
+                                },
+                                Tabs::Iptc => html! {
+                                    <IptcInfo iptc={iptc.clone()} file_type={*file_type} parse_error={(*iptc_error).clone()} />
+                                },
+                                Tabs::Segments => html! {
+                                    <SegmentInspector file_type={*file_type} file_bytes={file_bytes.clone()} file_size={file_size.clone()} />
+                                },
+                            }
+                        }
+                        </div>
+                        </div>
                     }
-                }
-                </div>
-                </div>
+                } else { html! {} } }
+                </>
             }
-        } else { html! {} } }
+        } }
 
         <footer class="text-center text-muted mt-5 mb-2 small">
             <p class="mb-1">{ "© 2025 "}<a href="https://j-impact.jp/">{"J-IMPACT"}</a>{". All rights reserved." }</p>
@@ -491,9 +952,9 @@ fn app() -> Html {
                 <div id="collapseInfo" class="accordion-collapse collapse" aria-labelledby="headingInfo" data-bs-parent="#footerAccordion">
                     <div class="accordion-body small text-muted">
                         <ul class="mb-0">
-                            <li>{ "対応形式: JPEG (.jpg), HEIC (.heic)" }</li>
+                            <li>{ "対応形式: JPEG (.jpg), HEIC (.heic), PNG (.png), WebP (.webp), TIFF (.tif)" }</li>
                             <li>{ "最大ファイルサイズ: 1GBまで" }</li>
-                            <li>{ "Exifデータを読み取り、編集、削除してJPEG形式で保存できます" }</li>
+                            <li>{ "Exifデータを読み取り、編集、削除して元の形式のまま保存できます" }</li>
                             <li>{ "変換後の画像はローカルで処理され、外部に送信されません" }</li>
                             <li>{ "本アプリはオープンソースとして提供されている試験的なツールです" }</li>
                         </ul>
@@ -0,0 +1,437 @@
+// XMPパケット（RDF/XML）の簡易的な読み書き。
+// rdf:Description直下のプロパティを扱い、構造体などの複雑な値は要素のまま保持して書き戻す
+
+use crate::geo::xml::{escape, unescape};
+
+pub mod container;
+pub mod sync;
+
+pub const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+pub const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+pub const XMP_NS: &str = "http://ns.adobe.com/xap/1.0/";
+pub const EXIF_NS: &str = "http://ns.adobe.com/exif/1.0/";
+pub const PHOTOSHOP_NS: &str = "http://ns.adobe.com/photoshop/1.0/";
+pub const TIFF_NS: &str = "http://ns.adobe.com/tiff/1.0/";
+// 読み込んだパケットの中で参照するだけの名前空間
+pub const AUX_NS: &str = "http://ns.adobe.com/exif/1.0/aux/";
+pub const EXIF_EX_NS: &str = "http://cipa.jp/exif/1.0/";
+pub const IPTC_CORE_NS: &str = "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/";
+
+// (接頭辞, 名前空間URI, 表示名)
+pub const NAMESPACES: &[(&str, &str, &str)] = &[
+    ("dc", DC_NS, "Dublin Core"),
+    ("xmp", XMP_NS, "XMP基本"),
+    ("exif", EXIF_NS, "Exif"),
+    ("photoshop", PHOTOSHOP_NS, "Photoshop"),
+    ("tiff", TIFF_NS, "TIFF"),
+];
+
+pub fn namespace_label(uri: &str) -> Option<&'static str> {
+    NAMESPACES.iter().find(|(_, u, _)| *u == uri).map(|(_, _, label)| *label)
+}
+
+fn default_prefix(uri: &str) -> Option<&'static str> {
+    NAMESPACES.iter().find(|(_, u, _)| *u == uri).map(|(prefix, _, _)| *prefix)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    fn prefix(&self) -> &str {
+        self.name.split_once(':').map(|(p, _)| p).unwrap_or("")
+    }
+
+    fn local_name(&self) -> &str {
+        self.name.split_once(':').map(|(_, n)| n).unwrap_or(&self.name)
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    fn child_elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|node| match node {
+            XmlNode::Element(e) => Some(e),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    fn text(&self) -> String {
+        self.children.iter().filter_map(|node| match node {
+            XmlNode::Text(t) => Some(t.as_str()),
+            XmlNode::Element(_) => None,
+        }).collect::<String>()
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let pad = " ".repeat(indent);
+        out.push_str(&format!("{}<{}", pad, self.name));
+        for (k, v) in self.attrs.iter() {
+            out.push_str(&format!(" {}=\"{}\"", k, escape(v)));
+        }
+        if self.children.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        if self.child_elements().next().is_none() {
+            out.push_str(&format!(">{}</{}>\n", escape(&self.text()), self.name));
+            return;
+        }
+        out.push_str(">\n");
+        for child in self.child_elements() {
+            child.write(out, indent + 1);
+        }
+        out.push_str(&format!("{}</{}>\n", pad, self.name));
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_past(&mut self, pattern: &str) -> Option<()> {
+        let i = self.rest().find(pattern)?;
+        self.pos += i + pattern.len();
+        Some(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    // 最初の要素まで読み飛ばしてから、その要素を読む
+    fn document(&mut self) -> Option<XmlElement> {
+        loop {
+            let i = self.rest().find('<')?;
+            self.pos += i;
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return self.element();
+            }
+        }
+    }
+
+    fn element(&mut self) -> Option<XmlElement> {
+        self.pos += 1;
+        let rest = self.rest();
+        let end = rest.find(|c: char| c.is_whitespace() || c == '/' || c == '>')?;
+        let name = rest[..end].to_string();
+        self.pos += end;
+
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Some(XmlElement { name, attrs, children: Vec::new() });
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let eq = rest.find('=')?;
+            let key = rest[..eq].trim().to_string();
+            self.pos += eq + 1;
+            self.skip_whitespace();
+            let quote = self.rest().chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+            self.pos += 1;
+            let j = self.rest().find(quote)?;
+            attrs.push((key, unescape(&self.rest()[..j])));
+            self.pos += j + 1;
+        }
+
+        let mut children = Vec::new();
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.skip_past(">")?;
+                return Some(XmlElement { name, attrs, children });
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let j = cdata.find("]]>")?;
+                children.push(XmlNode::Text(cdata[..j].to_string()));
+                self.pos += "<![CDATA[".len() + j + "]]>".len();
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                children.push(XmlNode::Element(self.element()?));
+            } else {
+                let j = rest.find('<')?;
+                let text = &rest[..j];
+                if !text.trim().is_empty() {
+                    children.push(XmlNode::Text(unescape(text)));
+                }
+                self.pos += j;
+            }
+        }
+    }
+}
+
+// 内側で宣言された接頭辞を優先して名前空間URIを求める
+fn resolve(scope: &[(String, String)], prefix: &str) -> String {
+    if prefix == "xml" {
+        return XML_NS.to_string();
+    }
+    scope.iter().rev().find(|(p, _)| p == prefix).map(|(_, uri)| uri.clone()).unwrap_or_default()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmpValue {
+    Text(String),
+    // 順序のある配列（dc:creatorなど）
+    Seq(Vec<String>),
+    // 順序のない配列（dc:subjectなど）
+    Bag(Vec<String>),
+    // 言語ごとの代替テキスト（言語, テキスト）
+    Alt(Vec<(String, String)>),
+    // 構造体など、編集に対応していない値
+    Other(XmlElement),
+}
+
+impl XmpValue {
+    pub fn is_editable(&self) -> bool {
+        !matches!(self, XmpValue::Other(_))
+    }
+
+    // 配列は1行に1項目、代替テキストはx-default（なければ先頭）を表示する
+    pub fn to_text(&self) -> String {
+        match self {
+            XmpValue::Text(s) => s.clone(),
+            XmpValue::Seq(v) | XmpValue::Bag(v) => v.join("\n"),
+            XmpValue::Alt(v) => v.iter()
+                .find(|(lang, _)| lang == "x-default")
+                .or_else(|| v.first())
+                .map(|(_, s)| s.clone())
+                .unwrap_or_default(),
+            XmpValue::Other(_) => "(構造化された値)".to_string(),
+        }
+    }
+
+    // to_textと同じ形式の文字列で値を置き換える
+    pub fn with_text(&self, text: &str) -> Option<Self> {
+        let items = || text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).map(|l| l.to_string()).collect::<Vec<String>>();
+        match self {
+            XmpValue::Text(_) => Some(XmpValue::Text(text.to_string())),
+            XmpValue::Seq(_) => Some(XmpValue::Seq(items())),
+            XmpValue::Bag(_) => Some(XmpValue::Bag(items())),
+            XmpValue::Alt(v) => {
+                let mut v = v.clone();
+                match v.iter().position(|(lang, _)| lang == "x-default") {
+                    Some(i) => v[i].1 = text.to_string(),
+                    None => v.insert(0, ("x-default".to_string(), text.to_string())),
+                }
+                Some(XmpValue::Alt(v))
+            }
+            XmpValue::Other(_) => None,
+        }
+    }
+
+    fn from_element(element: &XmlElement) -> Self {
+        let children = element.child_elements().collect::<Vec<&XmlElement>>();
+        let plain = element.attrs.iter().all(|(k, _)| k == "xml:lang");
+        if children.is_empty() && plain {
+            return XmpValue::Text(element.text());
+        }
+        if let ([container], true) = (children.as_slice(), plain) {
+            let items = container.child_elements().collect::<Vec<&XmlElement>>();
+            let simple = container.attrs.is_empty()
+                && items.iter().all(|li| li.name == "rdf:li" && li.child_elements().next().is_none());
+            if simple {
+                match container.name.as_str() {
+                    "rdf:Seq" => return XmpValue::Seq(items.iter().map(|li| li.text()).collect()),
+                    "rdf:Bag" => return XmpValue::Bag(items.iter().map(|li| li.text()).collect()),
+                    "rdf:Alt" => return XmpValue::Alt(items.iter().map(|li| {
+                        (li.attr("xml:lang").unwrap_or("x-default").to_string(), li.text())
+                    }).collect()),
+                    _ => {}
+                }
+            }
+        }
+        XmpValue::Other(element.clone())
+    }
+
+    fn to_element(&self, name: &str) -> XmlElement {
+        let text = |s: &str| if s.is_empty() { Vec::new() } else { vec![XmlNode::Text(s.to_string())] };
+        let list = |container: &str, items: Vec<XmlElement>| XmlElement {
+            name: name.to_string(),
+            attrs: Vec::new(),
+            children: vec![XmlNode::Element(XmlElement {
+                name: container.to_string(),
+                attrs: Vec::new(),
+                children: items.into_iter().map(XmlNode::Element).collect(),
+            })],
+        };
+        let li = |attrs: Vec<(String, String)>, s: &str| XmlElement { name: "rdf:li".to_string(), attrs, children: text(s) };
+        match self {
+            XmpValue::Text(s) => XmlElement { name: name.to_string(), attrs: Vec::new(), children: text(s) },
+            XmpValue::Seq(v) => list("rdf:Seq", v.iter().map(|s| li(Vec::new(), s)).collect()),
+            XmpValue::Bag(v) => list("rdf:Bag", v.iter().map(|s| li(Vec::new(), s)).collect()),
+            XmpValue::Alt(v) => list("rdf:Alt", v.iter().map(|(lang, s)| li(vec![("xml:lang".to_string(), lang.clone())], s)).collect()),
+            XmpValue::Other(e) => XmlElement { name: name.to_string(), ..e.clone() },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmpProperty {
+    pub namespace: String,
+    pub prefix: String,
+    pub name: String,
+    pub value: XmpValue,
+}
+
+impl XmpProperty {
+    pub fn qualified_name(&self) -> String {
+        format!("{}:{}", self.prefix, self.name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XmpPacket {
+    // (接頭辞, 名前空間URI)
+    pub namespaces: Vec<(String, String)>,
+    pub properties: Vec<XmpProperty>,
+}
+
+impl XmpPacket {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let root = Parser { s: text, pos: 0 }.document().ok_or_else(|| "XMLとして読み込めません".to_string())?;
+        let mut packet = Self::default();
+        packet.collect(&root, &mut Vec::new());
+        if packet.namespaces.iter().all(|(_, uri)| uri != RDF_NS) {
+            return Err("rdf:RDFが見つかりません".to_string());
+        }
+        Ok(packet)
+    }
+
+    fn declare(&mut self, element: &XmlElement, scope: &mut Vec<(String, String)>) {
+        for (k, v) in element.attrs.iter() {
+            if let Some(prefix) = k.strip_prefix("xmlns:") {
+                scope.push((prefix.to_string(), v.clone()));
+                if !self.namespaces.iter().any(|(p, _)| p == prefix) {
+                    self.namespaces.push((prefix.to_string(), v.clone()));
+                }
+            }
+        }
+    }
+
+    fn collect(&mut self, element: &XmlElement, scope: &mut Vec<(String, String)>) {
+        let depth = scope.len();
+        self.declare(element, scope);
+        if element.name == "rdf:Description" {
+            for (k, v) in element.attrs.iter() {
+                let Some((prefix, name)) = k.split_once(':') else { continue; };
+                if prefix == "xmlns" || prefix == "rdf" || prefix == "xml" { continue; }
+                self.push(resolve(scope, prefix), prefix, name, XmpValue::Text(v.clone()));
+            }
+            for child in element.child_elements() {
+                let value = XmpValue::from_element(child);
+                self.push(resolve(scope, child.prefix()), child.prefix(), child.local_name(), value);
+            }
+        } else {
+            for child in element.child_elements() {
+                self.collect(child, scope);
+            }
+        }
+        scope.truncate(depth);
+    }
+
+    fn push(&mut self, namespace: String, prefix: &str, name: &str, value: XmpValue) {
+        self.properties.push(XmpProperty { namespace, prefix: prefix.to_string(), name: name.to_string(), value });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    pub fn get(&self, namespace: &str, name: &str) -> Option<&XmpValue> {
+        self.properties.iter().find(|p| p.namespace == namespace && p.name == name).map(|p| &p.value)
+    }
+
+    pub fn get_text(&self, namespace: &str, name: &str) -> Option<String> {
+        self.get(namespace, name).filter(|v| v.is_editable()).map(|v| v.to_text())
+    }
+
+    // 接頭辞は既に宣言されていればそれを、なければ既知の名前空間から決める
+    pub fn set(&mut self, namespace: &str, name: &str, value: XmpValue) -> Result<(), String> {
+        if let Some(p) = self.properties.iter_mut().find(|p| p.namespace == namespace && p.name == name) {
+            p.value = value;
+            return Ok(());
+        }
+        let prefix = match self.namespaces.iter().find(|(_, uri)| uri == namespace) {
+            Some((prefix, _)) => prefix.clone(),
+            None => {
+                let prefix = default_prefix(namespace).ok_or_else(|| format!("未知の名前空間です: {}", namespace))?;
+                if self.namespaces.iter().any(|(p, _)| p == prefix) {
+                    return Err(format!("接頭辞{}が別の名前空間で使われています", prefix));
+                }
+                self.namespaces.push((prefix.to_string(), namespace.to_string()));
+                prefix.to_string()
+            }
+        };
+        self.push(namespace.to_string(), &prefix, name, value);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, namespace: &str, name: &str) -> bool {
+        let n = self.properties.len();
+        self.properties.retain(|p| !(p.namespace == namespace && p.name == name));
+        self.properties.len() != n
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut description = XmlElement {
+            name: "rdf:Description".to_string(),
+            attrs: vec![("rdf:about".to_string(), String::new())],
+            children: Vec::new(),
+        };
+        for (prefix, uri) in self.namespaces.iter() {
+            if prefix == "rdf" || prefix == "x" { continue; }
+            description.attrs.push((format!("xmlns:{}", prefix), uri.clone()));
+        }
+        for p in self.properties.iter() {
+            description.children.push(XmlNode::Element(p.value.to_element(&p.qualified_name())));
+        }
+
+        let mut out = String::new();
+        out.push_str("<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
+        out.push_str("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n");
+        out.push_str(&format!(" <rdf:RDF xmlns:rdf=\"{}\">\n", RDF_NS));
+        description.write(&mut out, 2);
+        out.push_str(" </rdf:RDF>\n");
+        out.push_str("</x:xmpmeta>\n");
+        out.push_str("<?xpacket end=\"w\"?>");
+        out
+    }
+}
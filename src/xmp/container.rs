@@ -0,0 +1,238 @@
+// JPEG/PNG/WebPの中のXMPパケットを読み書きする（HEIC, TIFFは未対応）
+
+use crate::file_type::FileType;
+use crate::jpeg;
+use crate::zip::crc32;
+
+use super::XmpPacket;
+
+pub const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const JPEG_EXTENDED_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+
+const PNG_SIGNATURE_LEN: usize = 8;
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+const WEBP_HEADER_LEN: usize = 12;
+// VP8Xのフラグ
+const WEBP_XMP_FLAG: u8 = 0x04;
+const WEBP_ALPHA_FLAG: u8 = 0x10;
+
+pub fn is_supported(file_type: FileType) -> bool {
+    matches!(file_type, FileType::JPEG | FileType::PNG | FileType::WEBP)
+}
+
+pub fn read(file_type: FileType, bytes: &[u8]) -> Option<String> {
+    let packet = match file_type {
+        FileType::JPEG => read_jpeg(bytes),
+        FileType::PNG => read_png(bytes),
+        FileType::WEBP => read_webp(bytes),
+        _ => None,
+    }?;
+    Some(String::from_utf8_lossy(packet).trim_start_matches('\u{FEFF}').to_string())
+}
+
+// packetがNoneならXMPを削除する
+pub fn write(file_type: FileType, bytes: &[u8], packet: Option<&str>) -> Result<Vec<u8>, String> {
+    match file_type {
+        FileType::JPEG => write_jpeg(bytes, packet),
+        FileType::PNG => write_png(bytes, packet),
+        FileType::WEBP => write_webp(bytes, packet),
+        _ => match packet {
+            Some(_) => Err(format!("{}のXMPには対応していません", file_type.label())),
+            None => Ok(bytes.to_vec()),
+        },
+    }
+}
+
+fn is_jpeg_xmp(marker: u8, payload: &[u8]) -> bool {
+    marker == jpeg::APP1 && (payload.starts_with(JPEG_XMP_HEADER) || payload.starts_with(JPEG_EXTENDED_XMP_HEADER))
+}
+
+fn read_jpeg(bytes: &[u8]) -> Option<&[u8]> {
+    jpeg::segments(bytes)?.iter()
+        .map(|segment| (segment.marker, segment.payload(bytes)))
+        .find(|(marker, payload)| *marker == jpeg::APP1 && payload.starts_with(JPEG_XMP_HEADER))
+        .map(|(_, payload)| &payload[JPEG_XMP_HEADER.len()..])
+}
+
+// 拡張XMP（64KBを超える部分）は書き出せないため、既存のものも合わせて削除する
+fn write_jpeg(bytes: &[u8], packet: Option<&str>) -> Result<Vec<u8>, String> {
+    let segments = match packet {
+        Some(packet) => vec![jpeg::segment(jpeg::APP1, &[JPEG_XMP_HEADER, packet.as_bytes()].concat())
+            .ok_or_else(|| "XMPが大きすぎます（約64KBまで）".to_string())?],
+        None => Vec::new(),
+    };
+    jpeg::replace_segments(bytes, is_jpeg_xmp, &segments)
+}
+
+// (種類, データの開始位置, データの長さ)
+fn png_chunks(bytes: &[u8]) -> Option<Vec<([u8; 4], usize, usize)>> {
+    if bytes.len() < PNG_SIGNATURE_LEN || FileType::detect(bytes) != Some(FileType::PNG) { return None; }
+    let mut ret = Vec::new();
+    let mut pos = PNG_SIGNATURE_LEN;
+    while pos + 12 <= bytes.len() {
+        let length = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let kind = [bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]];
+        let end = pos.checked_add(12)?.checked_add(length)?;
+        if end > bytes.len() { return None; }
+        ret.push((kind, pos + 8, length));
+        if &kind == b"IEND" { return Some(ret); }
+        pos = end;
+    }
+    // IENDがなければ途中で切れている
+    None
+}
+
+// 非圧縮のiTXtのみ読む
+fn png_xmp_text(data: &[u8]) -> Option<&[u8]> {
+    let rest = data.strip_prefix(PNG_XMP_KEYWORD)?.strip_prefix(&[0])?;
+    let (&compressed, rest) = rest.split_first()?;
+    if compressed != 0 { return None; }
+    // 圧縮方式
+    let rest = rest.get(1..)?;
+    let language_end = rest.iter().position(|b| *b == 0)?;
+    let rest = rest.get(language_end + 1..)?;
+    let translated_end = rest.iter().position(|b| *b == 0)?;
+    rest.get(translated_end + 1..)
+}
+
+fn read_png(bytes: &[u8]) -> Option<&[u8]> {
+    png_chunks(bytes)?.into_iter()
+        .filter(|(kind, _, _)| kind == b"iTXt")
+        .find_map(|(_, start, length)| png_xmp_text(&bytes[start..start + length]))
+}
+
+fn write_png(bytes: &[u8], packet: Option<&str>) -> Result<Vec<u8>, String> {
+    let chunks = png_chunks(bytes).ok_or_else(|| "PNGとして読み込めません".to_string())?;
+    let mut ret = Vec::with_capacity(bytes.len());
+    ret.extend_from_slice(&bytes[..PNG_SIGNATURE_LEN]);
+    let mut inserted = packet.is_none();
+    for (kind, start, length) in chunks.into_iter() {
+        let data = &bytes[start..start + length];
+        if &kind == b"iTXt" && data.starts_with(PNG_XMP_KEYWORD) && data.get(PNG_XMP_KEYWORD.len()) == Some(&0) {
+            continue;
+        }
+        // IDATより前に置く
+        if !inserted && (&kind == b"IDAT" || &kind == b"IEND") {
+            let mut itxt = PNG_XMP_KEYWORD.to_vec();
+            itxt.extend_from_slice(&[0, 0, 0, 0, 0]);
+            itxt.extend_from_slice(packet.unwrap_or_default().as_bytes());
+            push_png_chunk(&mut ret, b"iTXt", &itxt);
+            inserted = true;
+        }
+        ret.extend_from_slice(&bytes[start - 8..start + length + 4]);
+    }
+    Ok(ret)
+}
+
+fn push_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let crc_start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[crc_start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// (FourCC, データの開始位置, データの長さ)
+fn webp_chunks(bytes: &[u8]) -> Option<Vec<([u8; 4], usize, usize)>> {
+    if FileType::detect(bytes) != Some(FileType::WEBP) { return None; }
+    // RIFFヘッダの大きさより短ければ途中で切れている
+    let riff_end = (u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize).checked_add(8)?;
+    let bytes = bytes.get(..riff_end)?;
+    let mut ret = Vec::new();
+    let mut pos = WEBP_HEADER_LEN;
+    while pos + 8 <= bytes.len() {
+        let kind = [bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]];
+        let length = u32::from_le_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]]) as usize;
+        let end = pos.checked_add(8)?.checked_add(length)?;
+        if end > bytes.len() { return None; }
+        ret.push((kind, pos + 8, length));
+        // 奇数長のチャンクは1バイト埋める
+        pos = end + (length & 1);
+    }
+    Some(ret)
+}
+
+fn read_webp(bytes: &[u8]) -> Option<&[u8]> {
+    webp_chunks(bytes)?.into_iter()
+        .find(|(kind, _, _)| kind == b"XMP ")
+        .map(|(_, start, length)| &bytes[start..start + length])
+}
+
+fn push_webp_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(kind);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() & 1 == 1 {
+        out.push(0);
+    }
+}
+
+// 単純形式（VP8/VP8Lのみ）のWebPにメタデータを足すため、画像の大きさからVP8Xを作る
+fn webp_vp8x(kind: &[u8; 4], data: &[u8]) -> Option<[u8; 10]> {
+    let (width, height, alpha) = match kind {
+        b"VP8 " if data.len() >= 10 => (
+            (u16::from_le_bytes([data[6], data[7]]) & 0x3FFF) as u32,
+            (u16::from_le_bytes([data[8], data[9]]) & 0x3FFF) as u32,
+            false,
+        ),
+        b"VP8L" if data.len() >= 5 => {
+            let bits = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+            ((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1, bits & (1 << 28) != 0)
+        }
+        _ => return None,
+    };
+    if width == 0 || height == 0 { return None; }
+    let mut vp8x = [0u8; 10];
+    vp8x[0] = if alpha { WEBP_ALPHA_FLAG } else { 0 };
+    vp8x[4..7].copy_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x[7..10].copy_from_slice(&(height - 1).to_le_bytes()[..3]);
+    Some(vp8x)
+}
+
+fn write_webp(bytes: &[u8], packet: Option<&str>) -> Result<Vec<u8>, String> {
+    let chunks = webp_chunks(bytes).ok_or_else(|| "WebPとして読み込めません".to_string())?;
+    let mut body = Vec::with_capacity(bytes.len());
+    let has_vp8x = chunks.iter().any(|(kind, _, _)| kind == b"VP8X");
+    for (i, (kind, start, length)) in chunks.iter().enumerate() {
+        let data = &bytes[*start..*start + *length];
+        match kind {
+            b"XMP " => continue,
+            b"VP8X" => {
+                let mut vp8x = data.to_vec();
+                if let Some(flags) = vp8x.first_mut() {
+                    if packet.is_some() { *flags |= WEBP_XMP_FLAG; } else { *flags &= !WEBP_XMP_FLAG; }
+                }
+                push_webp_chunk(&mut body, kind, &vp8x);
+            }
+            _ => {
+                if i == 0 && !has_vp8x && packet.is_some() {
+                    let mut vp8x = webp_vp8x(kind, data).ok_or_else(|| "WebPの画像の大きさを読み取れません".to_string())?;
+                    vp8x[0] |= WEBP_XMP_FLAG;
+                    push_webp_chunk(&mut body, b"VP8X", &vp8x);
+                }
+                push_webp_chunk(&mut body, kind, data);
+            }
+        }
+    }
+    if let Some(packet) = packet {
+        push_webp_chunk(&mut body, b"XMP ", packet.as_bytes());
+    }
+
+    let size = u32::try_from(body.len() + 4).map_err(|_| "WebPが大きすぎます".to_string())?;
+    let mut ret = Vec::with_capacity(body.len() + WEBP_HEADER_LEN);
+    ret.extend_from_slice(b"RIFF");
+    ret.extend_from_slice(&size.to_le_bytes());
+    ret.extend_from_slice(b"WEBP");
+    ret.extend_from_slice(&body);
+    Ok(ret)
+}
+
+// 読み込み時から変わっていなければ元のXMP（解析できなかったものを含む）をそのまま残す
+pub fn write_changes(file_type: FileType, bytes: Vec<u8>, xmp: Option<&XmpPacket>, original: Option<&XmpPacket>) -> Result<Vec<u8>, String> {
+    if xmp == original {
+        return Ok(bytes);
+    }
+    write(file_type, &bytes, xmp.map(|packet| packet.serialize()).as_deref())
+}
@@ -0,0 +1,322 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use little_exif::exif_tag::ExifTag;
+
+use crate::exif::{ExifEditData, ExifTime};
+use crate::exif::exif_capture::TimeOffset;
+use crate::exif::gps::gps_ref;
+use crate::exif::tag_value::empty_tag;
+
+use super::{XmpPacket, XmpValue, DC_NS, EXIF_NS, PHOTOSHOP_NS, TIFF_NS, XMP_NS};
+
+// 緯度・経度が一致しているとみなす差（度）
+const POSITION_TOLERANCE: f64 = 1e-5;
+
+const POSITION_TAGS: &[&str] = &["GPSLatitudeRef", "GPSLatitude", "GPSLongitudeRef", "GPSLongitude"];
+
+// ExifとXMPの両方にある項目
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SyncField {
+    DateTimeOriginal,
+    CreateDate,
+    ModifyDate,
+    Position,
+    Artist,
+    Copyright,
+    Orientation,
+}
+
+impl SyncField {
+    pub fn all() -> [Self; 7] {
+        [
+            SyncField::DateTimeOriginal, SyncField::CreateDate, SyncField::ModifyDate,
+            SyncField::Position, SyncField::Artist, SyncField::Copyright, SyncField::Orientation,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncField::DateTimeOriginal => "撮影日時",
+            SyncField::CreateDate => "デジタル化日時",
+            SyncField::ModifyDate => "更新日時",
+            SyncField::Position => "緯度・経度",
+            SyncField::Artist => "作者",
+            SyncField::Copyright => "著作権",
+            SyncField::Orientation => "向き",
+        }
+    }
+
+    // XMP側のプロパティ名
+    pub fn xmp_names(&self) -> &'static str {
+        match self {
+            SyncField::DateTimeOriginal => "exif:DateTimeOriginal, photoshop:DateCreated",
+            SyncField::CreateDate => "xmp:CreateDate",
+            SyncField::ModifyDate => "xmp:ModifyDate",
+            SyncField::Position => "exif:GPSLatitude, exif:GPSLongitude",
+            SyncField::Artist => "dc:creator",
+            SyncField::Copyright => "dc:rights",
+            SyncField::Orientation => "tiff:Orientation",
+        }
+    }
+
+    fn exif_time(&self) -> Option<ExifTime> {
+        match self {
+            SyncField::DateTimeOriginal => Some(ExifTime::DateTimeOriginal),
+            SyncField::CreateDate => Some(ExifTime::CreateDate),
+            SyncField::ModifyDate => Some(ExifTime::ModifyDate),
+            _ => None,
+        }
+    }
+
+    // 比較しやすいよう、どちらもXMPの表記に揃えて返す
+    pub fn exif_value(&self, eed: &ExifEditData) -> Option<String> {
+        if let Some(time) = self.exif_time() {
+            let ndt = eed.pick_time(time)?;
+            return Some(format_xmp_date(&ndt, eed.pick_offset(time).as_ref()));
+        }
+        match self {
+            SyncField::Position => eed.position().map(|(lat, lng)| format!("{:.6}, {:.6}", lat, lng)),
+            SyncField::Artist => eed.user_info.artist.clone(),
+            SyncField::Copyright => eed.user_info.copyright.clone(),
+            SyncField::Orientation => eed.basic_image_info.image_format.orientation.as_ref()
+                .and_then(|o| o.to_vec().first().map(|v| v.to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn xmp_value(&self, packet: &XmpPacket) -> Option<String> {
+        match self {
+            SyncField::DateTimeOriginal => packet.get_text(EXIF_NS, "DateTimeOriginal")
+                .or_else(|| packet.get_text(PHOTOSHOP_NS, "DateCreated")),
+            SyncField::CreateDate => packet.get_text(XMP_NS, "CreateDate"),
+            SyncField::ModifyDate => packet.get_text(XMP_NS, "ModifyDate"),
+            SyncField::Position => {
+                let lat = parse_xmp_coordinate(&packet.get_text(EXIF_NS, "GPSLatitude")?)?;
+                let lng = parse_xmp_coordinate(&packet.get_text(EXIF_NS, "GPSLongitude")?)?;
+                Some(format!("{:.6}, {:.6}", lat, lng))
+            }
+            // 複数の作者はExifのArtistと同じく"; "で区切る
+            SyncField::Artist => match packet.get(DC_NS, "creator")? {
+                XmpValue::Seq(v) | XmpValue::Bag(v) => Some(v.join("; ")),
+                value => Some(value.to_text()),
+            },
+            SyncField::Copyright => packet.get_text(DC_NS, "rights"),
+            SyncField::Orientation => packet.get_text(TIFF_NS, "Orientation"),
+        }.filter(|s| !s.trim().is_empty())
+    }
+
+    // 日時は、タイムゾーンが片方にしかなければ日時だけを比べる
+    pub fn in_sync(&self, eed: &ExifEditData, packet: &XmpPacket) -> bool {
+        match (self.exif_value(eed), self.xmp_value(packet)) {
+            (None, None) => true,
+            (Some(a), Some(b)) => match self {
+                _ if self.exif_time().is_some() => match (parse_xmp_date(&a), parse_xmp_date(&b)) {
+                    (Some((n1, o1)), Some((n2, o2))) => n1 == n2 && (o1.is_none() || o2.is_none() || o1 == o2),
+                    _ => a == b,
+                },
+                SyncField::Position => match (parse_position(&a), parse_position(&b)) {
+                    (Some(p1), Some(p2)) => (p1.0 - p2.0).abs() < POSITION_TOLERANCE && (p1.1 - p2.1).abs() < POSITION_TOLERANCE,
+                    _ => a == b,
+                },
+                _ => a.trim() == b.trim(),
+            },
+            _ => false,
+        }
+    }
+
+    // Exifの値をXMPに書く。Exifに値がなければXMPからも削除する
+    pub fn to_xmp(&self, eed: &ExifEditData, packet: &mut XmpPacket) -> Result<(), String> {
+        let value = self.exif_value(eed);
+        match self {
+            SyncField::DateTimeOriginal => {
+                // photoshop:DateCreatedは既にある場合だけ合わせる
+                if packet.get(PHOTOSHOP_NS, "DateCreated").is_some() {
+                    set_text(packet, PHOTOSHOP_NS, "DateCreated", value.clone())?;
+                }
+                set_text(packet, EXIF_NS, "DateTimeOriginal", value)
+            }
+            SyncField::CreateDate => set_text(packet, XMP_NS, "CreateDate", value),
+            SyncField::ModifyDate => set_text(packet, XMP_NS, "ModifyDate", value),
+            SyncField::Position => {
+                let (lat, lng) = match eed.position() {
+                    Some((lat, lng)) => (Some(format_xmp_coordinate(lat, true)), Some(format_xmp_coordinate(lng, false))),
+                    None => (None, None),
+                };
+                set_text(packet, EXIF_NS, "GPSLatitude", lat)?;
+                set_text(packet, EXIF_NS, "GPSLongitude", lng)
+            }
+            SyncField::Artist => match value {
+                Some(s) => packet.set(DC_NS, "creator", XmpValue::Seq(
+                    s.split(';').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect()
+                )),
+                None => {
+                    packet.remove(DC_NS, "creator");
+                    Ok(())
+                }
+            },
+            SyncField::Copyright => match value {
+                Some(s) => {
+                    let rights = packet.get(DC_NS, "rights").cloned()
+                        .filter(|v| matches!(v, XmpValue::Alt(_)))
+                        .unwrap_or(XmpValue::Alt(Vec::new()));
+                    packet.set(DC_NS, "rights", rights.with_text(&s).unwrap_or(XmpValue::Alt(vec![("x-default".to_string(), s)])))
+                }
+                None => {
+                    packet.remove(DC_NS, "rights");
+                    Ok(())
+                }
+            },
+            SyncField::Orientation => set_text(packet, TIFF_NS, "Orientation", value),
+        }
+    }
+
+    // XMPの値をExifに書く。XMPに値がなければExifからも削除する
+    pub fn to_exif(&self, packet: &XmpPacket, eed: &mut ExifEditData) -> Result<(), String> {
+        let value = self.xmp_value(packet);
+        if let Some(time) = self.exif_time() {
+            match value {
+                Some(s) => {
+                    let (ndt, offset) = parse_xmp_date(&s).ok_or_else(|| format!("日時として読めません: {}", s))?;
+                    eed.update_tag(time.to_tag(&ndt));
+                    if let Some(offset) = offset {
+                        eed.update_tag(time.offset_tag(&offset));
+                    }
+                }
+                None => {
+                    eed.delete_tag(time.to_tag(&NaiveDateTime::default()));
+                    eed.delete_tag(time.offset_tag(&TimeOffset { sign: true, hour: 0, minute: 0 }));
+                }
+            }
+            return Ok(());
+        }
+        match self {
+            SyncField::Position => match value.as_deref().map(parse_position) {
+                Some(Some((lat, lng))) => {
+                    if !eed.set_lat_lng(lat, lng) {
+                        return Err("緯度・経度が範囲外です".to_string());
+                    }
+                }
+                Some(None) => return Err("緯度・経度として読めません".to_string()),
+                None => {
+                    for name in POSITION_TAGS.iter() {
+                        if let Some(tag) = empty_tag(name) {
+                            eed.delete_tag(tag);
+                        }
+                    }
+                }
+            },
+            SyncField::Artist => match value {
+                Some(s) => eed.update_tag(ExifTag::Artist(s)),
+                None => eed.delete_tag(ExifTag::Artist(String::new())),
+            },
+            SyncField::Copyright => match value {
+                Some(s) => eed.update_tag(ExifTag::Copyright(s)),
+                None => eed.delete_tag(ExifTag::Copyright(String::new())),
+            },
+            SyncField::Orientation => match value {
+                Some(s) => {
+                    let v = s.trim().parse::<u16>().ok()
+                        .filter(|v| (1..=8).contains(v))
+                        .ok_or_else(|| format!("向きとして読めません: {}", s))?;
+                    eed.update_tag(ExifTag::Orientation(vec![v]));
+                }
+                None => eed.delete_tag(ExifTag::Orientation(Vec::new())),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl XmpPacket {
+    pub fn conflicts(&self, eed: &ExifEditData) -> Vec<SyncField> {
+        SyncField::all().into_iter().filter(|field| !field.in_sync(eed, self)).collect()
+    }
+
+    // 食い違う項目をすべてExifに合わせ、合わせた項目の数を返す
+    pub fn sync_from_exif(&mut self, eed: &ExifEditData) -> Result<usize, String> {
+        let conflicts = self.conflicts(eed);
+        for field in conflicts.iter() {
+            field.to_xmp(eed, self)?;
+        }
+        Ok(conflicts.len())
+    }
+}
+
+fn set_text(packet: &mut XmpPacket, namespace: &str, name: &str, value: Option<String>) -> Result<(), String> {
+    match value {
+        Some(s) => packet.set(namespace, name, XmpValue::Text(s)),
+        None => {
+            packet.remove(namespace, name);
+            Ok(())
+        }
+    }
+}
+
+pub fn format_xmp_date(ndt: &NaiveDateTime, offset: Option<&TimeOffset>) -> String {
+    let s = ndt.format("%Y-%m-%dT%H:%M:%S").to_string();
+    match offset {
+        Some(offset) => format!("{}{}", s, offset.to_string()),
+        None => s,
+    }
+}
+
+// XMPの日時（YYYY, YYYY-MM-DD, YYYY-MM-DDThh:mm[:ss[.s]][TZD]）を読む。日付だけなら0時とする
+pub fn parse_xmp_date(s: &str) -> Option<(NaiveDateTime, Option<TimeOffset>)> {
+    let s = s.trim();
+    let (date, time) = match s.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let mut parts = date.split('-');
+    let year = parts.next()?.parse::<i32>().ok()?;
+    let month = parts.next().map(|m| m.parse::<u32>().ok()).unwrap_or(Some(1))?;
+    let day = parts.next().map(|d| d.parse::<u32>().ok()).unwrap_or(Some(1))?;
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+
+    let Some(time) = time else { return Some((date.and_hms_opt(0, 0, 0)?, None)); };
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, TimeOffset::from_str("+00:00"))
+    } else {
+        match time.rfind(|c: char| c == '+' || c == '-') {
+            Some(i) => (&time[..i], Some(TimeOffset::from_str(&time[i..])?)),
+            None => (time, None),
+        }
+    };
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()?;
+    Some((date.and_time(time), offset))
+}
+
+// XMPの座標（"DDD,MM.mmk" または "DDD,MM,SSk"）を符号付きの度にする
+pub fn parse_xmp_coordinate(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let hemisphere = s.chars().last()?;
+    let positive = gps_ref(&hemisphere.to_string())?;
+    let body = &s[..s.len() - hemisphere.len_utf8()];
+    let parts = body.split(',').map(|p| p.trim().parse::<f64>().ok()).collect::<Option<Vec<f64>>>()?;
+    let value = match parts.as_slice() {
+        [d, m] => d + m / 60.0,
+        [d, m, sec] => d + m / 60.0 + sec / 3600.0,
+        _ => return None,
+    };
+    Some(if positive { value } else { -value })
+}
+
+pub fn format_xmp_coordinate(value: f64, is_latitude: bool) -> String {
+    let hemisphere = match (is_latitude, value >= 0.0) {
+        (true, true) => "N",
+        (true, false) => "S",
+        (false, true) => "E",
+        (false, false) => "W",
+    };
+    // 分を丸めて60になる場合は度に繰り上げる
+    let micro_minutes = (value.abs() * 60.0 * 1e6).round() as u64;
+    let (degree, minute) = (micro_minutes / 60_000_000, micro_minutes % 60_000_000);
+    format!("{},{:.6}{}", degree, minute as f64 / 1e6, hemisphere)
+}
+
+fn parse_position(s: &str) -> Option<(f64, f64)> {
+    let (lat, lng) = s.split_once(',')?;
+    Some((lat.trim().parse().ok()?, lng.trim().parse().ok()?))
+}
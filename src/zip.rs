@@ -0,0 +1,138 @@
+use std::fmt;
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL: u32 = 0x06054b50;
+// ファイル名をUTF-8として扱うフラグ
+const FLAG_UTF8: u16 = 0x0800;
+const LOCAL_HEADER_LEN: usize = 30;
+const CENTRAL_HEADER_LEN: usize = 46;
+const END_OF_CENTRAL_LEN: usize = 22;
+
+// ZIP64には対応していないため、4GiB以上のZIPや65535個以上のファイルは作れない
+#[derive(Clone, Debug, PartialEq)]
+pub enum ZipError {
+    TooLarge,
+    TooManyFiles,
+    NameTooLong,
+}
+
+impl fmt::Display for ZipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZipError::TooLarge => write!(f, "ZIPファイルが4GiBを超えるため追加できません"),
+            ZipError::TooManyFiles => write!(f, "ZIPファイルに入れられるファイルは65535個までです"),
+            ZipError::NameTooLong => write!(f, "ファイル名が長すぎます"),
+        }
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, t) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *t = c;
+    }
+    let mut crc = 0xFFFFFFFFu32;
+    for b in data {
+        crc = table[((crc ^ *b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFFFFFF
+}
+
+// 画像は既に圧縮済みなので、無圧縮（stored）のZIPだけを作る
+pub struct ZipWriter {
+    buf: Vec<u8>,
+    central: Vec<u8>,
+    count: u16,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+impl ZipWriter {
+    pub fn new(modified: NaiveDateTime) -> Self {
+        let dos_time = ((modified.hour() << 11) | (modified.minute() << 5) | (modified.second() / 2)) as u16;
+        let dos_date = (((modified.year().max(1980) - 1980) as u32) << 9 | (modified.month() << 5) | modified.day()) as u16;
+        Self { buf: Vec::new(), central: Vec::new(), count: 0, dos_time, dos_date }
+    }
+
+    // 追加できないファイルはErrを返し、それまでに追加したファイルはそのまま残す
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<(), ZipError> {
+        let name_len = u16::try_from(name.len()).map_err(|_| ZipError::NameTooLong)?;
+        let count = self.count.checked_add(1).ok_or(ZipError::TooManyFiles)?;
+        // 追加した後もfinishで中央ディレクトリを書けるよう、全体の大きさで確かめる
+        let total = [self.buf.len(), self.central.len(), LOCAL_HEADER_LEN + CENTRAL_HEADER_LEN + END_OF_CENTRAL_LEN, name.len() * 2, data.len()]
+            .into_iter()
+            .try_fold(0usize, |acc, len| acc.checked_add(len))
+            .ok_or(ZipError::TooLarge)?;
+        if u32::try_from(total).is_err() {
+            return Err(ZipError::TooLarge);
+        }
+        let offset = self.buf.len() as u32;
+        let size = data.len() as u32;
+        self.count = count;
+        let crc = crc32(data);
+
+        push_u32(&mut self.buf, LOCAL_HEADER);
+        push_u16(&mut self.buf, 20);
+        push_u16(&mut self.buf, FLAG_UTF8);
+        push_u16(&mut self.buf, 0);
+        push_u16(&mut self.buf, self.dos_time);
+        push_u16(&mut self.buf, self.dos_date);
+        push_u32(&mut self.buf, crc);
+        push_u32(&mut self.buf, size);
+        push_u32(&mut self.buf, size);
+        push_u16(&mut self.buf, name_len);
+        push_u16(&mut self.buf, 0);
+        self.buf.extend_from_slice(name.as_bytes());
+        self.buf.extend_from_slice(data);
+
+        push_u32(&mut self.central, CENTRAL_HEADER);
+        push_u16(&mut self.central, 20);
+        push_u16(&mut self.central, 20);
+        push_u16(&mut self.central, FLAG_UTF8);
+        push_u16(&mut self.central, 0);
+        push_u16(&mut self.central, self.dos_time);
+        push_u16(&mut self.central, self.dos_date);
+        push_u32(&mut self.central, crc);
+        push_u32(&mut self.central, size);
+        push_u32(&mut self.central, size);
+        push_u16(&mut self.central, name_len);
+        push_u16(&mut self.central, 0);
+        push_u16(&mut self.central, 0);
+        push_u16(&mut self.central, 0);
+        push_u16(&mut self.central, 0);
+        push_u32(&mut self.central, 0);
+        push_u32(&mut self.central, offset);
+        self.central.extend_from_slice(name.as_bytes());
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, ZipError> {
+        let central_offset = u32::try_from(self.buf.len()).map_err(|_| ZipError::TooLarge)?;
+        let central_size = u32::try_from(self.central.len()).map_err(|_| ZipError::TooLarge)?;
+        self.buf.extend_from_slice(&self.central);
+        push_u32(&mut self.buf, END_OF_CENTRAL);
+        push_u16(&mut self.buf, 0);
+        push_u16(&mut self.buf, 0);
+        push_u16(&mut self.buf, self.count);
+        push_u16(&mut self.buf, self.count);
+        push_u32(&mut self.buf, central_size);
+        push_u32(&mut self.buf, central_offset);
+        push_u16(&mut self.buf, 0);
+        Ok(self.buf)
+    }
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,31 @@
+use chrono::Duration;
+use exif_edit::exif::clock::{format_shift, parse_shift};
+
+#[test]
+fn parse_shift_formats() {
+    assert_eq!(parse_shift("+01:30:00"), Some(Duration::minutes(90)));
+    assert_eq!(parse_shift("-2:00"), Some(Duration::hours(-2)));
+    assert_eq!(parse_shift("+1d 03:00:00"), Some(Duration::hours(27)));
+    assert_eq!(parse_shift("+1h 23m 5s"), Some(Duration::seconds(4985)));
+    assert_eq!(parse_shift("-30m"), Some(Duration::minutes(-30)));
+    assert_eq!(parse_shift("1:2:3:4"), None);
+    assert_eq!(parse_shift("5"), None);
+    assert_eq!(parse_shift("abc"), None);
+}
+
+#[test]
+fn parse_shift_overflow() {
+    // Durationに収まらない値や、i64の計算があふれる値は読み取れないものとして扱う
+    assert_eq!(parse_shift("+10000000000000000s"), None);
+    assert_eq!(parse_shift("+200000000000d"), None);
+    assert_eq!(parse_shift("-9223372036854775807h"), None);
+    assert_eq!(parse_shift("+9223372036854775807:00"), None);
+    assert_eq!(parse_shift("+200000000000d 00:00"), None);
+    assert_eq!(parse_shift("+9000000000000000s 9000000000000000s"), None);
+}
+
+#[test]
+fn format_parsed_shift() {
+    let delta = parse_shift("-1d 2h 3m 4s").unwrap();
+    assert_eq!(format_shift(&delta), "-26:03:04");
+}
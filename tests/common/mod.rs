@@ -0,0 +1,112 @@
+// 読み書きのテスト用に、最小限のJPEG/PNG/WebP/HEIFを手で組み立てる
+#![allow(dead_code)]
+
+// 画像データの代わりに使うバイト列。0xFF00（スタッフィング）とRST0を含める
+pub const SCAN_DATA: &[u8] = &[0x12, 0x34, 0xFF, 0x00, 0x56, 0xFF, 0xD0, 0x78, 0x9A];
+
+pub fn jpeg_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+    let mut ret = vec![0xFF, marker];
+    ret.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    ret.extend_from_slice(payload);
+    ret
+}
+
+pub fn jfif_segment() -> Vec<u8> {
+    jpeg_segment(0xE0, b"JFIF\0\x01\x02\0\0\x01\0\x01\0\0")
+}
+
+// SOI, 与えたセグメント, DQT, SOF0, DHT, SOS, 画像データ, EOI
+pub fn jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+    let mut ret = vec![0xFF, 0xD8];
+    for segment in segments.iter() {
+        ret.extend_from_slice(segment);
+    }
+    ret.extend_from_slice(&jpeg_segment(0xDB, &[0u8; 65]));
+    ret.extend_from_slice(&jpeg_segment(0xC0, &[8, 0, 16, 0, 16, 1, 1, 0x11, 0]));
+    ret.extend_from_slice(&jpeg_segment(0xC4, &[0u8; 17]));
+    ret.extend_from_slice(&jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+    ret.extend_from_slice(SCAN_DATA);
+    ret.extend_from_slice(&[0xFF, 0xD9]);
+    ret
+}
+
+// SOSの直後からEOIまで
+pub fn scan_data(jpeg: &[u8]) -> &[u8] {
+    let sos = jpeg.windows(2).position(|w| w == [0xFF, 0xDA]).expect("SOS");
+    let length = u16::from_be_bytes([jpeg[sos + 2], jpeg[sos + 3]]) as usize;
+    let start = sos + 2 + length;
+    let end = jpeg.windows(2).rposition(|w| w == [0xFF, 0xD9]).expect("EOI");
+    &jpeg[start..end]
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+pub fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut ret = (data.len() as u32).to_be_bytes().to_vec();
+    ret.extend_from_slice(kind);
+    ret.extend_from_slice(data);
+    ret.extend_from_slice(&crc32(&ret[4..]).to_be_bytes());
+    ret
+}
+
+// IHDR, 与えたチャンク, IDAT, IEND
+pub fn png(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut ret = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    ret.extend_from_slice(&png_chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0]));
+    for chunk in chunks.iter() {
+        ret.extend_from_slice(chunk);
+    }
+    ret.extend_from_slice(&png_chunk(b"IDAT", &[0x78, 0x9C, 0x63, 0x60, 0x60, 0x60, 0, 0, 0, 4, 0, 1]));
+    ret.extend_from_slice(&png_chunk(b"IEND", &[]));
+    ret
+}
+
+pub fn webp_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut ret = kind.to_vec();
+    ret.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    ret.extend_from_slice(data);
+    if data.len() & 1 == 1 {
+        ret.push(0);
+    }
+    ret
+}
+
+pub fn webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut ret = b"RIFF".to_vec();
+    ret.extend_from_slice(&((body.len() + 4) as u32).to_le_bytes());
+    ret.extend_from_slice(b"WEBP");
+    ret.extend_from_slice(&body);
+    ret
+}
+
+// 幅width・高さheightのVP8Lチャンク（ヘッダのみ）
+pub fn vp8l_chunk(width: u32, height: u32) -> Vec<u8> {
+    let bits = (width - 1) | ((height - 1) << 14);
+    let mut data = vec![0x2F];
+    data.extend_from_slice(&bits.to_le_bytes());
+    data.extend_from_slice(&[0x00, 0x11, 0x22]);
+    webp_chunk(b"VP8L", &data)
+}
+
+pub fn heif_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut ret = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    ret.extend_from_slice(kind);
+    ret.extend_from_slice(body);
+    ret
+}
+
+pub fn heif_full_box(kind: &[u8; 4], version: u8, body: &[u8]) -> Vec<u8> {
+    let mut full = vec![version, 0, 0, 0];
+    full.extend_from_slice(body);
+    heif_box(kind, &full)
+}
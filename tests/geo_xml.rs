@@ -0,0 +1,31 @@
+use exif_edit::geo::xml::{escape, unescape};
+
+#[test]
+fn unescape_entities() {
+    assert_eq!(unescape("a &lt;b&gt; &quot;c&quot; &apos;d&apos; &amp; e"), "a <b> \"c\" 'd' & e");
+    assert_eq!(unescape("&#10;&#x3042;&#X3044;"), "\nあい");
+}
+
+#[test]
+fn unescape_once() {
+    // 1回だけ戻す
+    assert_eq!(unescape("&amp;lt;"), "&lt;");
+    assert_eq!(unescape("&#38;lt;"), "&lt;");
+    assert_eq!(unescape("&amp;#60;"), "&#60;");
+    assert_eq!(unescape("&amp;amp;"), "&amp;");
+}
+
+#[test]
+fn unescape_invalid() {
+    // 参照として読めないものはそのまま残す
+    assert_eq!(unescape("a & b"), "a & b");
+    assert_eq!(unescape("&unknown; &#xZZ; &#1114112; &"), "&unknown; &#xZZ; &#1114112; &");
+    assert_eq!(unescape("&lt"), "&lt");
+}
+
+#[test]
+fn escape_roundtrip() {
+    for s in ["<tag attr=\"v\">", "Tom & Jerry's", "&lt;", "&#38;", "日本語"] {
+        assert_eq!(unescape(&escape(s)), s);
+    }
+}
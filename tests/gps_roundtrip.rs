@@ -0,0 +1,159 @@
+use exif_edit::exif::ExifEditData;
+use exif_edit::exif::gps::{gps_ref, Axis, DMS, SECONDS_DENOMINATOR};
+use exif_edit::exif::import::parse_document;
+use exif_edit::jpeg;
+
+// 再現性のため、シードを固定したxorshift64を使う
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        min + (max - min) * unit
+    }
+}
+
+// 秒を1/SECONDS_DENOMINATORで丸めたときの最大誤差（度）
+const TOLERANCE: f64 = 1.0 / (2.0 * SECONDS_DENOMINATOR as f64 * 3600.0) + 1e-12;
+
+fn roundtrip(lat: f64, lng: f64) -> (f64, f64) {
+    let mut eed = ExifEditData::empty();
+    assert!(eed.set_lat_lng(lat, lng), "set_lat_lng({}, {})", lat, lng);
+    let tiff = jpeg::metadata_to_tiff(&eed.metadata).expect("metadata_to_tiff");
+    let metadata = jpeg::metadata_from_tiff(&tiff).expect("metadata_from_tiff");
+    ExifEditData::new(&metadata).position().expect("position")
+}
+
+fn assert_dms_normalized(value: f64) {
+    let v = DMS::from_f64(value, None).unwrap().to_vec();
+    let minute = v[1].nominator as f64 / v[1].denominator as f64;
+    let second = v[2].nominator as f64 / v[2].denominator as f64;
+    assert!(minute < 60.0, "{}: minute {}", value, minute);
+    assert!(second < 60.0, "{}: second {}", value, second);
+}
+
+#[test]
+fn random_positions_roundtrip() {
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..500 {
+        let lat = rng.range(-90.0, 90.0);
+        let lng = rng.range(-180.0, 180.0);
+        let (lat2, lng2) = roundtrip(lat, lng);
+        assert!((lat - lat2).abs() <= TOLERANCE, "lat {} -> {}", lat, lat2);
+        assert!((lng - lng2).abs() <= TOLERANCE, "lng {} -> {}", lng, lng2);
+    }
+}
+
+#[test]
+fn boundary_positions_roundtrip() {
+    for &(lat, lng) in &[
+        (0.0, 0.0),
+        (90.0, 180.0),
+        (-90.0, -180.0),
+        (35.681236, 139.767125),
+        (-33.856784, 151.215297),
+        (40.689247, -74.044502),
+        (-22.951916, -43.210487),
+    ] {
+        let (lat2, lng2) = roundtrip(lat, lng);
+        assert!((lat - lat2).abs() <= TOLERANCE, "lat {} -> {}", lat, lat2);
+        assert!((lng - lng2).abs() <= TOLERANCE, "lng {} -> {}", lng, lng2);
+    }
+}
+
+#[test]
+fn seconds_and_minutes_never_reach_sixty() {
+    let mut rng = XorShift(0x0123_4567_89AB_CDEF);
+    let eps = 1.0 / (SECONDS_DENOMINATOR as f64 * 3600.0 * 4.0);
+    for _ in 0..500 {
+        assert_dms_normalized(rng.range(-180.0, 180.0));
+    }
+    // 分や度の切れ目のすぐ手前の値は、丸めると繰り上がる
+    for degree in 0..180 {
+        for minute in 0..60 {
+            let value = degree as f64 + minute as f64 / 60.0;
+            assert_dms_normalized(value);
+            assert_dms_normalized(value - eps);
+            assert_dms_normalized(-(value - eps));
+        }
+    }
+    let v = DMS::from_f64(34.999_999_99, None).unwrap().to_vec();
+    assert_eq!((v[0].nominator, v[1].nominator, v[2].nominator), (35, 0, 0));
+}
+
+#[test]
+fn sign_maps_to_hemisphere() {
+    for &(value, lat_ref, lng_ref) in &[(12.5, "N", "E"), (-12.5, "S", "W"), (0.0, "N", "E")] {
+        assert_eq!(Axis::Latitude.encode(value, None).unwrap().0, lat_ref);
+        assert_eq!(Axis::Longitude.encode(value, None).unwrap().0, lng_ref);
+    }
+
+    let (lat, lng) = roundtrip(-12.5, -45.25);
+    assert!(lat < 0.0 && lng < 0.0);
+    let (lat, lng) = roundtrip(12.5, -45.25);
+    assert!(lat > 0.0 && lng < 0.0);
+    let (lat, lng) = roundtrip(-12.5, 45.25);
+    assert!(lat < 0.0 && lng > 0.0);
+
+    let (_, v) = Axis::Longitude.encode(-45.25, None).unwrap();
+    assert_eq!(Axis::Longitude.decode("W", &v), Some(-45.25));
+    assert_eq!(Axis::Longitude.decode("E", &v), Some(45.25));
+}
+
+#[test]
+fn invalid_refs_are_rejected() {
+    assert_eq!(gps_ref("N"), Some(true));
+    assert_eq!(gps_ref("E"), Some(true));
+    assert_eq!(gps_ref("S"), Some(false));
+    assert_eq!(gps_ref("W"), Some(false));
+    for s in ["", " ", "X", "NE", "north", "0"] {
+        assert_eq!(gps_ref(s), None, "{:?}", s);
+    }
+    let (_, v) = Axis::Latitude.encode(10.0, None).unwrap();
+    assert_eq!(Axis::Latitude.decode("X", &v), None);
+}
+
+#[test]
+fn out_of_range_values_are_rejected() {
+    assert!(Axis::Latitude.encode(90.000_001, None).is_none());
+    assert!(Axis::Latitude.encode(-90.5, None).is_none());
+    assert!(Axis::Longitude.encode(180.000_001, None).is_none());
+    assert!(Axis::Longitude.encode(-181.0, None).is_none());
+    assert!(Axis::Latitude.encode(f64::NAN, None).is_none());
+    assert!(Axis::Longitude.encode(f64::INFINITY, None).is_none());
+
+    let mut eed = ExifEditData::empty();
+    assert!(!eed.set_lat_lng(91.0, 0.0));
+    assert!(!eed.set_lat_lng(0.0, 200.0));
+    assert!(eed.position().is_none());
+}
+
+#[test]
+fn exiftool_decimal_coordinates() {
+    // exiftool -j -nの緯度・経度は符号付きの度
+    let document = parse_document(r#"[{"SourceFile": "a.jpg", "GPSLatitude": -33.8568, "GPSLongitude": 151.2153}]"#).unwrap();
+    let mut eed = ExifEditData::empty();
+    let report = eed.import_entries(&document);
+    assert!(report.invalid.is_empty());
+    let (lat, lng) = eed.position().unwrap();
+    assert!((lat + 33.8568).abs() <= TOLERANCE && (lng - 151.2153).abs() <= TOLERANCE);
+
+    // 符号のない値に参照のタグが添えられていれば、参照のタグに従う
+    let document = parse_document(r#"{"GPSLatitudeRef": "S", "GPSLatitude": 33.8568, "GPSLongitude": 151.2153}"#).unwrap();
+    let mut eed = ExifEditData::empty();
+    eed.import_entries(&document);
+    assert!(eed.position().unwrap().0 < 0.0);
+
+    let document = parse_document(r#"{"GPSLatitude": -91.0, "GPSLongitude": 0}"#).unwrap();
+    let report = ExifEditData::empty().import_entries(&document);
+    assert_eq!(report.invalid.len(), 1);
+}
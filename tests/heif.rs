@@ -0,0 +1,158 @@
+mod common;
+
+use common::*;
+use exif_edit::heif::{is_heif, read_exif, write_exif};
+
+const IMAGE_DATA: &[u8] = b"hevc image data";
+const TIFF: &[u8] = b"II*\0\x08\0\0\0\0\0\0\0\0\0";
+
+fn be_u16(data: &[u8], pos: usize) -> usize {
+    u16::from_be_bytes([data[pos], data[pos + 1]]) as usize
+}
+
+fn be_u32(data: &[u8], pos: usize) -> usize {
+    u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize
+}
+
+// 画像アイテム（ID 1）だけを持つHEIC。画像データはmdatに置き、ilocで絶対位置を指す
+fn heic() -> Vec<u8> {
+    heif_with(b"heic\0\0\0\0mif1heic", None)
+}
+
+// ftypの中身と、ilocのbase_offset（指定すれば8バイトで書く）を変えたHEIF
+fn heif_with(ftyp: &[u8], base_offset: Option<u64>) -> Vec<u8> {
+    let ftyp = heif_box(b"ftyp", ftyp);
+    let meta = |offset: u32| {
+        let hdlr = heif_full_box(b"hdlr", 0, b"\0\0\0\0pict\0\0\0\0\0\0\0\0\0\0\0\0\0");
+        let pitm = heif_full_box(b"pitm", 0, &[0, 1]);
+        let mut iinf = vec![0, 1];
+        iinf.extend(heif_full_box(b"infe", 2, b"\0\x01\0\0hvc1\0"));
+        let mut iloc = vec![0x44, if base_offset.is_some() { 0x80 } else { 0x00 }, 0, 1, 0, 1, 0, 0, 0, 0];
+        if let Some(base_offset) = base_offset {
+            iloc.extend_from_slice(&base_offset.to_be_bytes());
+        }
+        iloc.extend_from_slice(&[0, 1]);
+        iloc.extend_from_slice(&offset.to_be_bytes());
+        iloc.extend_from_slice(&(IMAGE_DATA.len() as u32).to_be_bytes());
+        heif_full_box(b"meta", 0, &[hdlr, pitm, heif_full_box(b"iinf", 0, &iinf), heif_full_box(b"iloc", 1, &iloc)].concat())
+    };
+    let offset = ftyp.len() + meta(0).len() + 8;
+    [ftyp, meta(offset as u32), heif_box(b"mdat", IMAGE_DATA)].concat()
+}
+
+// 各ボックスを順にたどり、metaの子ボックスの範囲を返す
+fn meta_child(data: &[u8], kind: &[u8; 4]) -> (usize, usize) {
+    let mut pos = 0;
+    while &data[pos + 4..pos + 8] != b"meta" {
+        pos += be_u32(data, pos);
+    }
+    let end = pos + be_u32(data, pos);
+    pos += 12;
+    while pos < end {
+        let size = be_u32(data, pos);
+        if &data[pos + 4..pos + 8] == kind {
+            return (pos, pos + size);
+        }
+        pos += size;
+    }
+    panic!("{} not found", String::from_utf8_lossy(kind));
+}
+
+// 書き出したiloc（version 1以上、offset・lengthは4バイト、base_offsetとindexなし）から、アイテムの範囲を読む
+fn item_extent(data: &[u8], item_id: usize) -> (usize, usize) {
+    let (start, _) = meta_child(data, b"iloc");
+    let version = data[start + 8];
+    assert_eq!([data[start + 12], data[start + 13]], [0x44, 0x00]);
+    let id_size = if version < 2 { 2 } else { 4 };
+    let read_id = |pos: usize| if id_size == 2 { be_u16(data, pos) } else { be_u32(data, pos) };
+    let count = read_id(start + 14);
+    let mut pos = start + 14 + id_size;
+    for _ in 0..count {
+        let id = read_id(pos);
+        pos += id_size + 4;
+        let extent_count = be_u16(data, pos);
+        pos += 2;
+        if id == item_id {
+            assert_eq!(extent_count, 1);
+            return (be_u32(data, pos), be_u32(data, pos + 4));
+        }
+        pos += extent_count * 8;
+    }
+    panic!("item {} not found", item_id);
+}
+
+fn image_data(data: &[u8]) -> &[u8] {
+    let (offset, length) = item_extent(data, 1);
+    &data[offset..offset + length]
+}
+
+#[test]
+fn fixture_is_heif() {
+    let data = heic();
+    assert!(is_heif(&data));
+    assert_eq!(read_exif(&data), Ok(None));
+}
+
+#[test]
+fn add_exif_item() {
+    let data = heic();
+    let written = write_exif(&data, TIFF).unwrap();
+
+    assert_eq!(read_exif(&written), Ok(Some(TIFF.to_vec())));
+    // metaが大きくなった分、画像アイテムの位置もずらしてある
+    assert_eq!(image_data(&written), IMAGE_DATA);
+
+    let (iinf, iinf_end) = meta_child(&written, b"iinf");
+    assert_eq!(be_u16(&written, iinf + 12), 2);
+    assert!(written[iinf..iinf_end].windows(4).any(|w| w == b"Exif"));
+    // Exifアイテムから画像アイテムへのcdsc参照を追加する
+    let (iref, iref_end) = meta_child(&written, b"iref");
+    assert!(written[iref..iref_end].windows(4).any(|w| w == b"cdsc"));
+}
+
+#[test]
+fn replace_exif_item() {
+    let first = write_exif(&heic(), TIFF).unwrap();
+    let tiff = b"MM\0*\0\0\0\x08\0\0\0\0\0\0\0\0\0\0".to_vec();
+    let second = write_exif(&first, &tiff).unwrap();
+
+    assert_eq!(read_exif(&second), Ok(Some(tiff)));
+    assert_eq!(image_data(&second), IMAGE_DATA);
+    // アイテムは増やさず、古いExifデータは残さない
+    let (iinf, _) = meta_child(&second, b"iinf");
+    assert_eq!(be_u16(&second, iinf + 12), 2);
+    assert!(!second.windows(TIFF.len()).any(|w| w == TIFF));
+}
+
+#[test]
+fn heic_brands_only() {
+    assert!(is_heif(&heif_with(b"mif1\0\0\0\0mif1heic", None)));
+    assert!(!is_heif(&heif_with(b"avif\0\0\0\0mif1avif", None)));
+    assert!(!is_heif(&heif_with(b"mif1\0\0\0\0mif1avif", None)));
+    assert!(!is_heif(&heif_with(b"msf1\0\0\0\0msf1hevc", None)));
+}
+
+#[test]
+fn overflowing_sizes() {
+    // 64ビットのボックスサイズがファイルの位置に足すとあふれる
+    let mut data = heic();
+    let mut huge = vec![0, 0, 0, 1];
+    huge.extend_from_slice(b"free");
+    huge.extend_from_slice(&u64::MAX.to_be_bytes());
+    data.extend_from_slice(&huge);
+    assert!(read_exif(&data).is_err());
+    assert!(write_exif(&data, TIFF).is_err());
+
+    // ilocのbase_offsetとoffsetの和があふれる
+    let data = heif_with(b"heic\0\0\0\0mif1heic", Some(u64::MAX));
+    assert!(is_heif(&data));
+    assert!(write_exif(&data, TIFF).is_err());
+}
+
+#[test]
+fn truncated_heif() {
+    let data = heic();
+    let truncated = &data[..data.len() - 20];
+    assert!(write_exif(truncated, TIFF).is_err());
+    assert!(read_exif(&data[..30]).is_err());
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::*;
+use exif_edit::file_type::FileType;
+use exif_edit::icc::{self, write_changes, IccAction, IccProfile, ProfileFamily, SRGB_PROFILE};
+
+// descタグだけを持つプロファイル
+fn profile_with_desc(tag_count: u32, desc: &[u8]) -> Vec<u8> {
+    let mut data = vec![0u8; 128];
+    data[8] = 4;
+    data[9] = 0x30;
+    data[12..16].copy_from_slice(b"mntr");
+    data[16..20].copy_from_slice(b"RGB ");
+    data[20..24].copy_from_slice(b"XYZ ");
+    data[36..40].copy_from_slice(b"acsp");
+    data.extend_from_slice(&tag_count.to_be_bytes());
+    data.extend_from_slice(b"desc");
+    data.extend_from_slice(&(128 + 4 + 12u32).to_be_bytes());
+    data.extend_from_slice(&(desc.len() as u32).to_be_bytes());
+    data.extend_from_slice(desc);
+    let size = data.len() as u32;
+    data[0..4].copy_from_slice(&size.to_be_bytes());
+    data
+}
+
+fn mluc(records: u32, record_size: u32, text: &str) -> Vec<u8> {
+    let utf16 = text.encode_utf16().flat_map(|c| c.to_be_bytes()).collect::<Vec<u8>>();
+    let mut tag = b"mluc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&records.to_be_bytes());
+    tag.extend_from_slice(&record_size.to_be_bytes());
+    tag.extend_from_slice(b"enUS");
+    tag.extend_from_slice(&(utf16.len() as u32).to_be_bytes());
+    tag.extend_from_slice(&28u32.to_be_bytes());
+    tag.extend_from_slice(&utf16);
+    tag
+}
+
+#[test]
+fn parse_srgb() {
+    let profile = IccProfile::parse(SRGB_PROFILE).expect("parse");
+    assert_eq!(profile.size, SRGB_PROFILE.len());
+    assert_eq!(profile.color_space, "RGB");
+    assert_eq!(profile.family(), ProfileFamily::Srgb);
+}
+
+#[test]
+fn parse_mluc() {
+    let profile = IccProfile::parse(&profile_with_desc(1, &mluc(1, 12, "Display P3"))).expect("parse");
+    assert_eq!(profile.version, "4.3.0");
+    assert_eq!(profile.description.as_deref(), Some("Display P3"));
+    assert_eq!(profile.family(), ProfileFamily::DisplayP3);
+}
+
+#[test]
+fn parse_truncated() {
+    // どこで切れてもパニックしない
+    for len in 0..SRGB_PROFILE.len() {
+        assert!(IccProfile::parse(&SRGB_PROFILE[..len]).is_err(), "len={}", len);
+    }
+}
+
+#[test]
+fn parse_invalid_size() {
+    // ヘッダのサイズがヘッダより短い
+    for size in [0u32, 8, 40, 131] {
+        let mut data = SRGB_PROFILE.to_vec();
+        data[0..4].copy_from_slice(&size.to_be_bytes());
+        assert!(IccProfile::parse(&data).is_err(), "size={}", size);
+    }
+}
+
+#[test]
+fn parse_huge_counts() {
+    // タグ数やレコード数が極端に大きくてもパニックしない
+    let profile = IccProfile::parse(&profile_with_desc(u32::MAX, &mluc(1, 12, "sRGB"))).expect("parse");
+    assert_eq!(profile.description.as_deref(), Some("sRGB"));
+
+    for (records, record_size) in [(u32::MAX, 12), (u32::MAX, u32::MAX), (2, u32::MAX), (1, 0)] {
+        let profile = IccProfile::parse(&profile_with_desc(1, &mluc(records, record_size, "sRGB"))).expect("parse");
+        assert!(profile.description.is_none() || profile.description.as_deref() == Some("sRGB"));
+    }
+
+    // タグのオフセットと長さがデータの外を指す
+    let mut data = profile_with_desc(1, &mluc(1, 12, "sRGB"));
+    data[136..140].copy_from_slice(&u32::MAX.to_be_bytes());
+    data[140..144].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert_eq!(IccProfile::parse(&data).expect("parse").description, None);
+}
+
+#[test]
+fn replace_roundtrip() {
+    let original = jpeg(&[jfif_segment()]);
+    assert!(icc::read(FileType::JPEG, &original).is_none());
+
+    let replaced = write_changes(FileType::JPEG, original.clone(), IccAction::ReplaceSrgb).expect("replace");
+    assert_eq!(icc::read(FileType::JPEG, &replaced), Some(Ok(SRGB_PROFILE.to_vec())));
+    assert_eq!(scan_data(&replaced), SCAN_DATA);
+
+    // 置き換えても1つだけ
+    let twice = write_changes(FileType::JPEG, replaced.clone(), IccAction::ReplaceSrgb).expect("replace");
+    assert_eq!(twice, replaced);
+
+    assert_eq!(write_changes(FileType::JPEG, replaced.clone(), IccAction::Keep).expect("keep"), replaced);
+    assert_eq!(write_changes(FileType::JPEG, replaced, IccAction::Strip).expect("strip"), original);
+}
+
+#[test]
+fn large_profile_chunks() {
+    // 複数のAPP2に分割して書き、つなげて読み戻せる
+    let profile = (0..150_000u32).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let written = icc::write(FileType::JPEG, &jpeg(&[jfif_segment()]), Some(&profile)).expect("write");
+    assert_eq!(written.windows(12).filter(|w| *w == b"ICC_PROFILE\0").count(), 3);
+    assert_eq!(icc::read(FileType::JPEG, &written), Some(Ok(profile)));
+    assert_eq!(scan_data(&written), SCAN_DATA);
+}
+
+#[test]
+fn missing_chunk() {
+    let mut chunk = b"ICC_PROFILE\0".to_vec();
+    chunk.extend_from_slice(&[1, 2]);
+    chunk.extend_from_slice(&SRGB_PROFILE[..100]);
+    let bytes = jpeg(&[jpeg_segment(0xE2, &chunk)]);
+    assert!(matches!(icc::read(FileType::JPEG, &bytes), Some(Err(_))));
+}
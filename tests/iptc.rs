@@ -0,0 +1,150 @@
+mod common;
+
+use common::*;
+use exif_edit::file_type::FileType;
+use exif_edit::iptc::{self, find_field, write_changes, IptcData, KEYWORDS};
+use exif_edit::jpeg::segments;
+
+fn resource(id: u16, data: &[u8]) -> Vec<u8> {
+    let mut ret = b"8BIM".to_vec();
+    ret.extend_from_slice(&id.to_be_bytes());
+    ret.extend_from_slice(&[0, 0]);
+    ret.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    ret.extend_from_slice(data);
+    if data.len() & 1 == 1 {
+        ret.push(0);
+    }
+    ret
+}
+
+fn app13(irb: &[u8]) -> Vec<u8> {
+    let mut payload = b"Photoshop 3.0\0".to_vec();
+    payload.extend_from_slice(irb);
+    jpeg_segment(0xED, &payload)
+}
+
+fn dataset(record: u8, dataset: u8, data: &[u8]) -> Vec<u8> {
+    let mut ret = vec![0x1C, record, dataset];
+    ret.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    ret.extend_from_slice(data);
+    ret
+}
+
+fn values(data: &IptcData, name: &str) -> Vec<String> {
+    data.get(&find_field(name).unwrap())
+}
+
+fn read(bytes: &[u8]) -> IptcData {
+    iptc::read(FileType::JPEG, bytes).unwrap().unwrap()
+}
+
+#[test]
+fn add_to_jpeg_without_app13() {
+    let data = jpeg(&[jfif_segment(), jpeg_segment(0xFE, b"comment")]);
+    assert!(iptc::read(FileType::JPEG, &data).is_none());
+
+    let mut iptc = IptcData::default();
+    iptc.set(&find_field("ObjectName").unwrap(), &["夕焼け".to_string()]).unwrap();
+    iptc.set(&KEYWORDS, &["空".to_string(), "sunset".to_string()]).unwrap();
+    let written = iptc::write(FileType::JPEG, &data, Some(&iptc)).unwrap();
+
+    // RecordVersion（2:00）を補って書き出す
+    let reread = read(&written);
+    assert_eq!(reread.datasets[0].dataset, 0);
+    assert_eq!(reread.datasets[1..], iptc.datasets[..]);
+    assert_eq!(values(&reread, "Keywords"), vec!["空", "sunset"]);
+    // APP0の直後に置き、画像データには触れない
+    let app0_end = 2 + jfif_segment().len();
+    assert_eq!(&written[app0_end..app0_end + 2], &[0xFF, 0xED]);
+    assert_eq!(scan_data(&written), SCAN_DATA);
+}
+
+#[test]
+fn rewrite_keeps_other_resources() {
+    let resolution = resource(0x03ED, &[0, 72, 0, 0, 0, 1, 0, 1, 0, 72, 0, 0, 0, 1, 0, 1]);
+    // 文字コードの指定がないWindows-1252のIPTCと、そのダイジェスト
+    let iim = [dataset(2, 0, &[0, 4]), dataset(2, 5, b"Caf\xE9")].concat();
+    let irb = [resolution.clone(), resource(0x0404, &iim), resource(0x0425, &[0xAB; 16])].concat();
+    let data = jpeg(&[jfif_segment(), app13(&irb), jpeg_segment(0xFE, b"comment")]);
+
+    let mut iptc = read(&data);
+    assert_eq!(values(&iptc, "ObjectName"), vec!["Café"]);
+    iptc.set(&find_field("City").unwrap(), &["東京".to_string()]).unwrap();
+    let written = iptc::write(FileType::JPEG, &data, Some(&iptc)).unwrap();
+
+    let reread = read(&written);
+    assert_eq!(values(&reread, "ObjectName"), vec!["Café"]);
+    assert_eq!(values(&reread, "City"), vec!["東京"]);
+    assert!(written.windows(resolution.len()).any(|w| w == resolution));
+    assert!(!written.windows(16).any(|w| w == [0xAB; 16]));
+    // UTF-8であることを1:90に記録する
+    assert!(written.windows(8).any(|w| w == dataset(1, 90, b"\x1B%G")));
+    // APP13は元の位置（APP0とCOMの間）に置く
+    let app0_end = 2 + jfif_segment().len();
+    assert_eq!(&written[app0_end..app0_end + 2], &[0xFF, 0xED]);
+    assert_eq!(scan_data(&written), SCAN_DATA);
+}
+
+#[test]
+fn remove_iptc() {
+    let iim = dataset(2, 5, b"title");
+    let resolution = resource(0x03ED, &[0; 16]);
+    let only_iptc = jpeg(&[jfif_segment(), app13(&resource(0x0404, &iim))]);
+    let with_other = jpeg(&[jfif_segment(), app13(&[resource(0x0404, &iim), resolution.clone()].concat())]);
+
+    // 他のリソースがなければAPP13ごと削除する
+    assert_eq!(iptc::write(FileType::JPEG, &only_iptc, None).unwrap(), jpeg(&[jfif_segment()]));
+    assert_eq!(iptc::write(FileType::JPEG, &with_other, None).unwrap(), jpeg(&[jfif_segment(), app13(&resolution)]));
+}
+
+#[test]
+fn split_app13() {
+    let irb = resource(0x0404, &[dataset(2, 0, &[0, 4]), dataset(2, 120, &[b'a'; 40])].concat());
+    let (first, second) = irb.split_at(20);
+    let data = jpeg(&[app13(first), app13(second)]);
+    assert_eq!(values(&read(&data), "Caption-Abstract"), vec!["a".repeat(40)]);
+
+    // 書き直すと1つのAPP13にまとめる
+    let written = iptc::write(FileType::JPEG, &data, Some(&read(&data))).unwrap();
+    assert_eq!(written.windows(2).filter(|w| *w == [0xFF, 0xED]).count(), 1);
+    assert_eq!(read(&written), read(&data));
+}
+
+#[test]
+fn large_irb_spans_two_app13() {
+    // 1つのAPP13（約64KB）に収まらないサムネイルのリソース
+    let thumbnail = resource(0x0409, &vec![0x5A; 70_000]);
+    let (first, second) = thumbnail.split_at(60_000);
+    let data = jpeg(&[jfif_segment(), app13(first), app13(second)]);
+
+    let mut iptc = IptcData::default();
+    iptc.set(&KEYWORDS, &["空".to_string()]).unwrap();
+    let written = iptc::write(FileType::JPEG, &data, Some(&iptc)).unwrap();
+    assert_eq!(values(&read(&written), "Keywords"), vec!["空"]);
+
+    // 2つのAPP13に分けて書き、つなげると元のリソースがそのまま残っている
+    let payloads = segments(&written).unwrap().into_iter()
+        .filter(|segment| segment.marker == 0xED)
+        .map(|segment| segment.payload(&written).strip_prefix(b"Photoshop 3.0\0").unwrap().to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    assert_eq!(payloads.len(), 2);
+    assert!(payloads.concat().windows(thumbnail.len()).any(|w| w == thumbnail));
+    assert_eq!(scan_data(&written), SCAN_DATA);
+}
+
+#[test]
+fn unchanged_keeps_bytes() {
+    let iim = [dataset(2, 0, &[0, 2]), dataset(2, 5, b"title")].concat();
+    let data = jpeg(&[app13(&[resource(0x0404, &iim), resource(0x0425, &[1; 16])].concat())]);
+    let original = read(&data);
+    assert_eq!(write_changes(FileType::JPEG, data.clone(), Some(&original), Some(&original)).unwrap(), data);
+}
+
+#[test]
+fn unsupported_file_type() {
+    let data = png(&[]);
+    let mut iptc = IptcData::default();
+    iptc.set(&KEYWORDS, &["a".to_string()]).unwrap();
+    assert!(iptc::write(FileType::PNG, &data, Some(&iptc)).is_err());
+    assert_eq!(iptc::write(FileType::PNG, &data, None).unwrap(), data);
+}
@@ -0,0 +1,145 @@
+use exif_edit::exif::makernote::{decode, read_ifd, ByteOrder, IfdLayout, MakerNote, OffsetBase, Sensitivity};
+
+// (タグ, 型, 個数, 値またはオフセット)のエントリを並べたビッグエンディアンのIFD
+fn ifd_be(entries: &[(u16, u16, u32, [u8; 4])]) -> Vec<u8> {
+    let mut ret = (entries.len() as u16).to_be_bytes().to_vec();
+    for (tag, format, count, value) in entries.iter() {
+        ret.extend_from_slice(&tag.to_be_bytes());
+        ret.extend_from_slice(&format.to_be_bytes());
+        ret.extend_from_slice(&count.to_be_bytes());
+        ret.extend_from_slice(value);
+    }
+    ret.extend_from_slice(&[0, 0, 0, 0]);
+    ret
+}
+
+fn ifd_le(entries: &[(u16, u16, u32, [u8; 4])]) -> Vec<u8> {
+    let mut ret = (entries.len() as u16).to_le_bytes().to_vec();
+    for (tag, format, count, value) in entries.iter() {
+        ret.extend_from_slice(&tag.to_le_bytes());
+        ret.extend_from_slice(&format.to_le_bytes());
+        ret.extend_from_slice(&count.to_le_bytes());
+        ret.extend_from_slice(value);
+    }
+    ret.extend_from_slice(&[0, 0, 0, 0]);
+    ret
+}
+
+// "Nikon\0"、バージョン、TIFFヘッダ、IFD、シリアル番号の文字列
+fn nikon() -> Vec<u8> {
+    let mut data = b"Nikon\0\x02\x10\0\0MM\0\x2A\0\0\0\x08".to_vec();
+    // シリアル番号はTIFFヘッダから8 + 2 + 12 * 2 + 4 = 38バイト目
+    data.extend_from_slice(&ifd_be(&[
+        (0x0002, 3, 2, [0, 0, 0x01, 0x90]),
+        (0x001D, 2, 8, [0, 0, 0, 38]),
+    ]));
+    data.extend_from_slice(b"1234567\0");
+    data
+}
+
+fn field<'a>(note: &'a MakerNote, name: &str) -> &'a str {
+    &note.fields.iter().find(|f| f.name == name).unwrap_or_else(|| panic!("{}がありません", name)).value
+}
+
+#[test]
+fn nikon_type3() {
+    let note = decode("NIKON CORPORATION", &nikon()).expect("detect").expect("parse");
+    assert_eq!(note.vendor, "Nikon");
+    assert_eq!(field(&note, "ISO"), "0 400");
+    assert_eq!(field(&note, "SerialNumber"), "1234567");
+    let serial = note.fields.iter().find(|f| f.name == "SerialNumber").unwrap();
+    assert_eq!(serial.sensitivity, Some(Sensitivity::Device));
+}
+
+#[test]
+fn nikon_out_of_range_ifd() {
+    // IFDのオフセットが極端に大きい
+    for offset in [0x0000_0100u32, 0x7FFF_FFFF, u32::MAX] {
+        let mut data = nikon();
+        data[14..18].copy_from_slice(&offset.to_be_bytes());
+        assert!(decode("NIKON", &data).expect("detect").is_err(), "offset={:#X}", offset);
+    }
+}
+
+#[test]
+fn value_out_of_range() {
+    // 値のオフセットがMakerNoteの外を指していても、ほかの項目は読める
+    for offset in [0x0000_1000u32, u32::MAX - 4, u32::MAX] {
+        let mut data = nikon();
+        let at = 18 + 2 + 12 + 8;
+        data[at..at + 4].copy_from_slice(&offset.to_be_bytes());
+        let note = decode("NIKON", &data).expect("detect").expect("parse");
+        assert_eq!(field(&note, "ISO"), "0 400");
+        assert_eq!(field(&note, "SerialNumber"), "（読み取れません）");
+    }
+}
+
+#[test]
+fn huge_count() {
+    // 個数×型の大きさがあふれるエントリは飛ばす
+    let mut data = b"Nikon\0\x02\x10\0\0MM\0\x2A\0\0\0\x08".to_vec();
+    data.extend_from_slice(&ifd_be(&[
+        (0x0002, 3, 2, [0, 0, 0x01, 0x90]),
+        (0x0098, 12, u32::MAX, [0, 0, 0, 38]),
+    ]));
+    let note = decode("NIKON", &data).expect("detect").expect("parse");
+    assert_eq!(field(&note, "ISO"), "0 400");
+}
+
+#[test]
+fn fujifilm() {
+    let mut data = b"FUJIFILM\x0C\0\0\0".to_vec();
+    data.extend_from_slice(&ifd_le(&[
+        (0x0010, 2, 6, [42, 0, 0, 0]),
+        (0x1001, 3, 1, [3, 0, 0, 0]),
+    ]));
+    data.extend_from_slice(b"ABCDE\0");
+    let note = decode("FUJIFILM", &data).expect("detect").expect("parse");
+    assert_eq!(field(&note, "InternalSerialNumber"), "ABCDE");
+    assert_eq!(field(&note, "Sharpness"), "3");
+
+    for offset in [0x100u32, u32::MAX] {
+        let mut data = data.clone();
+        data[8..12].copy_from_slice(&offset.to_le_bytes());
+        assert!(decode("FUJIFILM", &data).expect("detect").is_err(), "offset={:#X}", offset);
+    }
+}
+
+#[test]
+fn apple_identifiers() {
+    let mut data = b"Apple iOS\0\0\x01MM".to_vec();
+    data.extend_from_slice(&ifd_be(&[
+        (0x000B, 2, 4, *b"abc\0"),
+        (0x0011, 2, 4, *b"def\0"),
+        (0x002B, 2, 4, *b"ghi\0"),
+    ]));
+    let note = decode("Apple", &data).expect("detect").expect("parse");
+    for name in ["BurstUUID", "ContentIdentifier", "PhotoIdentifier"] {
+        let field = note.fields.iter().find(|f| f.name == name).expect(name);
+        assert_eq!(field.sensitivity, Some(Sensitivity::Device), "{}", name);
+    }
+}
+
+#[test]
+fn truncated() {
+    // どこで切れてもパニックしない
+    let data = nikon();
+    for len in 0..data.len() {
+        let _ = decode("NIKON", &data[..len]);
+    }
+}
+
+#[test]
+fn read_ifd_out_of_range() {
+    let data = ifd_be(&[(0x0001, 3, 1, [0, 1, 0, 0])]);
+    for ifd_offset in [data.len(), usize::MAX - 1, usize::MAX] {
+        let layout = IfdLayout { ifd_offset, order: ByteOrder::Big, base: OffsetBase::At(0) };
+        assert_eq!(read_ifd(&data, &layout), None, "ifd_offset={}", ifd_offset);
+    }
+    for base in [OffsetBase::At(usize::MAX), OffsetBase::Tiff] {
+        let layout = IfdLayout { ifd_offset: 0, order: ByteOrder::Big, base };
+        assert_eq!(read_ifd(&data, &layout).map(|e| e.len()), Some(1));
+    }
+    assert_eq!(ByteOrder::guess(&data, usize::MAX), None);
+    assert_eq!(ByteOrder::guess(&data, 0), Some(ByteOrder::Big));
+}
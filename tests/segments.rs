@@ -0,0 +1,106 @@
+mod common;
+
+use common::*;
+use exif_edit::jpeg::{inspect, mpf_conflicts, replace_segments, strip_segments, SegmentKind};
+
+fn offsets_of(data: &[u8], kind: SegmentKind) -> Vec<usize> {
+    inspect(data).unwrap().iter().filter(|s| s.kind == kind).map(|s| s.offset).collect()
+}
+
+#[test]
+fn strip_keeps_scan_data() {
+    let comment = jpeg_segment(0xFE, b"hello");
+    let app10 = jpeg_segment(0xEA, b"unknown");
+    let data = jpeg(&[jfif_segment(), comment, app10]);
+
+    let mut offsets = offsets_of(&data, SegmentKind::Comment);
+    offsets.extend(offsets_of(&data, SegmentKind::OtherApp(10)));
+    let stripped = strip_segments(&data, &offsets).unwrap();
+
+    assert_eq!(scan_data(&stripped), SCAN_DATA);
+    assert_eq!(stripped, jpeg(&[jfif_segment()]));
+    assert!(offsets_of(&stripped, SegmentKind::Comment).is_empty());
+}
+
+#[test]
+fn strip_trailer() {
+    let mut data = jpeg(&[jfif_segment()]);
+    data.extend_from_slice(b"appended");
+
+    let stripped = strip_segments(&data, &offsets_of(&data, SegmentKind::Trailer)).unwrap();
+    assert_eq!(stripped, jpeg(&[jfif_segment()]));
+    assert_eq!(scan_data(&stripped), SCAN_DATA);
+}
+
+#[test]
+fn scan_data_is_not_removable() {
+    let data = jpeg(&[jfif_segment()]);
+    let offsets = offsets_of(&data, SegmentKind::EntropyData);
+    assert_eq!(offsets.len(), 1);
+    assert!(strip_segments(&data, &offsets).is_err());
+}
+
+#[test]
+fn stray_bytes_between_segments() {
+    let mut app = jfif_segment();
+    app.extend_from_slice(&[0x00, 0x12, 0x34]);
+    let data = jpeg(&[app, jpeg_segment(0xFE, b"comment")]);
+
+    let segments = inspect(&data).unwrap();
+    let stray = segments.iter().find(|s| s.kind == SegmentKind::Stray).unwrap();
+    assert_eq!(stray.length, 3);
+    assert!(segments.iter().any(|s| s.kind == SegmentKind::Comment));
+    assert!(segments.iter().any(|s| s.kind == SegmentKind::EntropyData));
+
+    let stripped = strip_segments(&data, &[stray.offset]).unwrap();
+    assert_eq!(stripped, jpeg(&[jfif_segment(), jpeg_segment(0xFE, b"comment")]));
+}
+
+#[test]
+fn keep_mpf_offsets() {
+    let mpf = jpeg_segment(0xE2, b"MPF\0II*\0\x08\0\0\0");
+    let second = jpeg(&[]);
+    let mut data = jpeg(&[jpeg_segment(0xFE, b"before"), mpf, jpeg_segment(0xFE, b"after")]);
+    data.extend_from_slice(&second);
+
+    let segments = inspect(&data).unwrap();
+    let comments = offsets_of(&data, SegmentKind::Comment);
+    let mpf = offsets_of(&data, SegmentKind::Mpf);
+    let trailer = offsets_of(&data, SegmentKind::Trailer);
+
+    // MPFより前のセグメントは、MPFからの相対位置を変えないので削除できる
+    assert!(mpf_conflicts(&segments, &comments[..1]).is_empty());
+    let stripped = strip_segments(&data, &comments[..1]).unwrap();
+    assert!(stripped.ends_with(&second));
+    assert_eq!(scan_data(&stripped[..stripped.len() - second.len()]), SCAN_DATA);
+
+    // MPFより後ろのセグメントやEOI以降の画像は、MPFを残したままでは削除できない
+    assert_eq!(mpf_conflicts(&segments, &comments[1..]), comments[1..].to_vec());
+    assert!(strip_segments(&data, &comments[1..]).is_err());
+    assert!(strip_segments(&data, &trailer).is_err());
+
+    // MPFも合わせて削除するなら構わない
+    let mut offsets = mpf.clone();
+    offsets.extend(trailer);
+    assert!(mpf_conflicts(&segments, &offsets).is_empty());
+    let stripped = strip_segments(&data, &offsets).unwrap();
+    assert_eq!(stripped, jpeg(&[jpeg_segment(0xFE, b"before"), jpeg_segment(0xFE, b"after")]));
+}
+
+#[test]
+fn replace_at_first_removed_segment() {
+    let is_comment = |marker: u8, _: &[u8]| marker == 0xFE;
+    let new = vec![jpeg_segment(0xFE, b"new")];
+    let data = jpeg(&[jfif_segment(), jpeg_segment(0xFE, b"a"), jpeg_segment(0xEA, b"app10"), jpeg_segment(0xFE, b"b")]);
+    let replaced = replace_segments(&data, is_comment, &new).unwrap();
+    assert_eq!(replaced, jpeg(&[jfif_segment(), jpeg_segment(0xFE, b"new"), jpeg_segment(0xEA, b"app10")]));
+
+    // 取り除くものがなければ、先頭のAPPnの並びの直後に置く
+    let data = jpeg(&[jfif_segment(), jpeg_segment(0xEA, b"app10")]);
+    let replaced = replace_segments(&data, is_comment, &new).unwrap();
+    assert_eq!(replaced, jpeg(&[jfif_segment(), jpeg_segment(0xEA, b"app10"), jpeg_segment(0xFE, b"new")]));
+    assert_eq!(scan_data(&replaced), SCAN_DATA);
+
+    // 画像データまで読めなければ書き換えない
+    assert!(replace_segments(&data[..data.len() - 40], is_comment, &new).is_err());
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::*;
+use exif_edit::file_type::FileType;
+use exif_edit::xmp::container::{read, write, JPEG_XMP_HEADER};
+
+const PACKET: &str = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"/></x:xmpmeta>";
+
+fn itxt(compressed: u8, text: &[u8]) -> Vec<u8> {
+    let mut data = b"XML:com.adobe.xmp\0".to_vec();
+    data.extend_from_slice(&[compressed, 0, 0, 0]);
+    data.extend_from_slice(text);
+    png_chunk(b"iTXt", &data)
+}
+
+#[test]
+fn jpeg_roundtrip() {
+    let original = jpeg(&[jfif_segment()]);
+    assert_eq!(read(FileType::JPEG, &original), None);
+
+    let written = write(FileType::JPEG, &original, Some(PACKET)).expect("write");
+    assert_eq!(read(FileType::JPEG, &written).as_deref(), Some(PACKET));
+    // JFIFの直後に置かれる
+    let app1 = 2 + jfif_segment().len();
+    assert_eq!(&written[app1..app1 + 2], &[0xFF, 0xE1]);
+    assert!(written[app1 + 4..].starts_with(JPEG_XMP_HEADER));
+    assert_eq!(scan_data(&written), SCAN_DATA);
+
+    // 書き直しても1つだけ
+    let rewritten = write(FileType::JPEG, &written, Some("<x:xmpmeta/>")).expect("rewrite");
+    assert_eq!(read(FileType::JPEG, &rewritten).as_deref(), Some("<x:xmpmeta/>"));
+    assert_eq!(rewritten.windows(JPEG_XMP_HEADER.len()).filter(|w| *w == JPEG_XMP_HEADER).count(), 1);
+
+    assert_eq!(write(FileType::JPEG, &written, None).expect("remove"), original);
+}
+
+#[test]
+fn jpeg_too_large() {
+    let original = jpeg(&[jfif_segment()]);
+    let packet = "a".repeat(70_000);
+    assert!(write(FileType::JPEG, &original, Some(&packet)).is_err());
+}
+
+#[test]
+fn jpeg_truncated() {
+    let mut xmp = JPEG_XMP_HEADER.to_vec();
+    xmp.extend_from_slice(PACKET.as_bytes());
+    let original = jpeg(&[jfif_segment(), jpeg_segment(0xE1, &xmp)]);
+    for len in [0, 1, 3, 10, 2 + jfif_segment().len() + 10] {
+        let truncated = &original[..len];
+        assert_eq!(read(FileType::JPEG, truncated), None, "len={}", len);
+        assert!(write(FileType::JPEG, truncated, Some(PACKET)).is_err(), "len={}", len);
+    }
+}
+
+#[test]
+fn png_roundtrip() {
+    let original = png(&[]);
+    assert_eq!(read(FileType::PNG, &original), None);
+
+    let written = write(FileType::PNG, &original, Some(PACKET)).expect("write");
+    assert_eq!(read(FileType::PNG, &written).as_deref(), Some(PACKET));
+    // IDATより前に置かれる
+    let itxt_at = written.windows(4).position(|w| w == b"iTXt").expect("iTXt");
+    let idat_at = written.windows(4).position(|w| w == b"IDAT").expect("IDAT");
+    assert!(itxt_at < idat_at);
+    // CRCが正しい
+    let length = u32::from_be_bytes([written[itxt_at - 4], written[itxt_at - 3], written[itxt_at - 2], written[itxt_at - 1]]) as usize;
+    let crc = &written[itxt_at + 4 + length..itxt_at + 8 + length];
+    assert_eq!(crc, crc32(&written[itxt_at..itxt_at + 4 + length]).to_be_bytes());
+
+    assert_eq!(write(FileType::PNG, &written, None).expect("remove"), original);
+}
+
+#[test]
+fn png_compressed_itxt() {
+    // 圧縮されたXMPは読めないが、書き込むときは置き換える
+    let original = png(&[itxt(1, &[0x78, 0x9C, 0x03, 0x00])]);
+    assert_eq!(read(FileType::PNG, &original), None);
+    let written = write(FileType::PNG, &original, Some(PACKET)).expect("write");
+    assert_eq!(read(FileType::PNG, &written).as_deref(), Some(PACKET));
+    assert_eq!(written.windows(4).filter(|w| *w == b"iTXt").count(), 1);
+}
+
+#[test]
+fn png_malformed_itxt() {
+    // キーワードの後ろが途中で切れているiTXt
+    for data in [&b"XML:com.adobe.xmp\0"[..], b"XML:com.adobe.xmp\0\0", b"XML:com.adobe.xmp\0\0\0", b"XML:com.adobe.xmp\0\0\0\0"] {
+        let original = png(&[png_chunk(b"iTXt", data)]);
+        assert_eq!(read(FileType::PNG, &original), None, "{:?}", data);
+        let written = write(FileType::PNG, &original, Some(PACKET)).expect("write");
+        assert_eq!(read(FileType::PNG, &written).as_deref(), Some(PACKET));
+    }
+}
+
+#[test]
+fn png_truncated() {
+    let original = png(&[itxt(0, PACKET.as_bytes())]);
+    assert_eq!(read(FileType::PNG, &original).as_deref(), Some(PACKET));
+    for len in [0, 4, 8, 20, 40, original.len() - 1] {
+        let truncated = &original[..len];
+        assert_eq!(read(FileType::PNG, truncated), None, "len={}", len);
+        assert!(write(FileType::PNG, truncated, Some(PACKET)).is_err(), "len={}", len);
+    }
+
+    // チャンクの長さが極端に大きい
+    let mut huge = original.clone();
+    huge[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert_eq!(read(FileType::PNG, &huge), None);
+    assert!(write(FileType::PNG, &huge, None).is_err());
+}
+
+#[test]
+fn webp_roundtrip() {
+    let original = webp(&[vp8l_chunk(640, 480)]);
+    assert_eq!(read(FileType::WEBP, &original), None);
+
+    let written = write(FileType::WEBP, &original, Some(PACKET)).expect("write");
+    assert_eq!(read(FileType::WEBP, &written).as_deref(), Some(PACKET));
+    // VP8Xが追加され、XMPのフラグと画像の大きさが入る
+    assert_eq!(&written[12..16], b"VP8X");
+    assert_eq!(written[20] & 0x04, 0x04);
+    assert_eq!(&written[24..30], &[0x7F, 0x02, 0x00, 0xDF, 0x01, 0x00]);
+    // RIFFの大きさ
+    assert_eq!(u32::from_le_bytes([written[4], written[5], written[6], written[7]]) as usize, written.len() - 8);
+
+    let removed = write(FileType::WEBP, &written, None).expect("remove");
+    assert_eq!(read(FileType::WEBP, &removed), None);
+    assert_eq!(removed[20] & 0x04, 0);
+    assert_eq!(u32::from_le_bytes([removed[4], removed[5], removed[6], removed[7]]) as usize, removed.len() - 8);
+}
+
+#[test]
+fn webp_odd_length_packet() {
+    let original = webp(&[vp8l_chunk(1, 1)]);
+    let packet = format!("{} ", PACKET);
+    assert_ne!(packet.len() % 2, PACKET.len() % 2);
+    for packet in [PACKET, packet.as_str()] {
+        let written = write(FileType::WEBP, &original, Some(packet)).expect("write");
+        assert_eq!(written.len() % 2, 0);
+        assert_eq!(read(FileType::WEBP, &written).as_deref(), Some(packet));
+    }
+}
+
+#[test]
+fn webp_truncated() {
+    let original = webp(&[vp8l_chunk(16, 16), webp_chunk(b"XMP ", PACKET.as_bytes())]);
+    assert_eq!(read(FileType::WEBP, &original).as_deref(), Some(PACKET));
+    for len in [0, 4, 12, 16, 24, original.len() - 1] {
+        let truncated = &original[..len];
+        assert_eq!(read(FileType::WEBP, truncated), None, "len={}", len);
+        assert!(write(FileType::WEBP, truncated, Some(PACKET)).is_err(), "len={}", len);
+    }
+
+    let mut huge = original.clone();
+    huge[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(read(FileType::WEBP, &huge), None);
+    assert!(write(FileType::WEBP, &huge, None).is_err());
+}
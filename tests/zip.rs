@@ -0,0 +1,60 @@
+use chrono::NaiveDate;
+use exif_edit::zip::{crc32, ZipError, ZipWriter};
+
+fn writer() -> ZipWriter {
+    ZipWriter::new(NaiveDate::from_ymd_opt(2024, 5, 6).unwrap().and_hms_opt(7, 8, 10).unwrap())
+}
+
+fn u16_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+#[test]
+fn stored_entries() {
+    let mut zip = writer();
+    zip.add_file("a.jpg", b"first").unwrap();
+    zip.add_file("写真.jpg", b"second").unwrap();
+    let data = zip.finish().unwrap();
+
+    // 末尾の中央ディレクトリ終端から、各ファイルのローカルヘッダーをたどる
+    let end = data.len() - 22;
+    assert_eq!(u32_at(&data, end), 0x06054b50);
+    assert_eq!(u16_at(&data, end + 10), 2);
+    let mut central = u32_at(&data, end + 16) as usize;
+    for (name, body) in [("a.jpg", &b"first"[..]), ("写真.jpg", &b"second"[..])] {
+        assert_eq!(u32_at(&data, central), 0x02014b50);
+        assert_eq!(u32_at(&data, central + 16), crc32(body));
+        let name_len = u16_at(&data, central + 28) as usize;
+        assert_eq!(&data[central + 46..central + 46 + name_len], name.as_bytes());
+
+        let local = u32_at(&data, central + 42) as usize;
+        assert_eq!(u32_at(&data, local), 0x04034b50);
+        let start = local + 30 + u16_at(&data, local + 26) as usize;
+        assert_eq!(&data[start..start + body.len()], body);
+        central += 46 + name_len;
+    }
+}
+
+#[test]
+fn rejected_file_keeps_others() {
+    let mut zip = writer();
+    zip.add_file("a.jpg", b"first").unwrap();
+    assert_eq!(zip.add_file(&"x".repeat(70000), b"second"), Err(ZipError::NameTooLong));
+    zip.add_file("b.jpg", b"third").unwrap();
+    let data = zip.finish().unwrap();
+    assert_eq!(u16_at(&data, data.len() - 12), 2);
+}
+
+#[test]
+fn too_many_files() {
+    let mut zip = writer();
+    for i in 0..u16::MAX {
+        zip.add_file(&i.to_string(), b"").unwrap();
+    }
+    assert_eq!(zip.add_file("last", b""), Err(ZipError::TooManyFiles));
+    assert!(zip.finish().is_ok());
+}